@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use gtk::gdk_pixbuf::{Colorspace, InterpType, Pixbuf, PixbufLoader};
+use gtk::prelude::*;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::errors::{DragonDisplayError, GlibSnafu, IOSnafu, OtherSnafu};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+/// Frames without a delay are shown for this long, like browsers do
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+const DISPOSE_BACKGROUND: u8 = 1;
+const DISPOSE_PREVIOUS: u8 = 2;
+const BLEND_OVER: u8 = 1;
+
+/// A chunk of a png file
+struct Chunk<'a> {
+    kind: [u8; 4],
+    data: &'a [u8],
+}
+
+/// The frame control chunk of an animated png
+struct FrameControl {
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    delay: Duration,
+    dispose: u8,
+    blend: u8,
+}
+
+/// Returns true if the file is an animated png. Gdk-pixbuf only shows the first frame of these
+pub fn is_animated(path: &Path) -> bool {
+    let Ok(data) = fs::read(path) else {
+        return false;
+    };
+    let Some(chunks) = chunks(&data) else {
+        return false;
+    };
+    // the animation control chunk has to come before the image data
+    chunks
+        .iter()
+        .take_while(|c| &c.kind != b"IDAT")
+        .any(|c| &c.kind == b"acTL")
+}
+
+/// Decode all frames of the animated png, every frame is returned as it is shown with the time
+/// it should be shown for
+pub fn frames(path: &Path) -> Result<Vec<(Pixbuf, Duration)>, DragonDisplayError> {
+    let data = fs::read(path).context(IOSnafu {
+        msg: format!("Could not read the image {}", path.display()),
+    })?;
+    let invalid = || OtherSnafu {
+        msg: format!("The animated png {} is invalid", path.display()),
+    };
+    let chunks = chunks(&data).with_context(invalid)?;
+    let header = chunks
+        .first()
+        .filter(|c| &c.kind == b"IHDR" && c.data.len() == 13)
+        .with_context(invalid)?;
+    let width = read_u32(header.data, 0).with_context(invalid)?;
+    let height = read_u32(header.data, 4).with_context(invalid)?;
+    // chunks such as the palette and transparency are shared by all frames
+    let shared: Vec<&Chunk> = chunks
+        .iter()
+        .take_while(|c| &c.kind != b"IDAT")
+        .filter(|c| !matches!(&c.kind, b"IHDR" | b"acTL" | b"fcTL"))
+        .collect();
+
+    let canvas =
+        Pixbuf::new(Colorspace::Rgb, true, 8, width as i32, height as i32).with_context(invalid)?;
+    canvas.fill(0);
+    let mut frames = Vec::new();
+    let mut control: Option<FrameControl> = None;
+    let mut image_data: Vec<&[u8]> = Vec::new();
+    for chunk in chunks.iter().skip(1) {
+        match &chunk.kind {
+            b"fcTL" | b"IEND" => {
+                if let Some(control) = control.take() {
+                    let frame = decode_frame(header.data, &shared, &control, &image_data)?;
+                    frames.push(draw_frame(&canvas, &frame, &control, frames.is_empty())?);
+                }
+                image_data.clear();
+                if &chunk.kind == b"fcTL" {
+                    control = Some(frame_control(chunk.data).with_context(invalid)?);
+                }
+            }
+            // image data without a frame control chunk before it is not part of the animation
+            b"IDAT" if control.is_some() => image_data.push(chunk.data),
+            b"fdAT" if chunk.data.len() > 4 => image_data.push(&chunk.data[4..]),
+            _ => (),
+        }
+    }
+    ensure!(!frames.is_empty(), invalid());
+    Ok(frames)
+}
+
+/// Decode the image data of a single frame by wrapping it in a png file of its own
+fn decode_frame(
+    header: &[u8],
+    shared: &[&Chunk],
+    control: &FrameControl,
+    image_data: &[&[u8]],
+) -> Result<Pixbuf, DragonDisplayError> {
+    let mut header = header.to_vec();
+    header[0..4].copy_from_slice(&control.width.to_be_bytes());
+    header[4..8].copy_from_slice(&control.height.to_be_bytes());
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    for chunk in shared {
+        write_chunk(&mut png, &chunk.kind, chunk.data);
+    }
+    for data in image_data {
+        write_chunk(&mut png, b"IDAT", data);
+    }
+    write_chunk(&mut png, b"IEND", &[]);
+
+    let loader = PixbufLoader::with_type("png").context(GlibSnafu {
+        msg: "Could not create a png loader".to_string(),
+    })?;
+    loader
+        .write(&png)
+        .and_then(|_| loader.close())
+        .context(GlibSnafu {
+            msg: "Could not decode a frame of the animated png".to_string(),
+        })?;
+    let pixbuf = loader.pixbuf().context(OtherSnafu {
+        msg: "Could not decode a frame of the animated png".to_string(),
+    })?;
+    Ok(pixbuf.add_alpha(false, 0, 0, 0).unwrap_or(pixbuf))
+}
+
+/// Draw the frame on the canvas and dispose of it as the frame control chunk describes. Returns
+/// a copy of the canvas as it should be shown
+fn draw_frame(
+    canvas: &Pixbuf,
+    frame: &Pixbuf,
+    control: &FrameControl,
+    first: bool,
+) -> Result<(Pixbuf, Duration), DragonDisplayError> {
+    let invalid = OtherSnafu {
+        msg: "A frame of the animated png is outside of the image".to_string(),
+    };
+    let (x, y) = (control.x as i32, control.y as i32);
+    let (width, height) = (frame.width(), frame.height());
+    if x + width > canvas.width() || y + height > canvas.height() {
+        return invalid.fail();
+    }
+    let region = canvas.new_subpixbuf(x, y, width, height);
+    let previous = match control.dispose {
+        DISPOSE_PREVIOUS if !first => region.copy(),
+        _ => None,
+    };
+    if control.blend == BLEND_OVER {
+        frame.composite(
+            canvas,
+            x,
+            y,
+            width,
+            height,
+            x as f64,
+            y as f64,
+            1.0,
+            1.0,
+            InterpType::Nearest,
+            255,
+        );
+    } else {
+        frame.copy_area(0, 0, width, height, canvas, x, y);
+    }
+    let shown = canvas.copy().context(OtherSnafu {
+        msg: "Could not copy a frame of the animated png".to_string(),
+    })?;
+    match (control.dispose, previous) {
+        (DISPOSE_PREVIOUS, Some(previous)) => previous.copy_area(0, 0, width, height, canvas, x, y),
+        // the first frame can not go back to a previous frame, so it is cleared instead
+        (DISPOSE_BACKGROUND, _) | (DISPOSE_PREVIOUS, None) => region.fill(0),
+        _ => (),
+    }
+    Ok((shown, control.delay))
+}
+
+/// Split the png file in its chunks, None if the file is not a png file
+fn chunks(data: &[u8]) -> Option<Vec<Chunk>> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    while position + 8 <= data.len() {
+        let length = read_u32(data, position)? as usize;
+        let kind: [u8; 4] = data[position + 4..position + 8].try_into().ok()?;
+        let start = position + 8;
+        let end = start.checked_add(length)?;
+        // the chunk is followed by a four byte checksum
+        if end + 4 > data.len() {
+            return None;
+        }
+        chunks.push(Chunk {
+            kind,
+            data: &data[start..end],
+        });
+        position = end + 4;
+        if &kind == b"IEND" {
+            break;
+        }
+    }
+    Some(chunks)
+}
+
+fn frame_control(data: &[u8]) -> Option<FrameControl> {
+    if data.len() < 26 {
+        return None;
+    }
+    let numerator = u16::from_be_bytes([data[20], data[21]]) as u64;
+    let denominator = match u16::from_be_bytes([data[22], data[23]]) {
+        0 => 100,
+        d => d as u64,
+    };
+    let delay = match Duration::from_millis(numerator * 1000 / denominator) {
+        Duration::ZERO => DEFAULT_DELAY,
+        delay => delay,
+    };
+    Some(FrameControl {
+        width: read_u32(data, 4)?,
+        height: read_u32(data, 8)?,
+        x: read_u32(data, 12)?,
+        y: read_u32(data, 16)?,
+        delay,
+        dispose: data[24],
+        blend: data[25],
+    })
+}
+
+fn read_u32(data: &[u8], position: usize) -> Option<u32> {
+    let bytes = data.get(position..position + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&[kind.as_slice(), data]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// The checksum that png uses for its chunks
+fn crc32(parts: &[&[u8]]) -> u32 {
+    static TABLE: [u32; 256] = crc_table();
+    let mut crc = 0xffff_ffffu32;
+    for byte in parts.iter().flat_map(|p| p.iter()) {
+        crc = TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xffff_ffff
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use gtk::glib;
+use gtk::graphene::{Matrix, Vec4};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+use crate::media::relative_path;
+
+/// Name of the file in the campaign data folder that holds the color grades of the media files
+const COLOR_GRADE_FILE: &str = "color_grades.toml";
+/// The weights of the red, green and blue channels in the brightness of a color
+const LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// The color filter of the display. Brightness is added to every channel, contrast and
+/// saturation are factors where 1 leaves the image unchanged and the tint multiplies the colors
+/// with the tint color by the tint strength
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdColorGrade")]
+pub struct ColorGrade {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    pub tint: [f32; 3],
+    pub tint_strength: f32,
+}
+
+/// Color grades for common scenes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradePreset {
+    Neutral,
+    Night,
+    Sepia,
+    Infernal,
+    Underwater,
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: [1.0, 1.0, 1.0],
+            tint_strength: 0.0,
+        }
+    }
+}
+
+impl ColorGrade {
+    /// Returns true if the grade leaves the colors unchanged
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The grade that is the fraction t of the way from this grade to the other grade
+    pub fn lerp(&self, other: &ColorGrade, t: f32) -> ColorGrade {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        ColorGrade {
+            brightness: mix(self.brightness, other.brightness),
+            contrast: mix(self.contrast, other.contrast),
+            saturation: mix(self.saturation, other.saturation),
+            tint: [
+                mix(self.tint[0], other.tint[0]),
+                mix(self.tint[1], other.tint[1]),
+                mix(self.tint[2], other.tint[2]),
+            ],
+            tint_strength: mix(self.tint_strength, other.tint_strength),
+        }
+    }
+
+    /// The color matrix and offset that apply the grade in a snapshot. The colors are first
+    /// saturated, then tinted and then the contrast and brightness are applied
+    pub fn color_matrix(&self) -> (Matrix, Vec4) {
+        let mut rows = [[0.0f32; 3]; 3];
+        for (row, output) in rows.iter_mut().enumerate() {
+            let tint = 1.0 - self.tint_strength + self.tint_strength * self.tint[row];
+            for (column, value) in output.iter_mut().enumerate() {
+                let identity = if row == column { 1.0 } else { 0.0 };
+                let saturated =
+                    self.saturation * identity + (1.0 - self.saturation) * LUMINANCE[column];
+                *value = self.contrast * tint * saturated;
+            }
+        }
+        // gsk multiplies the color as a row vector with the matrix, so the matrix is transposed
+        let mut values = [0.0f32; 16];
+        for (row, output) in rows.iter().enumerate() {
+            for (column, value) in output.iter().enumerate() {
+                values[column * 4 + row] = *value;
+            }
+        }
+        values[15] = 1.0;
+        let offset = 0.5 - 0.5 * self.contrast + self.brightness;
+        (
+            Matrix::from_float(values),
+            Vec4::new(offset, offset, offset, 0.0),
+        )
+    }
+}
+
+impl GradePreset {
+    pub const ALL: [GradePreset; 5] = [
+        GradePreset::Neutral,
+        GradePreset::Night,
+        GradePreset::Sepia,
+        GradePreset::Infernal,
+        GradePreset::Underwater,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GradePreset::Neutral => "Neutral",
+            GradePreset::Night => "Night",
+            GradePreset::Sepia => "Sepia flashback",
+            GradePreset::Infernal => "Infernal red",
+            GradePreset::Underwater => "Underwater",
+        }
+    }
+
+    pub fn grade(&self) -> ColorGrade {
+        match self {
+            GradePreset::Neutral => ColorGrade::default(),
+            GradePreset::Night => ColorGrade {
+                brightness: -0.2,
+                contrast: 0.9,
+                saturation: 0.4,
+                tint: [0.45, 0.55, 1.0],
+                tint_strength: 0.6,
+            },
+            GradePreset::Sepia => ColorGrade {
+                brightness: 0.05,
+                contrast: 0.9,
+                saturation: 0.0,
+                tint: [1.0, 0.85, 0.6],
+                tint_strength: 0.8,
+            },
+            GradePreset::Infernal => ColorGrade {
+                brightness: 0.0,
+                contrast: 1.2,
+                saturation: 1.2,
+                tint: [1.0, 0.3, 0.2],
+                tint_strength: 0.6,
+            },
+            GradePreset::Underwater => ColorGrade {
+                brightness: -0.1,
+                contrast: 0.95,
+                saturation: 0.8,
+                tint: [0.3, 0.8, 0.9],
+                tint_strength: 0.5,
+            },
+        }
+    }
+
+    /// The preset with exactly this grade, None if the grade was changed by hand
+    pub fn find(grade: &ColorGrade) -> Option<GradePreset> {
+        Self::ALL.into_iter().find(|p| p.grade() == *grade)
+    }
+}
+
+/// The color grades of the media files in a campaign, stored by their path relative to the
+/// campaign folder. Files without a color grade are shown without color filter
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CampaignColorGrades {
+    #[serde(default)]
+    media: BTreeMap<String, ColorGrade>,
+}
+
+impl CampaignColorGrades {
+    /// Read the color grades of the campaign, a campaign without color grade file has no
+    /// color grades
+    pub fn read(campaign_path: &str) -> Result<Self, DragonDisplayError> {
+        read_campaign_data(campaign_path, COLOR_GRADE_FILE)
+    }
+
+    /// Write the color grades to the campaign data folder
+    pub fn write(&self, campaign_path: &str) -> Result<(), DragonDisplayError> {
+        write_campaign_data(campaign_path, COLOR_GRADE_FILE, self)
+    }
+
+    /// The color grade of the file, the neutral grade if the file has none
+    pub fn get(&self, campaign_path: &str, file: &Path) -> ColorGrade {
+        self.media
+            .get(&relative_path(campaign_path, file))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Set the color grade of the file, a neutral grade is removed
+    pub fn set(&mut self, campaign_path: &str, file: &Path, grade: ColorGrade) {
+        let key = relative_path(campaign_path, file);
+        if grade.is_neutral() {
+            self.media.remove(&key);
+        } else {
+            self.media.insert(key, grade);
+        }
+    }
+}
@@ -1,32 +1,56 @@
 use crate::campaign::DdCampaign;
 // File containing functions that manage the config folder for campaign data
 use crate::errors::*;
+use crate::media::is_media;
+use gtk::glib;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use snafu::{prelude::*, ResultExt};
 use std::env;
 use std::fs::remove_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{
-    fs::{self, File, OpenOptions},
-    io::{self, ErrorKind, Read, Write},
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
 };
 use toml::to_string;
 
-pub const IMAGE_EXTENSIONS: [&str; 3] = ["jpeg", "jpg", "png"];
 pub const VIDEO_EXTENSIONS: [&str; 2] = ["mp4", "webm"];
 pub const VTT_EXTENSIONS: [&str; 3] = ["uvtt", "vtt", "dd2vtt"];
+pub const PDF_EXTENSIONS: [&str; 1] = ["pdf"];
 pub const CAMPAIGN_MAX_CHAR_LENGTH: u16 = 25;
 pub const MAX_CAMPAIGN_AMOUNT: u16 = 10;
 pub const SYNCHRONIZATION_OPTIONS: [&str; 2] = ["None", "Google Drive"];
-
-enum Operation {
-    READ,
-    WRITE,
-    APPEND,
-}
+/// Name of the hidden folder inside a campaign folder that holds the campaign specific data
+pub const CAMPAIGN_DATA_FOLDER: &str = ".dragon-display";
+/// Name of the folder in the user config and data folders that belongs to dragon display
+pub const APP_FOLDER: &str = "dragon-display";
+/// Name of the file in the config folder that holds the campaigns
+pub const CONFIG_FILE: &str = "config.toml";
+/// Name of the config file that older versions kept in the working directory
+pub const LEGACY_CONFIG_FILE: &str = ".config.toml";
+/// Name of the google client secret file in the config folder
+pub const CLIENT_SECRET_FILE: &str = "client_secret.json";
+/// Name of the folder in the config folder that holds the backups of the config file
+pub const BACKUP_FOLDER: &str = "backups";
+/// The number of previous config files that are kept as backup
+pub const MAX_BACKUPS: usize = 5;
+/// The version of the config file that this version of dragon display writes
+pub const CONFIG_VERSION: usize = 1;
+/// Updates a config file to the next version, the first migration updates version 1. Version 0
+/// is the config without version field, its campaigns are the same as those of version 1
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[];
+
+/// The config folder chosen with --config-dir, if any
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
 
 /// Structure representing the name of the campaign and the corresponding data
 #[derive(Serialize, Deserialize, Default)]
 struct Config {
+    #[serde(default)]
+    version: usize,
+    #[serde(default)]
     campaigns: Vec<Campaign>,
 }
 
@@ -151,51 +175,22 @@ impl Default for SynchronizationOption {
 /// Tries to read the campaign configurations from the config file and puts them in a Vector.
 /// if there is no config file this method will return an empty vector
 pub fn read_campaign_from_config() -> Result<Vec<Campaign>, DragonDisplayError> {
-    // We should return an empty vector if the config file is not found
-    let mut file = match get_campaign_config(Operation::READ) {
-        Ok(f) => f,
-        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
-        Err(e) => {
-            return Err(DragonDisplayError::IOError {
-                source: e,
-                msg: "Could not read the config file".to_owned(),
-            })
-        }
-    };
-
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).context(IOSnafu {
-        msg: "Could not read configuration file".to_owned(),
-    })?;
-
-    let config: Config = toml::from_str(&contents).context(SerializeSnafu {
-        msg: "The config file got corrupted. Please remove the .config.toml file 
-            (a hidden file in this directory) and restart the application"
-            .to_owned(),
-    })?;
-    check_integrity(&config)?;
-
-    Ok(config.campaigns)
+    Ok(read_config()?.campaigns)
 }
 
 /// Given a Campaign, this function will try to write the campaign to the config file and create a directory in the campaign.path. This function will update the values if the name of the campaign already exists
 pub fn write_campaign_to_config(campaign: Campaign) -> Result<(), DragonDisplayError> {
-    let config_item = Config {
-        campaigns: vec![campaign.clone()],
-    };
-    // if it exists we want to remove it to add the updated version
-    if campaign_exists(&campaign)? {
-        remove_campaign_from_config(campaign.clone(), false)?;
+    let mut config = read_config()?;
+    // if it exists we want to replace it with the updated version
+    let existing = config
+        .campaigns
+        .iter_mut()
+        .find(|c| c.name == campaign.name);
+    match existing {
+        Some(existing) => *existing = campaign.clone(),
+        None => config.campaigns.push(campaign.clone()),
     }
-    let mut config_file = get_campaign_config(Operation::APPEND).context(IOSnafu {
-        msg: "Could not open the campaign config file".to_owned(),
-    })?;
-    let toml_string = to_string(&config_item).unwrap();
-    config_file
-        .write_all(toml_string.as_bytes())
-        .context(IOSnafu {
-            msg: "Could not write to the campaign config file".to_owned(),
-        })?;
+    write_config(&config)?;
     fs::create_dir_all(campaign.path).context(IOSnafu {
         msg: "Could not create the folder to put the images in".to_owned(),
     })?;
@@ -208,115 +203,347 @@ pub fn remove_campaign_from_config(
     remove_folder: bool,
 ) -> Result<(), DragonDisplayError> {
     check_save_removal(&campaign.path)?;
-    let campaign_list = read_campaign_from_config()?;
-
-    ensure!(
-        campaign_list.len() > 0,
-        OtherSnafu {
-            msg: "Could not find the campaign to be removed".to_owned()
-        }
-    );
-
-    if campaign_list.len() == 1 {
-        if remove_folder {
-            remove_dir_all(&campaign.path).unwrap_or(());
-        }
-        return remove_campaign_config();
-    }
-
-    let mut new_campaign_list = Vec::from(campaign_list);
+    let mut config = read_config()?;
 
-    let index = new_campaign_list
+    let index = config
+        .campaigns
         .iter()
         .position(|c| c.name == campaign.name)
         .context(OtherSnafu {
             msg: "Could not find the campaign to be removed".to_owned(),
         })?;
+    config.campaigns.remove(index);
+    write_config(&config)?;
 
-    new_campaign_list.swap_remove(index);
-
-    let config_item = Config {
-        campaigns: new_campaign_list,
-    };
-    let mut config_file = get_campaign_config(Operation::WRITE).context(IOSnafu {
-        msg: "Could not open the campaign config file".to_owned(),
-    })?;
-    let toml_string =
-        to_string(&config_item).expect("Expected config item to be converted to string");
-    config_file
-        .write_all(toml_string.as_bytes())
-        .context(IOSnafu {
-            msg: "Could not write to the campaign config file".to_owned(),
-        })?;
     if remove_folder {
         remove_dir_all(&campaign.path).unwrap_or(());
     }
     Ok(())
 }
 
-/// Given a campaign, this function will return whether this campaign exists in the config file
-fn campaign_exists(campaign: &Campaign) -> Result<bool, DragonDisplayError> {
-    let campaign_list = read_campaign_from_config()?;
-    for c in campaign_list {
-        if c.name == campaign.name {
-            return Ok(true);
-        }
+/// Use the folder instead of the user config folder for the config of dragon display. Can only
+/// be set once, before the config is used
+pub fn set_config_dir(path: PathBuf) {
+    CONFIG_DIR
+        .set(path)
+        .expect("Expected the config folder to be set only once");
+}
+
+/// The folder that holds the config of dragon display, $XDG_CONFIG_HOME/dragon-display unless
+/// another folder was chosen with --config-dir
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR
+        .get_or_init(|| glib::user_config_dir().join(APP_FOLDER))
+        .clone()
+}
+
+/// The file that holds the campaigns
+pub fn config_file_path() -> PathBuf {
+    config_dir().join(CONFIG_FILE)
+}
+
+/// The folder that new campaigns are put in when the user does not choose a folder
+pub fn default_campaign_dir() -> PathBuf {
+    glib::user_data_dir().join(APP_FOLDER)
+}
+
+/// Import the config file and google client secret that older versions kept in the working
+/// directory. A file is only imported when it is not in the config folder yet, the old files are
+/// left in place
+pub fn migrate_legacy_config() -> Result<(), DragonDisplayError> {
+    import_legacy_file(LEGACY_CONFIG_FILE, &config_file_path())?;
+    import_legacy_file(CLIENT_SECRET_FILE, &config_dir().join(CLIENT_SECRET_FILE))
+}
+
+/// Copy the file with the name in the working directory to the path, unless the path exists
+fn import_legacy_file(name: &str, path: &Path) -> Result<(), DragonDisplayError> {
+    if path.exists() {
+        return Ok(());
     }
-    Ok(false)
+    let Ok(legacy_path) = env::current_dir().map(|dir| dir.join(name)) else {
+        return Ok(());
+    };
+    if !legacy_path.is_file() {
+        return Ok(());
+    }
+    fs::create_dir_all(config_dir()).context(IOSnafu {
+        msg: "Could not create the config folder".to_owned(),
+    })?;
+    fs::copy(&legacy_path, path).context(IOSnafu {
+        msg: format!("Could not import the file {}", legacy_path.display()),
+    })?;
+    Ok(())
+}
+
+/// Read the config file and update it to the current version. A missing config file is an
+/// empty config
+fn read_config() -> Result<Config, DragonDisplayError> {
+    let path = config_file_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => {
+            return Err(DragonDisplayError::IOError {
+                source: e,
+                msg: "Could not read the config file".to_owned(),
+            })
+        }
+    };
+    parse_config(&contents)
 }
 
-/// Given a file operation this function returns the file with the option for the inputted operation
-fn get_campaign_config(operation: Operation) -> Result<File, io::Error> {
-    let mut path = env::current_dir()?;
-    path.push(".config.toml");
-    match operation {
-        Operation::READ => {
-            let file = OpenOptions::new().read(true).open(&path)?;
-            Ok(file)
+/// Parse the contents of a config file, older versions of the config are migrated to the
+/// current version
+fn parse_config(contents: &str) -> Result<Config, DragonDisplayError> {
+    let corrupted = |err: toml::de::Error| DragonDisplayError::CorruptConfig {
+        msg: format!(
+            "The config file {} got corrupted: {}",
+            config_file_path().display(),
+            err.message()
+        ),
+    };
+    let mut table: toml::Table = contents.parse().map_err(corrupted)?;
+    let version = match table.get("version") {
+        Some(version) => version
+            .as_integer()
+            .and_then(|v| usize::try_from(v).ok())
+            .context(CorruptConfigSnafu {
+                msg: "The version of the config file is not valid".to_owned(),
+            })?,
+        // older versions of the config did not have a version
+        None => 0,
+    };
+    ensure!(
+        version <= CONFIG_VERSION,
+        OtherSnafu {
+            msg: format!(
+                "The config file {} was written by a newer version of dragon display",
+                config_file_path().display()
+            )
         }
-        Operation::WRITE => {
-            let file = OpenOptions::new().write(true).truncate(true).open(&path)?;
-            Ok(file)
+    );
+    for migration in MIGRATIONS.iter().skip(version.saturating_sub(1)) {
+        migration(&mut table);
+    }
+    table.insert(
+        "version".to_owned(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+    let config: Config = table.try_into().map_err(corrupted)?;
+    check_integrity(&config)?;
+    Ok(config)
+}
+
+/// Replace the config file with the config. The previous config file is kept as backup and the
+/// new file is written next to it before it replaces the old one, so a crash can not leave a
+/// half written config file behind
+fn write_config(config: &Config) -> Result<(), DragonDisplayError> {
+    let path = config_file_path();
+    fs::create_dir_all(config_dir()).context(IOSnafu {
+        msg: "Could not create the config folder".to_owned(),
+    })?;
+    backup_config().context(IOSnafu {
+        msg: "Could not make a backup of the campaign config file".to_owned(),
+    })?;
+    let config = Config {
+        version: CONFIG_VERSION,
+        campaigns: config.campaigns.clone(),
+    };
+    let toml_string = to_string(&config).expect("Expected config to be converted to string");
+    write_atomic(&path, toml_string.as_bytes()).context(IOSnafu {
+        msg: "Could not write to the campaign config file".to_owned(),
+    })?;
+    Ok(())
+}
+
+/// Write the contents to a temporary file that replaces the file at the path once it is
+/// completely on disk
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+    // the rename itself is only durable once the folder is synced, not every platform can
+    // open a folder so this is done where possible
+    if let Some(Ok(folder)) = path.parent().map(File::open) {
+        let _ = folder.sync_all();
+    }
+    Ok(())
+}
+
+/// The path of the backup with the number, 1 is the most recent backup
+fn backup_path(number: usize) -> PathBuf {
+    config_dir()
+        .join(BACKUP_FOLDER)
+        .join(format!("{}.{}", CONFIG_FILE, number))
+}
+
+/// Keep a copy of the current config file as the most recent backup, the oldest backup is
+/// removed when there are MAX_BACKUPS backups
+fn backup_config() -> Result<(), io::Error> {
+    let path = config_file_path();
+    let contents = match fs::read(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    fs::create_dir_all(config_dir().join(BACKUP_FOLDER))?;
+    for number in (1..MAX_BACKUPS).rev() {
+        if let Err(e) = fs::rename(backup_path(number), backup_path(number + 1)) {
+            if e.kind() != ErrorKind::NotFound {
+                return Err(e);
+            }
         }
-        Operation::APPEND => {
-            let file = OpenOptions::new().append(true).create(true).open(&path)?;
-            Ok(file)
+    }
+    write_atomic(&backup_path(1), &contents)
+}
+
+/// The most recent backup of the config file that can be read, if there is one
+pub fn latest_backup() -> Option<PathBuf> {
+    (1..=MAX_BACKUPS).map(backup_path).find(|path| {
+        fs::read_to_string(path)
+            .ok()
+            .is_some_and(|contents| parse_config(&contents).is_ok())
+    })
+}
+
+/// Replace the config file with the backup. The config file that is replaced is kept next to
+/// it with the extension .corrupted
+pub fn restore_backup(backup: &Path) -> Result<(), DragonDisplayError> {
+    let path = config_file_path();
+    let contents = fs::read(backup).context(IOSnafu {
+        msg: format!("Could not read the backup {}", backup.display()),
+    })?;
+    let mut corrupted_path = path.as_os_str().to_owned();
+    corrupted_path.push(".corrupted");
+    if let Err(e) = fs::rename(&path, PathBuf::from(corrupted_path)) {
+        if e.kind() != ErrorKind::NotFound {
+            return Err(DragonDisplayError::IOError {
+                source: e,
+                msg: "Could not move the corrupted config file".to_owned(),
+            });
         }
     }
+    write_atomic(&path, &contents).context(IOSnafu {
+        msg: "Could not restore the backup of the config file".to_owned(),
+    })?;
+    Ok(())
 }
 
-/// Tries to remove the campaign config file
-fn remove_campaign_config() -> Result<(), DragonDisplayError> {
-    let mut path = env::current_dir().context(IOSnafu {
-        msg: "Could not remove the campaign config file".to_owned(),
+/// Returns the path of a file in the data folder of the campaign at campaign_path
+pub fn campaign_data_path(campaign_path: &str, file_name: &str) -> PathBuf {
+    let mut path = PathBuf::from(campaign_path);
+    path.push(CAMPAIGN_DATA_FOLDER);
+    path.push(file_name);
+    path
+}
+
+/// Tries to read a toml file from the data folder of the campaign at campaign_path. If the file
+/// does not exist yet this function will return the default value
+pub fn read_campaign_data<T: DeserializeOwned + Default>(
+    campaign_path: &str,
+    file_name: &str,
+) -> Result<T, DragonDisplayError> {
+    let path = campaign_data_path(campaign_path, file_name);
+    let contents = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => {
+            return Err(DragonDisplayError::IOError {
+                source: e,
+                msg: format!("Could not read the campaign data file {}", file_name),
+            })
+        }
+    };
+    let data = toml::from_str(&contents).context(SerializeSnafu {
+        msg: format!("The campaign data file {} got corrupted", file_name),
     })?;
-    path.push(".config.toml");
-    fs::remove_file(&path).context(IOSnafu {
-        msg: "Could not remove the campaign config file".to_owned(),
+    Ok(data)
+}
+
+/// Writes the data as toml to a file in the data folder of the campaign at campaign_path, the
+/// data folder is created if it does not exist. Like the config file the data is written
+/// atomically, so a crash can not leave a half written file behind
+pub fn write_campaign_data<T: Serialize>(
+    campaign_path: &str,
+    file_name: &str,
+    data: &T,
+) -> Result<(), DragonDisplayError> {
+    let path = campaign_data_path(campaign_path, file_name);
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).context(IOSnafu {
+            msg: "Could not create the campaign data folder".to_owned(),
+        })?;
+    }
+    let toml_string = to_string(data).expect("Expected campaign data to be converted to string");
+    write_atomic(&path, toml_string.as_bytes()).context(IOSnafu {
+        msg: format!("Could not write the campaign data file {}", file_name),
     })?;
     Ok(())
 }
 
+/// Appends the data as toml to a file in the data folder of the campaign at campaign_path, the
+/// file and data folder are created if they do not exist. The data should only hold arrays of
+/// tables, so the appended tables extend the arrays that are already in the file
+pub fn append_campaign_data<T: Serialize>(
+    campaign_path: &str,
+    file_name: &str,
+    data: &T,
+) -> Result<(), DragonDisplayError> {
+    let path = campaign_data_path(campaign_path, file_name);
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).context(IOSnafu {
+            msg: "Could not create the campaign data folder".to_owned(),
+        })?;
+    }
+    let toml_string = to_string(data).expect("Expected campaign data to be converted to string");
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(toml_string.as_bytes()))
+        .context(IOSnafu {
+            msg: format!("Could not write the campaign data file {}", file_name),
+        })?;
+    Ok(())
+}
+
 /// Checks for the integrity of the config file.  
 /// Checks if there are no more campaigns in the file than MAX_CAMPAIGN_AMOUNT  
 /// Checks if there are no duplicate paths in the campaign folder
 fn check_integrity(config: &Config) -> Result<(), DragonDisplayError> {
     ensure!(
         config.campaigns.len() <= usize::from(MAX_CAMPAIGN_AMOUNT),
-        OtherSnafu {msg: "Too many campaigns found in the config file. Please remove the .config.toml file (a hidden file in this directory) and restart the application".to_owned()}
+        CorruptConfigSnafu {
+            msg: format!(
+                "Too many campaigns found in the config file {}",
+                config_file_path().display()
+            )
+        }
     );
 
     let mut path_names = Vec::new();
     for campaign in &config.campaigns {
         let path = &campaign.path;
-        ensure!(!path_names.contains(&path), OtherSnafu {msg: "Found a duplicate in the config file. Please remove the .config.toml file (a hidden file in this directory) and restart the application".to_owned()});
+        ensure!(
+            !path_names.contains(&path),
+            CorruptConfigSnafu {
+                msg: format!(
+                    "Found a duplicate in the config file {}",
+                    config_file_path().display()
+                )
+            }
+        );
         path_names.push(path);
     }
 
     Ok(())
 }
 
-/// Check if there only image files in the folder of the campaign to be removed
+/// Check if there are only media files in the folder of the campaign to be removed
 fn check_save_removal(campaign_path: &str) -> Result<(), DragonDisplayError> {
     let files = match fs::read_dir(&campaign_path) {
         Ok(f) => f,
@@ -336,16 +563,22 @@ fn check_save_removal(campaign_path: &str) -> Result<(), DragonDisplayError> {
             })?
             .path();
 
-        let extension_os = file_path.extension().context(OtherSnafu {
-            msg: "Could not get file extension".to_owned(),
-        })?;
+        // the campaign data folder is created by dragon display and can be removed
+        if file_path.ends_with(CAMPAIGN_DATA_FOLDER) {
+            continue;
+        }
 
-        let extension = extension_os.to_str().context(OtherSnafu {
-            msg: "Some internal error occured while reading file extensions",
-        })?;
+        // subfolders hold the categories of a synchronized campaign
+        if file_path.is_dir() {
+            check_save_removal(file_path.to_str().context(OtherSnafu {
+                msg: "Some internal error occured while reading the campaign folders",
+            })?)?;
+            continue;
+        }
 
+        // only media that dragon display can show is removed with the campaign
         ensure!(
-            IMAGE_EXTENSIONS.contains(&extension),
+            is_media(&file_path),
             OtherSnafu {
                 msg: "Could not remove the campaign".to_owned()
             }
@@ -354,3 +587,131 @@ fn check_save_removal(campaign_path: &str) -> Result<(), DragonDisplayError> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard};
+
+    use super::*;
+
+    /// The config tests share the config folder, so they can not run at the same time
+    static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Use an empty folder in the temporary folder as config folder, so the tests never touch
+    /// the config of the user. Holds the lock until the returned guard is dropped
+    fn test_config_dir() -> MutexGuard<'static, ()> {
+        let guard = CONFIG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = CONFIG_DIR.get_or_init(|| {
+            env::temp_dir().join(format!("dragon-display-test-{}", std::process::id()))
+        });
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).expect("Could not create the test config folder");
+        guard
+    }
+
+    fn campaign(name: &str) -> Campaign {
+        Campaign::new(name.to_owned(), format!("/campaigns/{}", name))
+    }
+
+    fn config_with(names: &[&str]) -> Config {
+        Config {
+            version: CONFIG_VERSION,
+            campaigns: names.iter().map(|name| campaign(name)).collect(),
+        }
+    }
+
+    fn campaign_names(config: &Config) -> Vec<&str> {
+        config.campaigns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_current_config() {
+        let _guard = test_config_dir();
+        let contents = to_string(&config_with(&["first", "second"])).unwrap();
+        let config = parse_config(&contents).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(campaign_names(&config), ["first", "second"]);
+    }
+
+    #[test]
+    fn parse_config_without_version() {
+        let _guard = test_config_dir();
+        let contents = r#"
+[[campaigns]]
+name = "old"
+path = "/campaigns/old"
+sync_option = "None"
+"#;
+        let config = parse_config(contents).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(campaign_names(&config), ["old"]);
+    }
+
+    #[test]
+    fn parse_empty_config() {
+        let _guard = test_config_dir();
+        let config = parse_config("").unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.campaigns.is_empty());
+    }
+
+    #[test]
+    fn reject_newer_config() {
+        let _guard = test_config_dir();
+        let contents = format!("version = {}", CONFIG_VERSION + 1);
+        assert!(parse_config(&contents).is_err());
+    }
+
+    #[test]
+    fn reject_corrupted_config() {
+        let _guard = test_config_dir();
+        assert!(parse_config("campaigns = [").is_err());
+        assert!(parse_config("version = -1").is_err());
+        let mut config = config_with(&["first", "second"]);
+        config.campaigns[1].path = config.campaigns[0].path.clone();
+        assert!(parse_config(&to_string(&config).unwrap()).is_err());
+    }
+
+    #[test]
+    fn rotate_backups() {
+        let _guard = test_config_dir();
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        assert!(names.len() > MAX_BACKUPS + 1);
+        for name in names {
+            write_config(&config_with(&[name])).unwrap();
+        }
+        assert!(!backup_path(MAX_BACKUPS + 1).exists());
+        // the most recent backup is the config before the last write
+        for number in 1..=MAX_BACKUPS {
+            let contents = fs::read_to_string(backup_path(number)).unwrap();
+            let backup = parse_config(&contents).unwrap();
+            assert_eq!(campaign_names(&backup), [names[names.len() - 1 - number]]);
+        }
+        assert_eq!(latest_backup(), Some(backup_path(1)));
+    }
+
+    #[test]
+    fn skip_unreadable_backups() {
+        let _guard = test_config_dir();
+        write_config(&config_with(&["first"])).unwrap();
+        write_config(&config_with(&["second"])).unwrap();
+        write_config(&config_with(&["third"])).unwrap();
+        fs::write(backup_path(1), "campaigns = [").unwrap();
+        assert_eq!(latest_backup(), Some(backup_path(2)));
+    }
+
+    #[test]
+    fn restore_latest_backup() {
+        let _guard = test_config_dir();
+        write_config(&config_with(&["first"])).unwrap();
+        write_config(&config_with(&["second"])).unwrap();
+        fs::write(config_file_path(), "campaigns = [").unwrap();
+        assert!(read_config().is_err());
+
+        let backup = latest_backup().unwrap();
+        restore_backup(&backup).unwrap();
+        assert_eq!(campaign_names(&read_config().unwrap()), ["first"]);
+        let corrupted = config_dir().join(format!("{}.corrupted", CONFIG_FILE));
+        assert_eq!(fs::read_to_string(corrupted).unwrap(), "campaigns = [");
+    }
+}
@@ -33,6 +33,8 @@ pub enum DragonDisplayError {
         msg: String,
     },
     #[snafu(display("{}", msg), visibility(pub))]
+    CorruptConfig { msg: String },
+    #[snafu(display("{}", msg), visibility(pub))]
     SerializeError {
         source: toml::de::Error,
         msg: String,
@@ -1,4 +1,5 @@
 use std::fs;
+use std::path::Path;
 use std::{env, fs::OpenOptions, io::Read, sync::mpsc};
 
 use async_channel::{Receiver, Sender};
@@ -10,20 +11,23 @@ use reqwest::blocking::Client as ReqwestClient;
 use rouille::{Response, Server};
 use snafu::{OptionExt, Report, ResultExt};
 
-use tokio::fs::File;
+use tokio::fs::{create_dir_all, File};
 use tokio::io::copy;
 
 use crate::{
+    config::{config_dir, CLIENT_SECRET_FILE},
     errors::{
         AddressInUseSnafu, ClientSecretSnafu, ClientSnafu, ConnectionRefusedSnafu,
         DragonDisplayError, IOSnafu, InvalidDataSnafu, RecvSnafu, SendMessageSnafu,
     },
+    media::campaign_files,
     setup::Token,
     try_emit,
     widgets::google_folder_object::GoogleFolderObject,
 };
 
 const SCOPE: &str = "https://www.googleapis.com/auth/drive.readonly";
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
 
 pub enum GdClientEvent {
     Url {
@@ -303,75 +307,81 @@ impl DragonDisplayGDClient {
      * ---------------------------------------------
      **/
 
-    /// Get the files in the google drive 'folder' that need to be downloaded, remove any local
-    /// file that is not in the drive folder
+    /// Get the files in the google drive 'folder' and its subfolders that need to be downloaded,
+    /// remove any local file that is not in the drive folder. The names of the files are paths
+    /// relative to the drive folder, so the local folder mirrors the folder structure
     pub async fn get_and_remove(&self, token: Token, folder: String, path: String) {
         try_emit!(self, Self::configure_environment(), true);
         let google_drive_client =
             Client::new_from_env(&token.access_token, &token.refresh_token).await;
         // get existing local files
-        let mut existing_files = Vec::new();
         let existing = try_emit!(
             self,
-            fs::read_dir(&path).context(IOSnafu {
+            campaign_files(Path::new(&path)).context(IOSnafu {
                 msg: "Could not read directory".to_string()
             }),
             true
         );
-        for f in existing {
-            let f = match f {
-                Ok(f) => f,
-                Err(_) => continue,
-            };
-            let binding = f.file_name();
-            let f = match binding.to_str() {
-                Some(f) => f,
-                None => continue,
+        let existing_files: Vec<String> = existing
+            .iter()
+            .filter_map(|f| f.strip_prefix(&path).ok())
+            .filter_map(|f| f.to_str())
+            .map(|f| f.to_string())
+            .collect();
+        // Get the files in the google drive folder and its subfolders
+        let mut drive_files = Vec::new();
+        let mut drive_folders = Vec::new();
+        let mut unvisited = vec![(folder, String::new())];
+        while let Some((folder, prefix)) = unvisited.pop() {
+            let query = format!("'{}' in parents and trashed = false", folder);
+            let request = google_drive_client
+                .files()
+                .list_all(
+                    "user", "", false, "", false, "name", &query, "", false, false, "",
+                )
+                .await;
+            let result = match request {
+                Ok(r) => r.body,
+                Err(_) => {
+                    self.emit_event(GdClientEvent::Refresh);
+                    return;
+                }
             };
-            existing_files.push(f.to_string());
-        }
-        // Get the files in the google drive folder
-        let query = format!(
-            "mimeType != 'application/vnd.google-apps.folder' and '{}' in parents and trashed = false",
-            folder
-        );
-        let request = google_drive_client
-            .files()
-            .list_all(
-                "user", "", false, "", false, "name", &query, "", false, false, "",
-            )
-            .await;
-        let result = match request {
-            Ok(r) => r.body,
-            Err(_) => {
-                self.emit_event(GdClientEvent::Refresh);
-                return;
+            for file in result {
+                // a slash in a drive name would otherwise create an extra folder
+                let name = format!("{}{}", prefix, file.name.replace('/', "_"));
+                if file.mime_type == FOLDER_MIME_TYPE {
+                    unvisited.push((file.id, format!("{}/", name)));
+                    drive_folders.push(name);
+                } else {
+                    drive_files.push((file.id, name));
+                }
             }
-        };
+        }
         let mut total = 0;
-        // files that should not be removed
-        let mut keep_files = Vec::new();
-        for file in result {
-            let mut keep = existing_files
-                .iter()
-                .filter(|f| *f == &file.name)
-                .map(|f| f.clone())
-                .collect();
-            keep_files.append(&mut keep);
-            if !existing_files.contains(&file.name) {
-                self.emit_event(GdClientEvent::DownloadFile {
-                    id: file.id,
-                    name: file.name,
-                });
+        for (id, name) in drive_files.iter().cloned() {
+            if !existing_files.contains(&name) {
+                self.emit_event(GdClientEvent::DownloadFile { id, name });
                 total += 1;
             }
         }
         for existing_file in existing_files {
-            if !keep_files.contains(&&existing_file) {
-                let _ = fs::remove_file(format!("{}/{}", &path, existing_file));
+            if drive_files.iter().any(|(_, name)| *name == existing_file) {
+                total += 1;
                 continue;
             }
-            total += 1;
+            let _ = fs::remove_file(format!("{}/{}", &path, existing_file));
+            // remove the folders that are no longer in the drive folder once they are empty
+            let mut parent = Path::new(&existing_file).parent();
+            while let Some(folder) = parent.and_then(|p| p.to_str()).filter(|p| !p.is_empty()) {
+                if drive_folders.iter().any(|f| f == folder) {
+                    break;
+                }
+                if fs::remove_dir(format!("{}/{}", &path, folder)).is_err() {
+                    break;
+                }
+                parent = Path::new(folder).parent();
+            }
         }
         self.emit_event(GdClientEvent::Totalfolders { total });
     }
@@ -399,7 +409,14 @@ impl DragonDisplayGDClient {
                     return;
                 }
             };
-            let mut destination = match File::create(format!("{}/{}", destination, name)).await {
+            let file_path = format!("{}/{}", destination, name);
+            if let Some(parent) = Path::new(&file_path).parent() {
+                if create_dir_all(parent).await.is_err() {
+                    failed_files.push(name);
+                    continue;
+                }
+            }
+            let mut destination = match File::create(&file_path).await {
                 Ok(d) => d,
                 Err(_) => {
                     failed_files.push(name);
@@ -437,14 +454,13 @@ impl DragonDisplayGDClient {
      **/
 
     /// Set the GOOGLE_KEY_ENCODED environment variable to enable calling client::new_from_env
-    /// A file named client_secret.json needs to be in the directory of the Dragon-Display program
+    /// A file named client_secret.json needs to be in the config folder of Dragon-Display
     fn configure_environment() -> Result<(), DragonDisplayError> {
         if let Ok(_) = env::var("GOOGLE_KEY_ENCODED") {
             return Ok(());
         }
 
-        let mut path = env::current_dir().context(ClientSecretSnafu)?;
-        path.push("client_secret.json");
+        let path = config_dir().join(CLIENT_SECRET_FILE);
 
         let mut file = OpenOptions::new()
             .read(true)
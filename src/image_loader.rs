@@ -0,0 +1,99 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use gdk4::Texture;
+use gtk::gdk_pixbuf::{Pixbuf, PixbufAnimation, PixbufAnimationIter};
+use gtk::prelude::*;
+use snafu::{OptionExt, ResultExt};
+
+use crate::apng;
+use crate::errors::{DragonDisplayError, GlibSnafu, OtherSnafu};
+
+/// An image as it is shown on the display
+pub enum DisplayImage {
+    Static(Texture),
+    Animated(Animation),
+}
+
+/// The frames of an animated image
+pub enum Animation {
+    /// An animation that gdk-pixbuf can decode, such as gif and webp
+    Pixbuf(PixbufAnimationIter),
+    /// An animation that is decoded up front, such as animated png
+    Frames {
+        frames: Vec<(Pixbuf, Duration)>,
+        index: usize,
+    },
+}
+
+impl Animation {
+    /// The frame that should be shown now
+    pub fn pixbuf(&self) -> Pixbuf {
+        match self {
+            Self::Pixbuf(iter) => iter.pixbuf(),
+            Self::Frames { frames, index } => frames[*index].0.clone(),
+        }
+    }
+
+    /// How long the current frame should be shown, None if it should be shown forever
+    pub fn delay(&self) -> Option<Duration> {
+        match self {
+            Self::Pixbuf(iter) => iter.delay_time(),
+            Self::Frames { frames, index } => Some(frames[*index].1),
+        }
+    }
+
+    /// Go to the next frame of the animation, animations start over when they end
+    pub fn advance(&mut self) {
+        match self {
+            Self::Pixbuf(iter) => {
+                iter.advance(SystemTime::now());
+            }
+            Self::Frames { frames, index } => *index = (*index + 1) % frames.len(),
+        }
+    }
+}
+
+/// Returns true if there is an image loader that can read the file. The format is detected from
+/// the content of the file, so the extension of the file does not matter
+pub fn is_image(path: &Path) -> bool {
+    Pixbuf::file_info(path).is_some()
+}
+
+/// Load the image to be shown on a display of the given size in pixels. Scalable images are
+/// rendered at the size of the display and animated images return all of their frames
+pub fn load_image(
+    path: &Path,
+    width: i32,
+    height: i32,
+) -> Result<DisplayImage, DragonDisplayError> {
+    let (format, _, _) = Pixbuf::file_info(path).context(OtherSnafu {
+        msg: format!("The file {} is not a supported image", path.display()),
+    })?;
+    if format.is_scalable() {
+        let pixbuf = Pixbuf::from_file_at_scale(path, width, height, true).context(GlibSnafu {
+            msg: format!("Could not render the image {}", path.display()),
+        })?;
+        return Ok(DisplayImage::Static(Texture::for_pixbuf(&pixbuf)));
+    }
+    if apng::is_animated(path) {
+        let frames = apng::frames(path)?;
+        if frames.len() > 1 {
+            return Ok(DisplayImage::Animated(Animation::Frames {
+                frames,
+                index: 0,
+            }));
+        }
+    }
+    let animation = PixbufAnimation::from_file(path).context(GlibSnafu {
+        msg: format!("Could not load the image {}", path.display()),
+    })?;
+    if !animation.is_static_image() {
+        let iter = animation.iter(Some(SystemTime::now()));
+        return Ok(DisplayImage::Animated(Animation::Pixbuf(iter)));
+    }
+    let texture = Texture::from_filename(path).context(GlibSnafu {
+        msg: format!("Could not load the image {}", path.display()),
+    })?;
+    Ok(DisplayImage::Static(texture))
+}
@@ -0,0 +1,153 @@
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+
+/// Name of the file in the campaign data folder that stores the combat
+pub const INITIATIVE_FILE: &str = "initiative.toml";
+pub const MIN_INITIATIVE: f64 = -10.0;
+pub const MAX_INITIATIVE: f64 = 50.0;
+
+/// A creature taking part in the combat. Hidden combatants are not shown to the players
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Combatant {
+    pub name: String,
+    pub initiative: i32,
+    pub modifier: i32,
+    pub hidden: bool,
+}
+
+/// The state of a combat. The combatants are kept in turn order, turn is the index of the
+/// combatant whose turn it is
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdCombat")]
+pub struct Combat {
+    pub combatants: Vec<Combatant>,
+    pub turn: usize,
+    pub round: u32,
+    /// Whether the tracker is shown on the display, a combat is hidden when the program starts
+    #[serde(skip)]
+    pub shown: bool,
+}
+
+impl Combatant {
+    pub fn new(name: String, modifier: i32, hidden: bool) -> Self {
+        Self {
+            name,
+            initiative: roll(modifier),
+            modifier,
+            hidden,
+        }
+    }
+}
+
+impl Combat {
+    /// Add a combatant to the combat in the position of its initiative. The combatant whose
+    /// turn it is keeps its turn
+    pub fn add(&mut self, combatant: Combatant) {
+        let position = self
+            .combatants
+            .iter()
+            .position(|c| c.initiative < combatant.initiative)
+            .unwrap_or(self.combatants.len());
+        if position <= self.turn && !self.combatants.is_empty() {
+            self.turn += 1;
+        }
+        self.combatants.insert(position, combatant);
+        if self.round == 0 {
+            self.round = 1;
+        }
+    }
+
+    /// Remove the combatant at index, the next combatant gets the turn if it was the turn of the
+    /// removed combatant
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.combatants.len() {
+            return;
+        }
+        self.combatants.remove(index);
+        if index < self.turn {
+            self.turn -= 1;
+        }
+        if self.turn >= self.combatants.len() {
+            self.turn = 0;
+        }
+    }
+
+    /// Roll the initiative of every combatant again and start the combat at the first round
+    pub fn roll_all(&mut self) {
+        for combatant in &mut self.combatants {
+            combatant.initiative = roll(combatant.modifier);
+        }
+        self.sort();
+        self.turn = 0;
+        self.round = 1;
+    }
+
+    /// Put the combatants in turn order, highest initiative first. Combatants with the same
+    /// initiative are ordered by their modifier
+    pub fn sort(&mut self) {
+        let current = self.combatants.get(self.turn).cloned();
+        self.combatants.sort_by(|a, b| {
+            b.initiative
+                .cmp(&a.initiative)
+                .then(b.modifier.cmp(&a.modifier))
+        });
+        if let Some(current) = current {
+            self.turn = self
+                .combatants
+                .iter()
+                .position(|c| *c == current)
+                .unwrap_or(0);
+        }
+    }
+
+    /// Give the turn to the next combatant, a new round starts after the last combatant
+    pub fn next_turn(&mut self) {
+        if self.combatants.is_empty() {
+            return;
+        }
+        self.turn += 1;
+        if self.turn >= self.combatants.len() {
+            self.turn = 0;
+            self.round += 1;
+        }
+    }
+
+    /// Give the turn back to the previous combatant
+    pub fn previous_turn(&mut self) {
+        if self.combatants.is_empty() {
+            return;
+        }
+        if self.turn > 0 {
+            self.turn -= 1;
+        } else if self.round > 1 {
+            self.turn = self.combatants.len() - 1;
+            self.round -= 1;
+        }
+    }
+
+    /// Remove all combatants and reset the round counter
+    pub fn end(&mut self) {
+        self.combatants.clear();
+        self.turn = 0;
+        self.round = 0;
+    }
+}
+
+/// Roll a d20 and add the modifier
+pub fn roll(modifier: i32) -> i32 {
+    glib::random_int_range(1, 21) + modifier
+}
+
+/// Read the combat of the campaign at campaign_path, returns an empty combat if the campaign has
+/// no combat going on
+pub fn read_combat(campaign_path: &str) -> Result<Combat, DragonDisplayError> {
+    read_campaign_data(campaign_path, INITIATIVE_FILE)
+}
+
+/// Write the combat to the data folder of the campaign at campaign_path
+pub fn write_combat(campaign_path: &str, combat: &Combat) -> Result<(), DragonDisplayError> {
+    write_campaign_data(campaign_path, INITIATIVE_FILE, combat)
+}
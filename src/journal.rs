@@ -0,0 +1,221 @@
+use gtk::glib;
+use gtk::graphene::Rect;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{append_campaign_data, read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+
+/// Name of the folder in the campaign data folder that holds a folder for every session
+pub const SESSIONS_FOLDER: &str = "sessions";
+const JOURNAL_FILE: &str = "journal.toml";
+
+/// The media that was shown on the display
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum JournalMedia {
+    Image {
+        path: String,
+    },
+    Video {
+        path: String,
+    },
+    Vtt {
+        path: String,
+    },
+    Pdf {
+        path: String,
+        page: u32,
+        crop: Option<[f64; 4]>,
+    },
+    Reset,
+}
+
+/// A change of the display. The fog is the name of the file in the session folder that holds
+/// the fog of war rectangles when the media is a vtt
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub timestamp: i64,
+    /// The number of the display window the media was shown on, counting from 0
+    #[serde(default)]
+    pub display: usize,
+    pub media: JournalMedia,
+    /// The clockwise rotation of the media in degrees
+    pub rotation: f32,
+    pub fog: Option<String>,
+}
+
+/// Structure of the journal file of a session
+#[derive(Serialize, Deserialize, Default)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+}
+
+/// Structure of a fog snapshot file, every rectangle is stored as [x, y, width, height]
+#[derive(Serialize, Deserialize, Default)]
+struct FogSnapshot {
+    rectangles: Vec<[f32; 4]>,
+}
+
+/// The journal of the current session, every change is appended to the journal file in the
+/// session folder directly. The file keeps everything that was shown, while the entries only
+/// hold the history that can be walked with back and forward
+pub struct SessionJournal {
+    campaign_path: String,
+    name: String,
+    entries: Vec<JournalEntry>,
+    /// The index of the entry that is currently displayed
+    position: Option<usize>,
+    /// The number of entries that were written to the journal file
+    recorded: usize,
+}
+
+impl SessionJournal {
+    /// Start a new session for the campaign at campaign_path, the session is named after the
+    /// current time
+    pub fn new(campaign_path: String) -> Self {
+        let now = glib::DateTime::now_local().expect("Could not get the local time");
+        let name = match now.format("%Y-%m-%d_%H-%M-%S") {
+            Ok(time) => format!("session-{}", time),
+            Err(_) => format!("session-{}", now.to_unix()),
+        };
+        Self {
+            campaign_path,
+            name,
+            entries: Vec::new(),
+            position: None,
+            recorded: 0,
+        }
+    }
+
+    /// The name of the session, also the name of its folder
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add an entry for the media that is shown. The fog of war is saved in a seperate snapshot
+    /// file in the session folder
+    pub fn record(
+        &mut self,
+        display: usize,
+        media: JournalMedia,
+        rotation: f32,
+        fog: Option<Vec<Rect>>,
+    ) -> Result<(), DragonDisplayError> {
+        let fog = match fog {
+            Some(rectangles) => Some(self.write_fog(rectangles)?),
+            None => None,
+        };
+        let entry = JournalEntry {
+            timestamp: now_unix(),
+            display,
+            media,
+            rotation,
+            fog,
+        };
+        self.push(entry)
+    }
+
+    /// Add an entry for a new rotation of the media that is currently shown on the display
+    pub fn record_rotation(
+        &mut self,
+        display: usize,
+        rotation: f32,
+    ) -> Result<(), DragonDisplayError> {
+        // the entries after the displayed entry are truncated when the rotation is pushed
+        let shown = self.position.map_or(0, |p| p + 1);
+        let mut entry = match self.entries[..shown]
+            .iter()
+            .rev()
+            .find(|e| e.display == display)
+        {
+            Some(e) => e.clone(),
+            None => return Ok(()),
+        };
+        entry.timestamp = now_unix();
+        entry.rotation = rotation;
+        self.push(entry)
+    }
+
+    /// Move one entry back in the history, returns the entry that should be displayed
+    pub fn back(&mut self) -> Option<JournalEntry> {
+        let position = self.position?.checked_sub(1)?;
+        self.position = Some(position);
+        self.entries.get(position).cloned()
+    }
+
+    /// Move one entry forward in the history, returns the entry that should be displayed
+    pub fn forward(&mut self) -> Option<JournalEntry> {
+        let position = self.position? + 1;
+        let entry = self.entries.get(position).cloned()?;
+        self.position = Some(position);
+        Some(entry)
+    }
+
+    /// Read the fog of war that belongs to the entry
+    pub fn fog(&self, entry: &JournalEntry) -> Result<Vec<Rect>, DragonDisplayError> {
+        read_fog(&self.campaign_path, &self.name, entry)
+    }
+
+    /// Add the entry after the entry that is currently displayed, the entries after it were
+    /// stepped back from and are no longer part of the history
+    fn push(&mut self, entry: JournalEntry) -> Result<(), DragonDisplayError> {
+        if let Some(position) = self.position {
+            self.entries.truncate(position + 1);
+        }
+        self.entries.push(entry.clone());
+        self.position = Some(self.entries.len() - 1);
+        self.recorded += 1;
+        let journal = Journal {
+            entries: vec![entry],
+        };
+        append_campaign_data(&self.campaign_path, &journal_file(&self.name), &journal)
+    }
+
+    fn write_fog(&self, rectangles: Vec<Rect>) -> Result<String, DragonDisplayError> {
+        // the entries can be truncated, so the fog is named after the entries in the file
+        let file_name = format!("fog-{}.toml", self.recorded);
+        let snapshot = FogSnapshot {
+            rectangles: rectangles
+                .iter()
+                .map(|r| [r.x(), r.y(), r.width(), r.height()])
+                .collect(),
+        };
+        let path = format!("{}/{}/{}", SESSIONS_FOLDER, self.name, file_name);
+        write_campaign_data(&self.campaign_path, &path, &snapshot)?;
+        Ok(file_name)
+    }
+}
+
+/// Read the journal of the session with the given name
+pub fn read_journal(campaign_path: &str, session: &str) -> Result<Journal, DragonDisplayError> {
+    read_campaign_data(campaign_path, &journal_file(session))
+}
+
+/// Read the fog of war that belongs to an entry of the given session, returns an empty vector
+/// if the entry has no fog
+pub fn read_fog(
+    campaign_path: &str,
+    session: &str,
+    entry: &JournalEntry,
+) -> Result<Vec<Rect>, DragonDisplayError> {
+    let file_name = match &entry.fog {
+        Some(f) => f,
+        None => return Ok(Vec::new()),
+    };
+    let path = format!("{}/{}/{}", SESSIONS_FOLDER, session, file_name);
+    let snapshot: FogSnapshot = read_campaign_data(campaign_path, &path)?;
+    Ok(snapshot
+        .rectangles
+        .iter()
+        .map(|r| Rect::new(r[0], r[1], r[2], r[3]))
+        .collect())
+}
+
+fn journal_file(session: &str) -> String {
+    format!("{}/{}/{}", SESSIONS_FOLDER, session, JOURNAL_FILE)
+}
+
+fn now_unix() -> i64 {
+    glib::DateTime::now_local()
+        .map(|t| t.to_unix())
+        .unwrap_or_default()
+}
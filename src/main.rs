@@ -1,3 +1,5 @@
+use std::env;
+use std::path::PathBuf;
 use std::sync::OnceLock;
 
 //GUI crates
@@ -6,15 +8,35 @@ use gtk::prelude::*;
 use gtk::{gio, glib};
 
 //imported modules
+pub mod apng;
 pub mod campaign;
+pub mod color_grade;
 pub mod config;
 pub mod errors;
 pub mod fogofwar;
 pub mod gd_client;
+pub mod image_loader;
+pub mod initiative;
+pub mod journal;
+pub mod media;
+pub mod metadata;
+pub mod monitors;
+pub mod pdf;
+pub mod playlist;
 pub mod program;
+pub mod recap;
+pub mod recorder;
+pub mod renderer;
 pub mod setup;
+pub mod snapshot;
+pub mod text_card;
+pub mod thumbnail_cache;
+pub mod timer;
+pub mod transform;
 pub mod ui;
+pub mod video_thumbnail;
 pub mod videopipeline;
+pub mod weather;
 pub mod widgets;
 
 use setup::DragonDisplaySetup;
@@ -50,6 +72,16 @@ fn main() -> glib::ExitCode {
         .expect("Failed to register resources");
     let app: adw::Application = adw::Application::builder().application_id(APP_ID).build();
 
+    let mut args: Vec<String> = env::args().collect();
+    // `dragon_display --snapshot` asks the running program to save what the players see
+    if args.iter().skip(1).any(|arg| arg == "--snapshot") {
+        return request_snapshot(&app);
+    }
+    // `dragon_display --config-dir <folder>` keeps the campaigns in another config folder
+    if let Some(config_dir) = take_config_dir(&mut args) {
+        config::set_config_dir(config_dir);
+    }
+
     let setup = DragonDisplaySetup::new();
 
     app.connect_activate(clone!( @weak setup => move |app| {
@@ -62,5 +94,48 @@ fn main() -> glib::ExitCode {
         }
     ));
 
-    app.run()
+    app.run_with_args(&args)
+}
+
+/// Remove the --config-dir option from the arguments and return its folder, gtk does not know
+/// the option and would refuse to start
+fn take_config_dir(args: &mut Vec<String>) -> Option<PathBuf> {
+    let index = args
+        .iter()
+        .position(|arg| arg == "--config-dir" || arg.starts_with("--config-dir="))?;
+    let arg = args.remove(index);
+    let folder = match arg.strip_prefix("--config-dir=") {
+        Some(folder) => PathBuf::from(folder),
+        None if index < args.len() => PathBuf::from(args.remove(index)),
+        None => {
+            eprintln!("--config-dir needs a folder, using the default config folder");
+            return None;
+        }
+    };
+    // the working directory can change while running, so a relative folder is made absolute
+    match std::path::absolute(&folder) {
+        Ok(folder) => Some(folder),
+        Err(e) => {
+            eprintln!(
+                "Could not use the config folder {}: {}",
+                folder.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Ask the running program to save snapshots of its displays to the campaign folder
+fn request_snapshot(app: &adw::Application) -> glib::ExitCode {
+    if app.register(None::<&gio::Cancellable>).is_err() || !app.is_remote() {
+        eprintln!("Dragon Display is not running, start it before taking a snapshot");
+        return glib::ExitCode::FAILURE;
+    }
+    app.activate_action("save-snapshot", None);
+    // the action is sent over dbus, make sure it is sent before the process exits
+    if let Some(connection) = app.dbus_connection() {
+        let _ = connection.flush_sync(None::<&gio::Cancellable>);
+    }
+    glib::ExitCode::SUCCESS
 }
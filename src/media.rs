@@ -0,0 +1,389 @@
+use std::cmp::{Ordering, Reverse};
+use std::fs::read_dir;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::config::{
+    read_campaign_data, write_campaign_data, PDF_EXTENSIONS, VIDEO_EXTENSIONS, VTT_EXTENSIONS,
+};
+use crate::errors::{DragonDisplayError, IOSnafu};
+use crate::image_loader::is_image;
+
+/// Name of the file in the campaign data folder that holds the order of the media
+const ORDER_FILE: &str = "order.toml";
+/// The maximum amount of files that is remembered as recently shown
+const MAX_RECENTLY_SHOWN: usize = 500;
+
+/// The media files of a campaign, seperated by the page they are shown on
+#[derive(Default)]
+pub struct CampaignMedia {
+    pub images: Vec<PathBuf>,
+    pub vtts: Vec<PathBuf>,
+    pub videos: Vec<PathBuf>,
+    pub pdfs: Vec<PathBuf>,
+}
+
+/// Reads all files in the given folder and its subfolders and seperates images, vtt files,
+/// videos and pdfs. Videos, vtt files and pdfs are recognized by their extension, images by
+/// their content
+pub fn seperate_media(campaign_path: &str) -> Result<CampaignMedia, DragonDisplayError> {
+    let files = campaign_files(Path::new(campaign_path)).context(IOSnafu {
+        msg: "Could not read the campaign directory".to_string(),
+    })?;
+
+    let mut media = CampaignMedia::default();
+    for file in files.into_iter().filter(|f| f.to_str().is_some()) {
+        if has_extension(&file, &VTT_EXTENSIONS) {
+            media.vtts.push(file);
+        } else if has_extension(&file, &VIDEO_EXTENSIONS) {
+            media.videos.push(file);
+        } else if has_extension(&file, &PDF_EXTENSIONS) {
+            media.pdfs.push(file);
+        } else if is_image(&file) {
+            media.images.push(file);
+        }
+    }
+    Ok(media)
+}
+
+/// Returns true if the extension of the file is one of the extensions, ignoring case
+pub fn has_extension(file: &Path, extensions: &[&str]) -> bool {
+    file.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+}
+
+/// Returns true if dragon display can show the file
+pub fn is_media(file: &Path) -> bool {
+    has_extension(file, &VTT_EXTENSIONS)
+        || has_extension(file, &VIDEO_EXTENSIONS)
+        || has_extension(file, &PDF_EXTENSIONS)
+        || is_image(file)
+}
+
+/// Returns all files in the folder and its subfolders. Hidden files and folders (starting with
+/// a dot) and symbolic links to folders are skipped
+pub fn campaign_files(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    add_files(folder, &mut files, true)?;
+    Ok(files)
+}
+
+fn add_files(folder: &Path, files: &mut Vec<PathBuf>, top_level: bool) -> io::Result<()> {
+    let entries = match read_dir(folder) {
+        Ok(e) => e,
+        // an unreadable subfolder should not make the entire campaign unreadable
+        Err(_) if !top_level => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let hidden = entry
+            .file_name()
+            .to_str()
+            .map_or(true, |name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        let file_type = match entry.file_type() {
+            Ok(t) => t,
+            Err(_) => continue,
+        };
+        if file_type.is_dir() {
+            add_files(&entry.path(), files, false)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the folder and all its subfolders, hidden folders and symbolic links to folders are
+/// skipped
+pub fn campaign_folders(folder: &Path) -> Vec<PathBuf> {
+    let mut folders = vec![folder.to_path_buf()];
+    let mut i = 0;
+    while let Some(current) = folders.get(i).cloned() {
+        i += 1;
+        let entries = match read_dir(&current) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let hidden = entry
+                .file_name()
+                .to_str()
+                .map_or(true, |name| name.starts_with('.'));
+            if !hidden && entry.file_type().map_or(false, |t| t.is_dir()) {
+                folders.push(entry.path());
+            }
+        }
+    }
+    folders
+}
+
+/// Returns the category of the file, which is the path of its folder relative to the campaign
+/// folder (for example "Act 1/Taverns"). Files in the campaign folder itself have an empty
+/// category
+pub fn category(campaign_path: &str, file: &Path) -> String {
+    let folder = match file.parent() {
+        Some(f) => f,
+        None => return String::new(),
+    };
+    let relative = match folder.strip_prefix(campaign_path) {
+        Ok(r) => r,
+        Err(_) => return String::new(),
+    };
+    relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// Returns whether the file is in the category or one of its subcategories. Every file is in
+/// the empty category
+pub fn in_category(campaign_path: &str, file: &Path, category_name: &str) -> bool {
+    if category_name.is_empty() {
+        return true;
+    }
+    let file_category = category(campaign_path, file);
+    file_category == category_name || file_category.starts_with(&format!("{}/", category_name))
+}
+
+/// Returns a sorted list of all categories of the files, including the parent categories of
+/// nested categories
+pub fn categories<'a>(
+    campaign_path: &str,
+    files: impl Iterator<Item = &'a PathBuf>,
+) -> Vec<String> {
+    let mut categories: Vec<String> = Vec::new();
+    for file in files {
+        let file_category = category(campaign_path, file);
+        let mut parent = String::new();
+        for part in file_category.split('/').filter(|p| !p.is_empty()) {
+            if !parent.is_empty() {
+                parent.push('/');
+            }
+            parent.push_str(part);
+            if !categories.contains(&parent) {
+                categories.push(parent.clone());
+            }
+        }
+    }
+    categories.sort();
+    categories
+}
+
+/// Returns whether the file matches the search query. Every word of the query should be part of
+/// the file name or one of the tags, upper and lower case are ignored. Every file matches an
+/// empty query
+pub fn matches_search(file: &Path, tags: &[String], query: &str) -> bool {
+    let name = match file.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n.to_lowercase(),
+        None => return false,
+    };
+    let tags: Vec<String> = tags.iter().map(|t| t.to_lowercase()).collect();
+    query.split_whitespace().all(|word| {
+        let word = word.to_lowercase();
+        name.contains(&word) || tags.iter().any(|t| t.contains(&word))
+    })
+}
+
+/// The path of the file relative to the campaign folder with forward slashes
+pub fn relative_path(campaign_path: &str, file: &Path) -> String {
+    let relative = file.strip_prefix(campaign_path).unwrap_or(file);
+    relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// The order in which the media is shown in the control window
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortMode {
+    /// Natural sort of the file names, so "map 2" comes before "map 10"
+    #[default]
+    Name,
+    /// The last modified file first
+    Modified,
+    /// The largest file first
+    Size,
+    /// The last shown file first
+    RecentlyShown,
+    /// The order in which the files were dragged
+    Manual,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 5] = [
+        SortMode::Name,
+        SortMode::Modified,
+        SortMode::Size,
+        SortMode::RecentlyShown,
+        SortMode::Manual,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::Modified => "Last modified",
+            SortMode::Size => "Size",
+            SortMode::RecentlyShown => "Recently shown",
+            SortMode::Manual => "Manual",
+        }
+    }
+}
+
+/// The sort mode of a campaign, with the manual order and the recently shown files. Files are
+/// stored by their path relative to the campaign folder
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MediaOrder {
+    #[serde(default)]
+    pub mode: SortMode,
+    #[serde(default)]
+    manual: Vec<String>,
+    /// The most recently shown file first
+    #[serde(default)]
+    recently_shown: Vec<String>,
+}
+
+impl MediaOrder {
+    /// Read the order of the campaign, a campaign without order file is sorted by name
+    pub fn read(campaign_path: &str) -> Result<Self, DragonDisplayError> {
+        read_campaign_data(campaign_path, ORDER_FILE)
+    }
+
+    /// Write the order to the campaign data folder
+    pub fn write(&self, campaign_path: &str) -> Result<(), DragonDisplayError> {
+        write_campaign_data(campaign_path, ORDER_FILE, self)
+    }
+
+    /// Sort the files according to the sort mode. Files without a manual position or that were
+    /// never shown are placed after the others, sorted by name
+    pub fn sort(&self, campaign_path: &str, files: &mut [PathBuf]) {
+        match self.mode {
+            SortMode::Name => files.sort_by(|a, b| compare_names(a, b)),
+            SortMode::Modified => {
+                let modified = |f: &Path| f.metadata().and_then(|m| m.modified()).ok();
+                files
+                    .sort_by_cached_key(|f| Reverse(modified(f).unwrap_or(SystemTime::UNIX_EPOCH)));
+            }
+            SortMode::Size => {
+                files.sort_by_cached_key(|f| Reverse(f.metadata().map_or(0, |m| m.len())))
+            }
+            SortMode::RecentlyShown => {
+                self.sort_by_list(campaign_path, files, &self.recently_shown)
+            }
+            SortMode::Manual => self.sort_by_list(campaign_path, files, &self.manual),
+        }
+    }
+
+    fn sort_by_list(&self, campaign_path: &str, files: &mut [PathBuf], list: &[String]) {
+        // the sort is stable, so files that are not in the list stay sorted by name
+        files.sort_by(|a, b| compare_names(a, b));
+        files.sort_by_cached_key(|f| {
+            let file = relative_path(campaign_path, f);
+            list.iter().position(|l| *l == file).unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Remember that the file was shown, returns false if the file already was the most
+    /// recently shown file so the order did not change
+    pub fn shown(&mut self, campaign_path: &str, file: &Path) -> bool {
+        let file = relative_path(campaign_path, file);
+        if self.recently_shown.first() == Some(&file) {
+            return false;
+        }
+        self.recently_shown.retain(|f| *f != file);
+        self.recently_shown.insert(0, file);
+        self.recently_shown.truncate(MAX_RECENTLY_SHOWN);
+        true
+    }
+
+    /// Switch to the manual sort mode and move the file to the position of the target file.
+    /// The files are all files in the current order, which becomes the start of the manual order
+    pub fn move_file(
+        &mut self,
+        campaign_path: &str,
+        files: &[PathBuf],
+        file: &Path,
+        target: &Path,
+    ) {
+        let mut order: Vec<String> = files
+            .iter()
+            .map(|f| relative_path(campaign_path, f))
+            .collect();
+        // keep the manual position of files that are not part of the files
+        for f in &self.manual {
+            if !order.contains(f) {
+                order.push(f.clone());
+            }
+        }
+        let file = relative_path(campaign_path, file);
+        let target = relative_path(campaign_path, target);
+        let from = order.iter().position(|f| *f == file);
+        let to = order.iter().position(|f| *f == target);
+        if let (Some(from), Some(to)) = (from, to) {
+            let moved = order.remove(from);
+            order.insert(to, moved);
+        }
+        self.manual = order;
+        self.mode = SortMode::Manual;
+    }
+}
+
+/// Compare the file names case insensitive, with the numbers in the names compared by value
+fn compare_names(a: &Path, b: &Path) -> Ordering {
+    let a = a.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let b = b.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    natural_cmp(&a.to_lowercase(), &b.to_lowercase())
+}
+
+/// Compare two strings where sequences of digits are compared as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x_number = take_number(&mut a_chars);
+                let y_number = take_number(&mut b_chars);
+                // compare the length first, so numbers of any size can be compared
+                let ordering = x_number
+                    .len()
+                    .cmp(&y_number.len())
+                    .then_with(|| x_number.cmp(&y_number));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Take a sequence of digits from the characters, without leading zeros
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+        chars.next();
+    }
+    number
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+use crate::media::relative_path;
+
+/// Name of the file in the campaign data folder that holds the metadata of the media files
+const METADATA_FILE: &str = "metadata.toml";
+
+/// The tags, favorite status and notes of a media file. The notes are only shown in the control
+/// window
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MediaMetadata {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl MediaMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns a description of the tags and notes, or None if there are none
+    pub fn description(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if !self.tags.is_empty() {
+            lines.push(format!("Tags: {}", self.tags.join(", ")));
+        }
+        if !self.notes.is_empty() {
+            lines.push(self.notes.clone());
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+/// The metadata of all media files in a campaign. The files are stored by their path relative to
+/// the campaign folder, metadata of files that no longer exist is kept so it is restored when
+/// the file returns (for example after a google drive synchronization)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CampaignMetadata {
+    #[serde(default)]
+    media: BTreeMap<String, MediaMetadata>,
+}
+
+impl CampaignMetadata {
+    /// Read the metadata of the campaign, a campaign without metadata file has no metadata
+    pub fn read(campaign_path: &str) -> Result<Self, DragonDisplayError> {
+        read_campaign_data(campaign_path, METADATA_FILE)
+    }
+
+    /// Write the metadata to the campaign data folder
+    pub fn write(&self, campaign_path: &str) -> Result<(), DragonDisplayError> {
+        write_campaign_data(campaign_path, METADATA_FILE, self)
+    }
+
+    /// The metadata of the file, files without metadata return the default metadata
+    pub fn get(&self, campaign_path: &str, file: &Path) -> MediaMetadata {
+        self.media
+            .get(&relative_path(campaign_path, file))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Set the metadata of the file, empty metadata is removed
+    pub fn set(&mut self, campaign_path: &str, file: &Path, metadata: MediaMetadata) {
+        let key = relative_path(campaign_path, file);
+        if metadata.is_empty() {
+            self.media.remove(&key);
+        } else {
+            self.media.insert(key, metadata);
+        }
+    }
+}
+
+/// Split a comma seperated list of tags, empty tags and duplicates are removed
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
@@ -0,0 +1,143 @@
+use gdk4::{Display, Monitor};
+use gtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+
+/// Name of the file in the campaign data folder that holds the monitors of the display windows
+const MONITOR_FILE: &str = "monitors.toml";
+
+/// Identifies a monitor across restarts and reconnects. The index of a monitor changes when
+/// monitors are plugged in or out, so the connector, manufacturer and model are used instead
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MonitorId {
+    pub connector: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+/// The monitors that the display windows of a campaign were opened on last time, in the order
+/// of the display windows
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CampaignMonitors {
+    #[serde(default)]
+    pub displays: Vec<MonitorId>,
+    /// Whether the virtual display was enabled when the monitors were chosen
+    #[serde(default)]
+    pub virtual_display: bool,
+}
+
+impl MonitorId {
+    pub fn from_monitor(monitor: &Monitor) -> Self {
+        Self {
+            connector: monitor.connector().map(|c| c.to_string()),
+            manufacturer: monitor.manufacturer().map(|m| m.to_string()),
+            model: monitor.model().map(|m| m.to_string()),
+        }
+    }
+
+    /// How well the monitor matches this id, None if it does not match. The same monitor on
+    /// the same connector matches best, the same monitor plugged into another connector matches
+    /// next and a monitor without model information only matches on its connector
+    fn score(&self, monitor: &Monitor) -> Option<u32> {
+        let other = Self::from_monitor(monitor);
+        let same_connector = self.connector.is_some() && self.connector == other.connector;
+        let same_model = (self.manufacturer.is_some() || self.model.is_some())
+            && self.manufacturer == other.manufacturer
+            && self.model == other.model;
+        match (same_connector, same_model) {
+            (true, true) => Some(3),
+            (false, true) => Some(2),
+            (true, false) if other.model.is_none() && self.model.is_none() => Some(1),
+            _ => None,
+        }
+    }
+
+    /// The connected monitor that matches this id best. Monitors that are taken by other display
+    /// windows are skipped, so two display windows never share a monitor
+    pub fn find(&self, monitors: &[Monitor], taken: &[Monitor]) -> Option<Monitor> {
+        monitors
+            .iter()
+            .filter(|m| !taken.contains(m))
+            .filter_map(|m| Some((self.score(m)?, m)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, m)| m.clone())
+    }
+}
+
+impl CampaignMonitors {
+    /// Read the monitors of the campaign, a campaign without monitor file has no monitors
+    pub fn read(campaign_path: &str) -> Result<Self, DragonDisplayError> {
+        read_campaign_data(campaign_path, MONITOR_FILE)
+    }
+
+    /// Write the monitors to the campaign data folder
+    pub fn write(&self, campaign_path: &str) -> Result<(), DragonDisplayError> {
+        write_campaign_data(campaign_path, MONITOR_FILE, self)
+    }
+
+    pub fn from_monitors(monitors: &[Monitor], virtual_display: bool) -> Self {
+        Self {
+            displays: monitors.iter().map(MonitorId::from_monitor).collect(),
+            virtual_display,
+        }
+    }
+
+    /// The connected monitors in the order of the display windows. Returns None if one of the
+    /// remembered monitors is not connected or if the virtual display was switched on or off
+    /// since the monitors were chosen, then the monitors have to be chosen again
+    pub fn find(&self, monitors: &[Monitor], virtual_display: bool) -> Option<Vec<Monitor>> {
+        if self.virtual_display != virtual_display {
+            return None;
+        }
+        // without monitors there is nothing to display on, unless the virtual display is used
+        if self.displays.is_empty() && !virtual_display {
+            return None;
+        }
+        let found = self.connected(monitors);
+        (found.len() == self.displays.len()).then_some(found)
+    }
+
+    /// The remembered monitors that are still connected, in the order of the display windows
+    pub fn connected(&self, monitors: &[Monitor]) -> Vec<Monitor> {
+        let mut found = Vec::new();
+        for id in &self.displays {
+            if let Some(monitor) = id.find(monitors, &found) {
+                found.push(monitor);
+            }
+        }
+        found
+    }
+}
+
+/// All monitors that are connected to the default display
+pub fn connected_monitors() -> Vec<Monitor> {
+    let Some(display) = Display::default() else {
+        return Vec::new();
+    };
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i).and_downcast::<Monitor>())
+        .collect()
+}
+
+/// A label that tells the monitors apart, for example "HDMI-1: Samsung U28E590 (62cmx34cm)"
+pub fn monitor_label(monitor: &Monitor) -> String {
+    let size = format!(
+        "{}cmx{}cm",
+        monitor.width_mm() / 10,
+        monitor.height_mm() / 10
+    );
+    let name: Vec<String> = [monitor.manufacturer(), monitor.model()]
+        .into_iter()
+        .flatten()
+        .map(|n| n.to_string())
+        .collect();
+    match (monitor.connector(), name.is_empty()) {
+        (Some(connector), false) => format!("{}: {} ({})", connector, name.join(" "), size),
+        (Some(connector), true) => format!("{} ({})", connector, size),
+        (None, false) => format!("{} ({})", name.join(" "), size),
+        (None, true) => size,
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::Path;
+
+use async_channel::Sender;
+use gtk::cairo::{Context, Format, ImageSurface};
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+use gtk::{gio, glib};
+use poppler::Document;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+
+use crate::errors::{DragonDisplayError, GlibSnafu, OtherSnafu};
+use crate::thumbnail_cache::{cache_file, store, THUMBNAIL_SIZE};
+
+/// A page of a pdf as it is shown on the display. The crop is the shown region of the page as
+/// [x, y, width, height] in fractions of the page size
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdPdfPage")]
+pub struct PdfPage {
+    pub path: String,
+    pub page: u32,
+    pub crop: Option<[f64; 4]>,
+}
+
+impl PdfPage {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            page: 0,
+            crop: None,
+        }
+    }
+}
+
+/// Returns the amount of pages in the pdf
+pub fn page_count(path: &Path) -> Result<u32, DragonDisplayError> {
+    Ok(open(path)?.n_pages().max(0) as u32)
+}
+
+/// Render the page of the pdf so that its largest side is size pixels. Only the cropped region
+/// of the page is rendered, so a crop is as sharp as the full page
+pub fn render_page(
+    path: &Path,
+    page: u32,
+    crop: Option<[f64; 4]>,
+    size: i32,
+) -> Result<Pixbuf, DragonDisplayError> {
+    render(&open(path)?, path, page, crop, size)
+}
+
+/// Returns the png data of the thumbnail of the page. The thumbnail is read from the cache if
+/// it exists. This function blocks, so it should not be called on the main thread
+pub fn page_thumbnail(path: &Path, page: u32) -> Result<Vec<u8>, DragonDisplayError> {
+    let cache_file = cache_file(path, &format!("pdf-page-{}", page))?;
+    if let Ok(data) = fs::read(&cache_file) {
+        return Ok(data);
+    }
+    let pixbuf = render_page(path, page, None, THUMBNAIL_SIZE)?;
+    store(&cache_file, &pixbuf)
+}
+
+/// Send the png data of the thumbnails of all pages of the pdf, one page at a time. Stops when
+/// the receiver is closed. This function blocks, so it should not be called on the main thread
+pub fn send_page_thumbnails(path: &Path, sender: Sender<(u32, Vec<u8>)>) {
+    let Ok(document) = open(path) else {
+        return;
+    };
+    for page in 0..document.n_pages().max(0) as u32 {
+        if sender.is_closed() {
+            return;
+        }
+        let Ok(cache_file) = cache_file(path, &format!("pdf-page-{}", page)) else {
+            return;
+        };
+        let data = match fs::read(&cache_file) {
+            Ok(data) => data,
+            Err(_) => match render(&document, path, page, None, THUMBNAIL_SIZE)
+                .and_then(|pixbuf| store(&cache_file, &pixbuf))
+            {
+                Ok(data) => data,
+                Err(_) => continue,
+            },
+        };
+        if sender.send_blocking((page, data)).is_err() {
+            return;
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<Document, DragonDisplayError> {
+    let uri = gio::File::for_path(path).uri();
+    Document::from_file(&uri, None).context(GlibSnafu {
+        msg: format!("Could not open the pdf {}", path.display()),
+    })
+}
+
+fn render(
+    document: &Document,
+    path: &Path,
+    page: u32,
+    crop: Option<[f64; 4]>,
+    size: i32,
+) -> Result<Pixbuf, DragonDisplayError> {
+    let cairo_error = || OtherSnafu {
+        msg: format!("Could not render page {} of {}", page + 1, path.display()),
+    };
+    let pdf_page = document.page(page as i32).with_context(|| OtherSnafu {
+        msg: format!("The pdf {} has no page {}", path.display(), page + 1),
+    })?;
+    let (page_width, page_height) = pdf_page.size();
+    let [x, y, width, height] = crop.unwrap_or([0.0, 0.0, 1.0, 1.0]);
+    let (region_width, region_height) = (page_width * width, page_height * height);
+    let scale = size as f64 / region_width.max(region_height).max(1.0);
+    let surface_width = ((region_width * scale).round() as i32).max(1);
+    let surface_height = ((region_height * scale).round() as i32).max(1);
+
+    let surface = ImageSurface::create(Format::Rgb24, surface_width, surface_height)
+        .ok()
+        .with_context(cairo_error)?;
+    {
+        let context = Context::new(&surface).ok().with_context(cairo_error)?;
+        // pdf pages are drawn on white paper
+        context.set_source_rgb(1.0, 1.0, 1.0);
+        context.paint().ok().with_context(cairo_error)?;
+        context.scale(scale, scale);
+        context.translate(-page_width * x, -page_height * y);
+        pdf_page.render(&context);
+    }
+    surface.flush();
+
+    let stride = surface.stride() as usize;
+    let data = surface.take_data().ok().with_context(cairo_error)?;
+    // cairo stores the pixels as native endian 32 bit xrgb values
+    let mut rgb = Vec::with_capacity(surface_width as usize * surface_height as usize * 3);
+    for row in data.chunks(stride).take(surface_height as usize) {
+        for pixel in row[..surface_width as usize * 4].chunks_exact(4) {
+            let value = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            rgb.extend_from_slice(&[(value >> 16) as u8, (value >> 8) as u8, value as u8]);
+        }
+    }
+    Ok(Pixbuf::from_mut_slice(
+        rgb,
+        Colorspace::Rgb,
+        false,
+        8,
+        surface_width,
+        surface_height,
+        surface_width * 3,
+    ))
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data, VIDEO_EXTENSIONS};
+use crate::errors::DragonDisplayError;
+
+/// Name of the file in the campaign data folder that stores the playlists
+pub const PLAYLIST_FILE: &str = "playlists.toml";
+/// The amount of seconds an image is shown when it is added to a playlist
+pub const DEFAULT_ITEM_DURATION: u32 = 10;
+pub const MIN_ITEM_DURATION: f64 = 1.0;
+pub const MAX_ITEM_DURATION: f64 = 3600.0;
+
+/// Structure representing the playlists of a campaign as they are stored in the data folder
+#[derive(Serialize, Deserialize, Default)]
+struct Playlists {
+    playlists: Vec<Playlist>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Playlist {
+    pub name: String,
+    pub looping: bool,
+    pub items: Vec<PlaylistItem>,
+}
+
+/// A media file in a playlist. The duration (in seconds) is only used for images, videos advance
+/// when they reach their end
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlaylistItem {
+    pub path: String,
+    pub duration: u32,
+}
+
+impl Playlist {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            looping: false,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl PlaylistItem {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            duration: DEFAULT_ITEM_DURATION,
+        }
+    }
+
+    /// Returns whether the item is a video file
+    pub fn is_video(&self) -> bool {
+        Path::new(&self.path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e))
+    }
+
+    /// The time the item should be shown
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration as u64)
+    }
+
+    /// The name of the file of the item
+    pub fn file_name(&self) -> String {
+        Path::new(&self.path)
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or(&self.path)
+            .to_string()
+    }
+}
+
+/// Read the playlists of the campaign at campaign_path, returns an empty vector if the campaign
+/// has no playlists
+pub fn read_playlists(campaign_path: &str) -> Result<Vec<Playlist>, DragonDisplayError> {
+    let playlists: Playlists = read_campaign_data(campaign_path, PLAYLIST_FILE)?;
+    Ok(playlists.playlists)
+}
+
+/// Write the playlists to the data folder of the campaign at campaign_path
+pub fn write_playlists(
+    campaign_path: &str,
+    playlists: &[Playlist],
+) -> Result<(), DragonDisplayError> {
+    let playlists = Playlists {
+        playlists: playlists.to_vec(),
+    };
+    write_campaign_data(campaign_path, PLAYLIST_FILE, &playlists)
+}
@@ -1,26 +1,54 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use gdk4::Monitor;
 use glib::subclass::*;
+use gtk::gio;
 use gtk::gio::prelude::SettingsExt;
 use gtk::glib::prelude::*;
 use gtk::glib::{self, clone};
-use gtk::prelude::{ApplicationExt, GtkWindowExt};
+use gtk::graphene::Rect;
+use gtk::prelude::{ActionMapExt, ApplicationExt, GtkWindowExt};
 use gtk::subclass::prelude::*;
-use snafu::Report;
-
-use crate::errors::DragonDisplayError;
+use snafu::{OptionExt, Report, ResultExt};
+use tokio::task::JoinHandle;
+
+use crate::errors::{DragonDisplayError, IOSnafu, OtherSnafu};
+use crate::journal::{JournalEntry, JournalMedia, SessionJournal};
+use crate::pdf::PdfPage;
+use crate::recap::{export_recap, load_recap};
+use crate::recorder::{recording_path, Recorder, RecordingSettings};
+use crate::snapshot::{save_snapshot, snapshot_path};
+use crate::transform::{DisplayTransform, Rotation};
 use crate::ui::control_window::DdControlWindow;
 use crate::ui::display_window::DdDisplayWindow;
 use crate::ui::options::{ColorPreset, DdOptionsWindow};
-use crate::{try_emit, APP_ID};
+use crate::{runtime, try_emit, APP_ID};
 mod imp {
 
-    use std::{cell::OnceCell, sync::OnceLock};
+    use std::{
+        cell::{Cell, OnceCell, RefCell},
+        sync::OnceLock,
+    };
+
+    use crate::journal::SessionJournal;
+    use crate::recorder::Recorder;
 
     use super::*;
     #[derive(Default)]
     pub struct DragonDisplayProgram {
         pub control_window: OnceCell<DdControlWindow>,
-        pub display_window: OnceCell<DdDisplayWindow>,
+        /// The display windows in the order of their monitors
+        pub display_windows: OnceCell<Vec<DdDisplayWindow>>,
+        /// The display that plays the video of a playlist
+        pub video_display: Cell<Option<usize>>,
+        pub campaign_path: OnceCell<String>,
+        /// The journal of everything that is shown on the display during this session
+        pub journal: RefCell<Option<SessionJournal>>,
+        /// The recording of the display that is running
+        pub recorder: RefCell<Option<Recorder>>,
+        /// The timeout that adds the frames of the display to the recording
+        pub recording_source: RefCell<Option<glib::SourceId>>,
     }
 
     #[glib::object_subclass]
@@ -55,12 +83,36 @@ impl DragonDisplayProgram {
         obj
     }
 
-    pub fn run(&self, app: &adw::Application, monitor: &Monitor, campaign: String) {
+    /// Open the control window and a display window on every monitor. The display windows are
+    /// numbered in the order of the monitors, the virtual display comes after them if it is
+    /// enabled
+    pub fn run(&self, app: &adw::Application, monitors: &[Monitor], campaign: String) {
+        self.imp()
+            .journal
+            .replace(Some(SessionJournal::new(campaign.clone())));
+        self.imp()
+            .campaign_path
+            .set(campaign.clone())
+            .expect("Expected campaign path to not be set");
         let control_window = try_emit!(self, DdControlWindow::new(app, campaign), true);
-        let display_window = DdDisplayWindow::new(monitor);
+        let mut display_windows: Vec<DdDisplayWindow> =
+            monitors.iter().map(DdDisplayWindow::new).collect();
+        let mut names: Vec<String> = (1..=display_windows.len())
+            .map(|number| format!("Display {}", number))
+            .collect();
+        let settings = gtk::gio::Settings::new(APP_ID);
+        if settings.boolean("virtual-display") {
+            display_windows.push(DdDisplayWindow::new_virtual(
+                settings.int("virtual-display-width"),
+                settings.int("virtual-display-height"),
+            ));
+            names.push("Virtual display".to_string());
+        }
+        control_window.set_displays(&names);
         control_window.present();
-        display_window.present();
-        display_window.fullscreen_on_monitor(monitor);
+        for display_window in &display_windows {
+            display_window.show_on_monitor();
+        }
         control_window.set_maximized(true);
 
         control_window.connect_refresh(clone!(@weak self as obj => move |_| {
@@ -72,53 +124,191 @@ impl DragonDisplayProgram {
             obj.present_options(&app);
         }));
 
-        control_window.connect_fit(clone!(@weak display_window => move |_| {
-            display_window.toggle_fit();
+        control_window.connect_fit(clone!(@weak self as obj => move |_| {
+            for (_, display_window) in obj.targets() {
+                display_window.toggle_fit();
+            }
         }));
 
-        control_window.connect_reset_display(clone!(@weak display_window => move |_| {
-            display_window.reset();
+        control_window.connect_reset_display(clone!(@weak self as obj => move |_| {
+            for (display, display_window) in obj.targets() {
+                display_window.reset();
+                obj.record(display, JournalMedia::Reset, None);
+            }
         }));
 
-        control_window.connect_grid(clone!(@weak display_window => move |_| {
-            display_window.toggle_grid();
+        control_window.connect_grid(clone!(@weak self as obj => move |_| {
+            for (_, display_window) in obj.targets() {
+                display_window.toggle_grid();
+            }
         }));
 
-        control_window.connect_rotate90(clone!(@weak display_window => move |_| {
-            display_window.rotate_90();
+        control_window.connect_rotation(clone!(@weak self as obj => move |_, rotation, changed| {
+            for (display, display_window) in obj.targets() {
+                display_window.set_rotation(rotation);
+                // the rotation of newly shown media is written with the media itself
+                if changed {
+                    obj.record_rotation(display);
+                }
+            }
         }));
 
-        control_window.connect_rotate180(clone!(@weak display_window => move |_| {
-            display_window.rotate_180();
+        control_window.connect_update(clone!(@weak self as obj => move |_, path, fow| {
+            for (display, display_window) in obj.targets() {
+                display_window.set_vtt(path.clone(), fow.fow());
+                obj.record(display, JournalMedia::Vtt { path: path.clone() }, Some(fow.fow()));
+            }
         }));
 
-        control_window.connect_rotate270(clone!(@weak display_window => move |_| {
-            display_window.rotate_270();
+        control_window.connect_image(clone!(@weak self as obj => move |_, path| {
+            for (display, display_window) in obj.targets() {
+                display_window.set_image(path.clone());
+                obj.record(display, JournalMedia::Image { path: path.clone() }, None);
+            }
         }));
 
-        control_window.connect_update(clone!(@weak display_window => move |_, path, fow| {
-            display_window.set_vtt(path, fow.fow());
+        control_window.connect_video(clone!(@weak self as obj => move |_, path, looping| {
+            let targets = obj.targets();
+            // a playlist continues when the video on the first targeted display finished
+            obj.imp()
+                .video_display
+                .set(targets.first().map(|(display, _)| *display));
+            for (display, display_window) in targets {
+                display_window.set_video(path.clone(), looping);
+                obj.record(display, JournalMedia::Video { path: path.clone() }, None);
+            }
         }));
 
-        control_window.connect_image(clone!(@weak display_window => move |_, path| {
-            display_window.set_image(path);
+        control_window.connect_pdf(clone!(@weak self as obj => move |_, page| {
+            for (display, display_window) in obj.targets() {
+                display_window.set_pdf(&page);
+                let media = JournalMedia::Pdf {
+                    path: page.path.clone(),
+                    page: page.page,
+                    crop: page.crop,
+                };
+                obj.record(display, media, None);
+            }
         }));
 
-        control_window.connect_video(clone!(@weak display_window => move |_, path| {
-            display_window.set_video(path);
+        control_window.connect_show_card(clone!(@weak self as obj => move |_, card| {
+            for (_, display_window) in obj.targets() {
+                display_window.show_card(card.clone());
+            }
         }));
 
-        control_window.connect_error(clone!(@weak self as obj => move |_, msg, fatal| {
-            obj.emit_by_name::<()>("error", &[&msg, &fatal]);
+        control_window.connect_hide_card(clone!(@weak self as obj => move |_| {
+            for (_, display_window) in obj.targets() {
+                display_window.hide_card();
+            }
+        }));
+
+        control_window.connect_initiative(clone!(@weak self as obj => move |_, combat| {
+            for (_, display_window) in obj.targets() {
+                display_window.set_initiative(&combat);
+            }
+        }));
+
+        control_window.connect_timers(clone!(@weak self as obj => move |_, timers| {
+            for (_, display_window) in obj.targets() {
+                display_window.set_timers(&timers);
+            }
+        }));
+
+        control_window.connect_weather(clone!(@weak self as obj => move |_, settings| {
+            for (_, display_window) in obj.targets() {
+                display_window.set_weather(settings);
+            }
         }));
 
-        display_window.connect_error(clone!(@weak self as obj => move |_, msg, fatal| {
+        control_window.connect_grade(clone!(@weak self as obj => move |_, grade, animate| {
+            for (_, display_window) in obj.targets() {
+                display_window.set_color_grade(grade, animate);
+            }
+        }));
+
+        control_window.connect_back(clone!(@weak self as obj => move |_| {
+            let entry = obj.imp().journal.borrow_mut().as_mut().and_then(|j| j.back());
+            if let Some(entry) = entry {
+                obj.show_entry(entry);
+            }
+        }));
+
+        control_window.connect_forward(clone!(@weak self as obj => move |_| {
+            let entry = obj.imp().journal.borrow_mut().as_mut().and_then(|j| j.forward());
+            if let Some(entry) = entry {
+                obj.show_entry(entry);
+            }
+        }));
+
+        control_window.connect_export_recap(clone!(@weak self as obj => move |_| {
+            obj.export_recap();
+        }));
+
+        control_window.connect_snapshot(clone!(@weak self as obj => move |_| {
+            obj.save_snapshots();
+        }));
+
+        control_window.connect_snapshot_as(clone!(@weak self as obj => move |_, path| {
+            let Some((display, display_window)) = obj.targets().into_iter().next() else {
+                return;
+            };
+            obj.save_snapshot(display, &display_window, PathBuf::from(path));
+        }));
+
+        control_window.connect_record(
+            clone!(@weak self as obj => move |control_window, recording| {
+                if !recording {
+                    obj.stop_recording();
+                    return;
+                }
+                if let Err(err) = obj.start_recording() {
+                    obj.emit_error(err, false);
+                    control_window.set_recording(false);
+                }
+            }),
+        );
+
+        // the snapshot can also be taken from outside of the program with the --snapshot option
+        let snapshot_action = gio::SimpleAction::new("save-snapshot", None);
+        snapshot_action.connect_activate(clone!(@weak self as obj => move |_, _| {
+            obj.save_snapshots();
+        }));
+        app.add_action(&snapshot_action);
+
+        control_window.connect_paused(clone!(@weak self as obj => move |_, paused| {
+            for (_, display_window) in obj.targets() {
+                display_window.set_paused(paused);
+            }
+        }));
+
+        control_window.connect_error(clone!(@weak self as obj => move |_, msg, fatal| {
             obj.emit_by_name::<()>("error", &[&msg, &fatal]);
         }));
 
+        for (display, display_window) in display_windows.iter().enumerate() {
+            display_window.connect_video_finished(
+                clone!(@weak self as obj, @weak control_window => move |_| {
+                    if obj.imp().video_display.get() == Some(display) {
+                        control_window.video_finished();
+                    }
+                }),
+            );
+
+            display_window.connect_error(clone!(@weak self as obj => move |_, msg, fatal| {
+                obj.emit_by_name::<()>("error", &[&msg, &fatal]);
+            }));
+        }
+
         control_window.connect_close_request(
-            clone!(@weak app, @weak display_window, @strong control_window => @default-return glib::Propagation::Proceed, move |_| {
-                display_window.destroy();
+            clone!(@weak app, @weak self as obj, @strong control_window => @default-return glib::Propagation::Proceed, move |_| {
+                // the recording has to be complete before the program exits
+                if let Some(finished) = obj.stop_recording() {
+                    let _ = runtime().block_on(finished);
+                }
+                for display_window in obj.display_windows() {
+                    display_window.destroy();
+                }
                 control_window.destroy();
                 app.quit();
                 glib::Propagation::Proceed
@@ -130,9 +320,18 @@ impl DragonDisplayProgram {
             .set(control_window)
             .expect("Expected control window to not be set");
         self.imp()
-            .display_window
-            .set(display_window)
-            .expect("Expected control window to not be set");
+            .display_windows
+            .set(display_windows)
+            .expect("Expected display windows to not be set");
+    }
+
+    /// Watch the campaign folder so new, removed or renamed files show up without a refresh
+    pub fn watch_campaign_folder(&self) {
+        let control_window = match self.imp().control_window.get() {
+            Some(c) => c,
+            None => return,
+        };
+        try_emit!(self, control_window.watch_campaign_folder(), false);
     }
 
     /// Update the grid of thumbnails for the pages in the control window of the program
@@ -148,22 +347,229 @@ impl DragonDisplayProgram {
         );
     }
 
+    /// Write a change of a display to the journal of the session
+    fn record(&self, display: usize, media: JournalMedia, fog: Option<Vec<Rect>>) {
+        // the display is only rotated after the media is shown, so the rotation is taken from
+        // the controls
+        let rotation = match self.imp().control_window.get() {
+            Some(control_window) => control_window.media_rotation(),
+            None => self.display_windows()[display].rotation(),
+        }
+        .degrees();
+        let mut journal = self.imp().journal.borrow_mut();
+        let journal = journal.as_mut().expect("Expected a journal");
+        try_emit!(self, journal.record(display, media, rotation, fog), false);
+    }
+
+    /// Write a rotation of the current media of a display to the journal of the session
+    fn record_rotation(&self, display: usize) {
+        let rotation = self.display_windows()[display].rotation().degrees();
+        let mut journal = self.imp().journal.borrow_mut();
+        let journal = journal.as_mut().expect("Expected a journal");
+        try_emit!(self, journal.record_rotation(display, rotation), false);
+    }
+
+    /// Show an entry of the journal on its display without adding it to the journal
+    fn show_entry(&self, entry: JournalEntry) {
+        let display_window = match self.display_windows().get(entry.display) {
+            Some(w) => w,
+            None => return,
+        };
+        let rotation = Rotation::from_degrees(entry.rotation);
+        display_window.set_rotation(rotation);
+        if let Some(control_window) = self.imp().control_window.get() {
+            control_window.show_rotation(rotation);
+        }
+        match entry.media {
+            JournalMedia::Image { path } => display_window.set_image(path),
+            JournalMedia::Video { path } => display_window.set_video(path, true),
+            JournalMedia::Vtt { path } => {
+                let fog = {
+                    let journal = self.imp().journal.borrow();
+                    let journal = journal.as_ref().expect("Expected a journal");
+                    journal.fog(&entry)
+                };
+                let fog = try_emit!(self, fog, false);
+                display_window.set_vtt(path, fog);
+            }
+            JournalMedia::Pdf { path, page, crop } => {
+                display_window.set_pdf(&PdfPage { path, page, crop })
+            }
+            JournalMedia::Reset => display_window.reset(),
+        }
+    }
+
+    /// Export the journal of this session to a recap and open it, the media is loaded in the
+    /// background
+    fn export_recap(&self) {
+        let session = self
+            .imp()
+            .journal
+            .borrow()
+            .as_ref()
+            .expect("Expected a journal")
+            .name()
+            .to_string();
+        let campaign_path = self
+            .imp()
+            .campaign_path
+            .get()
+            .expect("Expected a campaign path")
+            .clone();
+        let (sender, receiver) = async_channel::bounded(1);
+        runtime().spawn_blocking(move || {
+            let _ = sender.send_blocking(load_recap(&campaign_path, &session));
+        });
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            let result = match receiver.recv().await {
+                Ok(r) => r,
+                Err(_) => return,
+            };
+            let recap = try_emit!(obj, result, false);
+            // the thumbnails are drawn on the main thread
+            let path = try_emit!(obj, export_recap(recap), false);
+            try_emit!(
+                obj,
+                open::that(&path).context(IOSnafu {
+                    msg: "Could not open the recap".to_owned(),
+                }),
+                false
+            );
+        }));
+    }
+
+    /// Save what the players see on the targeted displays to the snapshots folder of the campaign
+    fn save_snapshots(&self) {
+        let campaign_path = self
+            .imp()
+            .campaign_path
+            .get()
+            .expect("Expected a campaign path")
+            .clone();
+        for (display, display_window) in self.targets() {
+            let path = try_emit!(self, snapshot_path(&campaign_path, display), false);
+            self.save_snapshot(display, &display_window, path);
+        }
+    }
+
+    /// Save what the players see on the display to a png at the path
+    fn save_snapshot(&self, display: usize, display_window: &DdDisplayWindow, path: PathBuf) {
+        let frame = try_emit!(
+            self,
+            display_window.render_frame().context(OtherSnafu {
+                msg: format!("Display {} has nothing to save", display + 1),
+            }),
+            false
+        );
+        try_emit!(self, save_snapshot(&frame, &path), false);
+    }
+
+    /// Record the first targeted display to the recordings folder of the campaign
+    fn start_recording(&self) -> Result<(), DragonDisplayError> {
+        let imp = self.imp();
+        let (display, display_window) = self.targets().into_iter().next().context(OtherSnafu {
+            msg: "There is no display to record".to_string(),
+        })?;
+        let campaign_path = imp.campaign_path.get().expect("Expected a campaign path");
+        let settings = RecordingSettings::from_settings(&gio::Settings::new(APP_ID));
+        let path = recording_path(campaign_path, display, settings.format)?;
+        let recorder = Recorder::start(&path, settings)?;
+        let interval = Duration::from_secs_f64(1.0 / recorder.framerate() as f64);
+        imp.recorder.replace(Some(recorder));
+        let source = glib::timeout_add_local(
+            interval,
+            clone!(@weak self as obj => @default-return glib::ControlFlow::Break, move || {
+                let borrowed = obj.imp().recorder.borrow();
+                let Some(recorder) = borrowed.as_ref() else {
+                    return glib::ControlFlow::Break;
+                };
+                let (width, height) = recorder.size();
+                // a display without media has no frame, the last frame is repeated instead
+                let Some(frame) = display_window.render_frame_sized(width, height) else {
+                    return glib::ControlFlow::Continue;
+                };
+                let Err(err) = recorder.push_frame(&frame) else {
+                    return glib::ControlFlow::Continue;
+                };
+                drop(borrowed);
+                // the source is removed by returning break
+                obj.imp().recording_source.take();
+                obj.stop_recording();
+                obj.emit_error(err, false);
+                obj.imp()
+                    .control_window
+                    .get()
+                    .expect("Expected a control window")
+                    .set_recording(false);
+                glib::ControlFlow::Break
+            }),
+        );
+        imp.recording_source.replace(Some(source));
+        Ok(())
+    }
+
+    /// Stop the recording of the display if there is one. Returns a handle that finishes when
+    /// the recording is written
+    fn stop_recording(&self) -> Option<JoinHandle<()>> {
+        let imp = self.imp();
+        if let Some(source) = imp.recording_source.take() {
+            source.remove();
+        }
+        let recorder = imp.recorder.take()?;
+        Some(recorder.stop())
+    }
+
+    fn display_windows(&self) -> &[DdDisplayWindow] {
+        self.imp()
+            .display_windows
+            .get()
+            .expect("Expected display windows")
+    }
+
+    /// The display windows that the control window targets, with their number
+    fn targets(&self) -> Vec<(usize, DdDisplayWindow)> {
+        let control_window = self
+            .imp()
+            .control_window
+            .get()
+            .expect("Expected a control window");
+        let display_windows = self.display_windows();
+        control_window
+            .targets()
+            .into_iter()
+            .filter_map(|display| Some((display, display_windows.get(display)?.clone())))
+            .collect()
+    }
+
     fn present_options(&self, app: &adw::Application) {
         let options_window = DdOptionsWindow::new(app);
 
         options_window.connect_confirm(clone!(@weak self as obj => move |window| {
             obj.imp().control_window.get().expect("Expected a control window").set_options_sensitive(true);
             obj.update_thumbnail_grid();
+            for display_window in obj.display_windows() {
+                display_window.show_on_monitor();
+            }
             window.destroy();
         }));
 
         options_window.connect_color(clone!(@weak self as obj => move |_, color| {
             let color = ColorPreset::from_index(color).to_rgba();
-            obj.imp().display_window.get().expect("Expected a display window").update_grid_color(color); 
+            for display_window in obj.display_windows() {
+                display_window.update_grid_color(color);
+            }
         }));
 
         options_window.connect_grid_line_width(clone!(@weak self as obj => move |_, width| {
-            obj.imp().display_window.get().expect("Expected a display window").set_gridline_width(width);
+            for display_window in obj.display_windows() {
+                display_window.set_gridline_width(width);
+            }
+        }));
+
+        options_window.connect_transform(clone!(@weak self as obj => move |_, transform| {
+            for display_window in obj.display_windows() {
+                display_window.set_transform(transform);
+            }
         }));
 
         options_window.connect_close_request(
@@ -172,10 +578,13 @@ impl DragonDisplayProgram {
 
                 let index = settings.int("grid-color-preset") as u32;
                 let color = ColorPreset::from_index(index).to_rgba();
-                obj.imp().display_window.get().expect("Expected a display window").update_grid_color(color); 
-
                 let width = settings.double("grid-line-width") as f32;
-                obj.imp().display_window.get().expect("Expected a display window").set_gridline_width(width);
+                let transform = DisplayTransform::from_settings(&settings);
+                for display_window in obj.display_windows() {
+                    display_window.update_grid_color(color);
+                    display_window.set_gridline_width(width);
+                    display_window.set_transform(transform);
+                }
 
                 obj.imp().control_window.get().expect("Expected a control window").set_options_sensitive(true);
                 glib::Propagation::Proceed
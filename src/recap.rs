@@ -0,0 +1,277 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gdk4::Texture;
+use gtk::gdk_pixbuf::prelude::*;
+use gtk::gdk_pixbuf::{InterpType, Pixbuf, PixbufLoader};
+use gtk::glib;
+use gtk::graphene::Rect;
+use gtk::gsk;
+use snafu::{OptionExt, ResultExt};
+use vtt_rust::open_vtt;
+
+use crate::config::campaign_data_path;
+use crate::errors::{DragonDisplayError, GlibSnafu, IOSnafu, OtherSnafu};
+use crate::journal::{read_fog, read_journal, JournalEntry, JournalMedia};
+use crate::pdf::render_page;
+use crate::renderer::{compose, offscreen_renderer, render, DisplayState, Fit};
+use crate::transform::{DisplayTransform, Rotation};
+use crate::video_thumbnail::{video_offset, video_poster};
+
+/// Name of the folder in the campaign data folder that holds the exported recaps
+pub const RECAPS_FOLDER: &str = "recaps";
+/// The width in pixels of the thumbnails in a recap
+const THUMBNAIL_WIDTH: i32 = 480;
+
+/// An item of the recap, a media file that was shown during the session
+struct RecapItem {
+    time: String,
+    name: String,
+    thumbnail: Option<String>,
+}
+
+/// A media file of the recap, the media is scaled to the width of a thumbnail and drawn the way
+/// the display showed it when the recap is written
+struct RecapMedia {
+    time: String,
+    name: String,
+    media: Option<(Pixbuf, DisplayState)>,
+}
+
+/// The media that was shown during a session, loaded so the recap can be written
+pub struct Recap {
+    session: String,
+    folder: PathBuf,
+    media: Vec<RecapMedia>,
+}
+
+/// Load the media in the journal of the session. Loading the media is slow, so it can be done
+/// in the background, the recap is written with export_recap
+pub fn load_recap(campaign_path: &str, session: &str) -> Result<Recap, DragonDisplayError> {
+    let journal = read_journal(campaign_path, session)?;
+    let folder = campaign_data_path(campaign_path, &format!("{}/{}", RECAPS_FOLDER, session));
+
+    // Only the last state of every shown media is part of the recap
+    let mut shown: Vec<&JournalEntry> = Vec::new();
+    let mut previous: Option<&JournalMedia> = None;
+    for entry in &journal.entries {
+        if entry.media == JournalMedia::Reset {
+            previous = None;
+            continue;
+        }
+        if previous == Some(&entry.media) {
+            shown.pop();
+        }
+        shown.push(entry);
+        previous = Some(&entry.media);
+    }
+
+    let media = shown
+        .into_iter()
+        .map(|entry| RecapMedia {
+            time: format_time(entry.timestamp),
+            name: media_name(&entry.media),
+            media: load_media(campaign_path, session, entry).ok(),
+        })
+        .collect();
+    Ok(Recap {
+        session: session.to_owned(),
+        folder,
+        media,
+    })
+}
+
+/// Export the recap to a markdown and html file with thumbnails of the shown media. The
+/// thumbnails are drawn by gtk, so this has to run on the main thread. Returns the path of the
+/// html file
+pub fn export_recap(recap: Recap) -> Result<PathBuf, DragonDisplayError> {
+    let folder = recap.folder;
+    fs::create_dir_all(folder.join("thumbnails")).context(IOSnafu {
+        msg: "Could not create the recap folder".to_owned(),
+    })?;
+    let renderer = offscreen_renderer()?;
+
+    let mut items = Vec::new();
+    for (index, media) in recap.media.into_iter().enumerate() {
+        let thumbnail_name = format!("thumbnails/{}.png", index);
+        let thumbnail = media
+            .media
+            .and_then(|(pixbuf, state)| create_thumbnail(&renderer, &pixbuf, &state))
+            .and_then(|texture| texture.save_to_png(folder.join(&thumbnail_name)).ok())
+            .map(|_| thumbnail_name);
+        items.push(RecapItem {
+            time: media.time,
+            name: media.name,
+            thumbnail,
+        });
+    }
+
+    fs::write(folder.join("recap.md"), to_markdown(&recap.session, &items)).context(IOSnafu {
+        msg: "Could not write the markdown recap".to_owned(),
+    })?;
+    let html_path = folder.join("recap.html");
+    fs::write(&html_path, to_html(&recap.session, &items)).context(IOSnafu {
+        msg: "Could not write the html recap".to_owned(),
+    })?;
+    Ok(html_path)
+}
+
+/// Draw the media the way it was shown on the display, scaled to the width of a thumbnail
+fn create_thumbnail(
+    renderer: &gsk::Renderer,
+    pixbuf: &Pixbuf,
+    state: &DisplayState,
+) -> Option<Texture> {
+    let (node, width, height) = compose(&Texture::for_pixbuf(pixbuf), state)?;
+    let thumbnail_height = (height * THUMBNAIL_WIDTH as f32 / width.max(1.0)).round() as i32;
+    render(
+        renderer,
+        &node,
+        (width, height),
+        THUMBNAIL_WIDTH,
+        thumbnail_height.max(1),
+        Fit::Fill,
+    )
+}
+
+/// Load the media of the entry scaled to the width of a thumbnail, together with the state of
+/// the display that showed it
+fn load_media(
+    campaign_path: &str,
+    session: &str,
+    entry: &JournalEntry,
+) -> Result<(Pixbuf, DisplayState), DragonDisplayError> {
+    let mut fog_of_war = Vec::new();
+    let pixbuf = match &entry.media {
+        JournalMedia::Image { path } => Pixbuf::from_file(path).context(GlibSnafu {
+            msg: format!("Could not load image at {}", path),
+        })?,
+        JournalMedia::Video { path } => {
+            let poster = video_poster(Path::new(path), video_offset())?;
+            pixbuf_from_data(&poster.data, "Failed to load the poster of the video")?
+        }
+        JournalMedia::Vtt { path } => {
+            fog_of_war = read_fog(campaign_path, session, entry)?;
+            vtt_pixbuf(path)?
+        }
+        JournalMedia::Pdf { path, page, crop } => {
+            render_page(Path::new(path), *page, *crop, THUMBNAIL_WIDTH)?
+        }
+        JournalMedia::Reset => {
+            return Err(DragonDisplayError::Other {
+                msg: "A reset display has no thumbnail".to_owned(),
+            })
+        }
+    };
+
+    // the media is scaled down before it is composed, so the fog has to be scaled with it
+    let scale = THUMBNAIL_WIDTH as f32 / pixbuf.width().max(1) as f32;
+    let height = (pixbuf.height() as f32 * scale).round() as i32;
+    let pixbuf = pixbuf
+        .scale_simple(THUMBNAIL_WIDTH, height.max(1), InterpType::Bilinear)
+        .context(OtherSnafu {
+            msg: "Could not scale the thumbnail".to_owned(),
+        })?;
+    let state = DisplayState {
+        transform: DisplayTransform {
+            rotation: Rotation::from_degrees(entry.rotation),
+            ..Default::default()
+        },
+        fog_of_war: fog_of_war
+            .iter()
+            .map(|rect| {
+                Rect::new(
+                    rect.x() * scale,
+                    rect.y() * scale,
+                    rect.width() * scale,
+                    rect.height() * scale,
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+    Ok((pixbuf, state))
+}
+
+/// Load the image inside a vtt file
+fn vtt_pixbuf(path: &str) -> Result<Pixbuf, DragonDisplayError> {
+    let mut vtt = open_vtt(path).ok().context(OtherSnafu {
+        msg: "Failed to open vtt".to_string(),
+    })?;
+    let image = vtt.take_image().ok().context(OtherSnafu {
+        msg: "Failed to get image from vtt file".to_string(),
+    })?;
+    pixbuf_from_data(&image, "Failed to load the image in the vtt")
+}
+
+/// Load a pixbuf from the data of an image file
+fn pixbuf_from_data(data: &[u8], msg: &str) -> Result<Pixbuf, DragonDisplayError> {
+    let loader = PixbufLoader::new();
+    loader.write(data).context(GlibSnafu {
+        msg: msg.to_string(),
+    })?;
+    loader.close().context(GlibSnafu {
+        msg: msg.to_string(),
+    })?;
+    let pixbuf = loader.pixbuf().context(OtherSnafu {
+        msg: msg.to_string(),
+    })?;
+    Ok(pixbuf.copy().unwrap_or(pixbuf))
+}
+
+fn media_name(media: &JournalMedia) -> String {
+    let path = match media {
+        JournalMedia::Image { path }
+        | JournalMedia::Video { path }
+        | JournalMedia::Vtt { path }
+        | JournalMedia::Pdf { path, .. } => path,
+        JournalMedia::Reset => return String::new(),
+    };
+    Path::new(path)
+        .file_stem()
+        .and_then(|f| f.to_str())
+        .unwrap_or(path)
+        .to_string()
+}
+
+fn format_time(timestamp: i64) -> String {
+    glib::DateTime::from_unix_local(timestamp)
+        .ok()
+        .and_then(|t| t.format("%H:%M").ok())
+        .map(|t| t.to_string())
+        .unwrap_or_default()
+}
+
+fn to_markdown(session: &str, items: &[RecapItem]) -> String {
+    let mut markdown = format!("# Recap of {}\n\n", session);
+    for item in items {
+        markdown.push_str(&format!("## {} - {}\n\n", item.time, item.name));
+        if let Some(thumbnail) = &item.thumbnail {
+            markdown.push_str(&format!("![{}]({})\n\n", item.name, thumbnail));
+        }
+    }
+    markdown
+}
+
+fn to_html(session: &str, items: &[RecapItem]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Recap of {0}</title>\n</head>\n<body>\n<h1>Recap of {0}</h1>\n",
+        escape_html(session)
+    );
+    for item in items {
+        let name = escape_html(&item.name);
+        html.push_str(&format!("<h2>{} - {}</h2>\n", item.time, name));
+        if let Some(thumbnail) = &item.thumbnail {
+            html.push_str(&format!("<img src=\"{}\" alt=\"{}\">\n", thumbnail, name));
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
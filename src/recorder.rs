@@ -0,0 +1,288 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gdk4::Texture;
+use gstreamer::prelude::*;
+use gstreamer::{Caps, ClockTime, Element, ElementFactory, Fraction, MessageType, Pipeline, State};
+use gstreamer_app::AppSrc;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use snafu::{OptionExt, ResultExt};
+use tokio::task::JoinHandle;
+
+use crate::config::campaign_data_path;
+use crate::errors::{DragonDisplayError, IOSnafu, OtherSnafu};
+use crate::runtime;
+
+/// Name of the folder in the campaign data folder that holds the recordings of the display
+pub const RECORDINGS_FOLDER: &str = "recordings";
+/// The pulseaudio source that records everything that is played on the default output
+const AUDIO_MONITOR: &str = "@DEFAULT_MONITOR@";
+/// The time that a recording gets to write its last frames after it is stopped
+const FINISH_TIMEOUT: u64 = 10;
+
+/// The container of a recording, the order of this list should be equal to the order of the
+/// dropdown list defined in the options.ui
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordingFormat {
+    Webm,
+    Mp4,
+}
+
+/// The settings of a recording as they are stored in the settings of the application
+#[derive(Clone, Copy, Debug)]
+pub struct RecordingSettings {
+    pub width: i32,
+    pub height: i32,
+    pub framerate: i32,
+    pub format: RecordingFormat,
+    /// Record the audio that is played on the default audio output
+    pub audio: bool,
+}
+
+/// Records frames of the display to a video file. All encoders run on the cpu, so no video card
+/// is needed
+pub struct Recorder {
+    pipeline: Pipeline,
+    appsrc: AppSrc,
+    settings: RecordingSettings,
+}
+
+impl RecordingFormat {
+    /// Create a new recording format based on the dropdown index
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            0 => Self::Webm,
+            1 => Self::Mp4,
+            _ => panic!("Found invalid index"),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Webm => "webm",
+            Self::Mp4 => "mp4",
+        }
+    }
+
+    /// The video encoder, audio encoder and muxer of the format
+    fn elements(&self) -> Result<(Element, Element, Element), DragonDisplayError> {
+        match self {
+            Self::Webm => {
+                let video = make_element("vp8enc")?;
+                // encode as fast as frames come in instead of for the best quality
+                video.set_property_from_str("deadline", "1");
+                video.set_property_from_str("cpu-used", "8");
+                Ok((video, make_element("opusenc")?, make_element("webmmux")?))
+            }
+            Self::Mp4 => {
+                let video = make_element("x264enc")?;
+                video.set_property_from_str("speed-preset", "ultrafast");
+                video.set_property_from_str("tune", "zerolatency");
+                Ok((video, make_element("avenc_aac")?, make_element("mp4mux")?))
+            }
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub fn from_settings(settings: &gio::Settings) -> Self {
+        // video encoders need an even number of pixels in both directions
+        Self {
+            width: settings.int("recording-width") & !1,
+            height: settings.int("recording-height") & !1,
+            framerate: settings.int("recording-framerate"),
+            format: RecordingFormat::from_index(settings.int("recording-format") as u32),
+            audio: settings.boolean("recording-audio"),
+        }
+    }
+}
+
+impl Recorder {
+    /// Start a recording to the file at the path. Frames are added with push_frame, they are
+    /// timestamped when they are pushed
+    pub fn start(path: &Path, settings: RecordingSettings) -> Result<Self, DragonDisplayError> {
+        gstreamer::init().ok().context(OtherSnafu {
+            msg: "Could not initialize gstreamer".to_string(),
+        })?;
+        let location = path.to_str().context(OtherSnafu {
+            msg: "The recording path is not valid unicode".to_string(),
+        })?;
+        let pipeline = Pipeline::new();
+        // the frames are pushed at a variable rate, videorate turns them into a steady rate
+        let caps = Caps::builder("video/x-raw")
+            .field("format", "BGRA")
+            .field("width", settings.width)
+            .field("height", settings.height)
+            .field("framerate", Fraction::new(0, 1))
+            .build();
+        let appsrc = AppSrc::builder()
+            .caps(&caps)
+            .is_live(true)
+            .do_timestamp(true)
+            .format(gstreamer::Format::Time)
+            .build();
+        let videoconvert = make_element("videoconvert")?;
+        let videorate = make_element("videorate")?;
+        let rate_caps = Caps::builder("video/x-raw")
+            .field("framerate", Fraction::new(settings.framerate, 1))
+            .build();
+        let capsfilter = ElementFactory::make("capsfilter")
+            .property("caps", rate_caps)
+            .build()
+            .ok()
+            .context(OtherSnafu {
+                msg: "Could not create the gstreamer element capsfilter".to_string(),
+            })?;
+        let video_queue = make_element("queue")?;
+        let (video_encoder, audio_encoder, muxer) = settings.format.elements()?;
+        let filesink = make_element("filesink")?;
+        filesink.set_property("location", location);
+
+        let video = [
+            appsrc.upcast_ref::<Element>(),
+            &videoconvert,
+            &videorate,
+            &capsfilter,
+            &video_encoder,
+            &video_queue,
+            &muxer,
+            &filesink,
+        ];
+        pipeline
+            .add_many(video)
+            .and_then(|_| Element::link_many(video))
+            .ok()
+            .context(OtherSnafu {
+                msg: format!(
+                    "Could not create the {} recording pipeline",
+                    settings.format.extension()
+                ),
+            })?;
+
+        if settings.audio {
+            let audio_source = audio_source()?;
+            let audio = [
+                &audio_source,
+                &make_element("audioconvert")?,
+                &make_element("audioresample")?,
+                &audio_encoder,
+                &make_element("queue")?,
+                &muxer,
+            ];
+            pipeline
+                .add_many(&audio[..audio.len() - 1])
+                .and_then(|_| Element::link_many(audio))
+                .ok()
+                .context(OtherSnafu {
+                    msg: "Could not record the audio".to_string(),
+                })?;
+        }
+
+        if pipeline.set_state(State::Playing).is_err() {
+            let _ = pipeline.set_state(State::Null);
+            return Err(DragonDisplayError::Other {
+                msg: "Could not start the recording".to_string(),
+            });
+        }
+        Ok(Self {
+            pipeline,
+            appsrc,
+            settings,
+        })
+    }
+
+    /// The width and height in pixels of the frames of the recording
+    pub fn size(&self) -> (i32, i32) {
+        (self.settings.width, self.settings.height)
+    }
+
+    /// The number of frames per second of the recording
+    pub fn framerate(&self) -> i32 {
+        self.settings.framerate
+    }
+
+    /// Add a frame to the recording, the frame has to be the size of the recording
+    pub fn push_frame(&self, frame: &Texture) -> Result<(), DragonDisplayError> {
+        let (width, height) = self.size();
+        if frame.width() != width || frame.height() != height {
+            return Err(DragonDisplayError::Other {
+                msg: "The frame does not have the size of the recording".to_string(),
+            });
+        }
+        let stride = width as usize * 4;
+        let mut data = vec![0; stride * height as usize];
+        // gdk downloads in the native byte order, which is BGRA on little endian machines
+        frame.download(&mut data, stride);
+        self.appsrc
+            .push_buffer(gstreamer::Buffer::from_mut_slice(data))
+            .ok()
+            .context(OtherSnafu {
+                msg: "Could not add the frame to the recording".to_string(),
+            })?;
+        Ok(())
+    }
+
+    /// Finish the recording, the last frames are written to the file in the background. Wait
+    /// for the returned handle to make sure the file is complete
+    pub fn stop(self) -> JoinHandle<()> {
+        let pipeline = self.pipeline;
+        pipeline.send_event(gstreamer::event::Eos::new());
+        runtime().spawn_blocking(move || {
+            if let Some(bus) = pipeline.bus() {
+                let _ = bus.timed_pop_filtered(
+                    ClockTime::from_seconds(FINISH_TIMEOUT),
+                    &[MessageType::Eos, MessageType::Error],
+                );
+            }
+            let _ = pipeline.set_state(State::Null);
+        })
+    }
+}
+
+/// The path of a new recording of the display in the recordings folder of the campaign, the
+/// folder is created if it does not exist
+pub fn recording_path(
+    campaign_path: &str,
+    display: usize,
+    format: RecordingFormat,
+) -> Result<PathBuf, DragonDisplayError> {
+    let folder = campaign_data_path(campaign_path, RECORDINGS_FOLDER);
+    fs::create_dir_all(&folder).context(IOSnafu {
+        msg: "Could not create the recordings folder".to_owned(),
+    })?;
+    let time = glib::DateTime::now_local()
+        .ok()
+        .and_then(|t| t.format("%Y-%m-%d_%H-%M-%S").ok())
+        .map(|t| t.to_string())
+        .unwrap_or_default();
+    Ok(folder.join(format!(
+        "recording-{}-display-{}.{}",
+        time,
+        display + 1,
+        format.extension()
+    )))
+}
+
+/// The source of the audio of a recording. Everything that is played on the default output is
+/// recorded, if that is not possible the recording gets a silent audio track
+fn audio_source() -> Result<Element, DragonDisplayError> {
+    if let Ok(source) = ElementFactory::make("pulsesrc")
+        .property("device", AUDIO_MONITOR)
+        .build()
+    {
+        return Ok(source);
+    }
+    let source = make_element("audiotestsrc")?;
+    source.set_property_from_str("wave", "silence");
+    source.set_property("is-live", true);
+    Ok(source)
+}
+
+/// Create a gstreamer element from the factory with the given name
+fn make_element(name: &str) -> Result<Element, DragonDisplayError> {
+    ElementFactory::make(name).build().ok().context(OtherSnafu {
+        msg: format!("Could not create the gstreamer element {}", name),
+    })
+}
@@ -0,0 +1,945 @@
+use gdk4::{Texture, RGBA};
+use gtk::graphene::{Point, Rect, Size};
+use gtk::prelude::*;
+use gtk::{gsk, pango};
+
+use crate::color_grade::ColorGrade;
+use crate::errors::DragonDisplayError;
+use crate::initiative::Combat;
+use crate::text_card::{CardLayout, TextCard};
+use crate::timer::{TimerPosition, TimerStyle, Timers};
+use crate::transform::DisplayTransform;
+
+/// The size of a grid square in millimeters, about an inch
+const SQUARE_MM: i32 = 25;
+/// The distance in pixels between the overlays and the edge of the display, and between the
+/// items of an overlay. These follow the stylesheet of the display window
+const OVERLAY_MARGIN: f32 = 16.0;
+const INITIATIVE_SPACING: f32 = 12.0;
+const CARD_SPACING: f32 = 12.0;
+const LOWER_THIRD_MARGIN: f32 = 80.0;
+const TIMER_SPACING: f32 = 6.0;
+
+/// How composed media is placed in a frame of another size
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Fit {
+    /// Scale the media to fit inside the frame and fill the rest with black
+    #[default]
+    Contain,
+    /// Stretch the media over the whole frame
+    Fill,
+}
+
+/// The grid that is drawn over the media, with a square for every inch of the display
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    pub color: RGBA,
+    pub line_width: f32,
+    /// The width and height of the display in millimeters
+    pub physical_size: (i32, i32),
+}
+
+/// The text that is shown over the media of a display. A display on a monitor shows these with
+/// widgets, a virtual display draws them with draw_overlays
+#[derive(Clone, Debug, Default)]
+pub struct Overlays {
+    /// The text card that is shown, if any
+    pub card: Option<TextCard>,
+    /// The combat of the initiative strip, None when the strip is hidden
+    pub combat: Option<Combat>,
+    pub timers: Timers,
+}
+
+/// Everything about the display that changes how the media is drawn
+#[derive(Clone, Debug, Default)]
+pub struct DisplayState {
+    /// The transform of the display combined with the rotation of the media
+    pub transform: DisplayTransform,
+    pub grade: ColorGrade,
+    pub grid: Option<Grid>,
+    /// Rectangles in pixels of the media that are hidden from the players
+    pub fog_of_war: Vec<Rect>,
+}
+
+/// Draw the media the way the display shows it. The media is transformed together with the fog
+/// of war on it, then the grid is drawn over the transformed media. Returns the size of the
+/// bounding box of the transformed media, corners that the media does not cover stay transparent
+pub fn draw_media(snapshot: &gtk::Snapshot, texture: &Texture, state: &DisplayState) -> (f32, f32) {
+    let width = texture.width() as f32;
+    let height = texture.height() as f32;
+    snapshot.save();
+    let (new_width, new_height) = draw_rotation(snapshot, width, height, &state.transform);
+    draw_texture(snapshot, texture, &state.grade);
+    draw_fogofwar(snapshot, &state.fog_of_war);
+    snapshot.restore();
+    if let Some(grid) = &state.grid {
+        draw_grid(snapshot, new_width, new_height, grid);
+    }
+    (new_width, new_height)
+}
+
+/// The render node of the media drawn the way the display shows it, together with its width
+/// and height
+pub fn compose(texture: &Texture, state: &DisplayState) -> Option<(gsk::RenderNode, f32, f32)> {
+    let snapshot = gtk::Snapshot::new();
+    let (width, height) = draw_media(&snapshot, texture, state);
+    Some((snapshot.to_node()?, width, height))
+}
+
+/// Draw the node of the given size in a frame of the given size on a black background
+pub fn draw_fitted(
+    snapshot: &gtk::Snapshot,
+    node: &gsk::RenderNode,
+    (node_width, node_height): (f32, f32),
+    (width, height): (f32, f32),
+    fit: Fit,
+) {
+    let rect = fit_rect((node_width, node_height), (width, height), fit);
+    snapshot.append_color(&RGBA::BLACK, &Rect::new(0.0, 0.0, width, height));
+    snapshot.save();
+    snapshot.translate(&Point::new(rect.x(), rect.y()));
+    snapshot.scale(
+        rect.width() / node_width.max(1.0),
+        rect.height() / node_height.max(1.0),
+    );
+    snapshot.append_node(node);
+    snapshot.restore();
+}
+
+/// Render the node of the given size to a texture of the given size
+pub fn render(
+    renderer: &gsk::Renderer,
+    node: &gsk::RenderNode,
+    node_size: (f32, f32),
+    width: i32,
+    height: i32,
+    fit: Fit,
+) -> Option<Texture> {
+    let (width, height) = (width as f32, height as f32);
+    let snapshot = gtk::Snapshot::new();
+    draw_fitted(&snapshot, node, node_size, (width, height), fit);
+    let node = snapshot.to_node()?;
+    Some(renderer.render_texture(&node, Some(&Rect::new(0.0, 0.0, width, height))))
+}
+
+/// A renderer that does not need a window, so it renders virtual displays and thumbnails as well
+pub fn offscreen_renderer() -> Result<gsk::Renderer, DragonDisplayError> {
+    let renderer = gsk::CairoRenderer::new();
+    if let Err(err) = renderer.realize(None) {
+        return Err(DragonDisplayError::Other {
+            msg: format!("Could not create an offscreen renderer: {}", err),
+        });
+    }
+    Ok(renderer.upcast())
+}
+
+/// The rectangle that content of the given size takes up in a frame of the given size
+pub fn fit_rect(content: (f32, f32), frame: (f32, f32), fit: Fit) -> Rect {
+    let (content_width, content_height) = (content.0.max(1.0), content.1.max(1.0));
+    let (width, height) = frame;
+    match fit {
+        Fit::Fill => Rect::new(0.0, 0.0, width, height),
+        Fit::Contain => {
+            let scale = (width / content_width).min(height / content_height);
+            let (fit_width, fit_height) = (content_width * scale, content_height * scale);
+            Rect::new(
+                (width - fit_width) / 2.0,
+                (height - fit_height) / 2.0,
+                fit_width,
+                fit_height,
+            )
+        }
+    }
+}
+
+/// The horizontal and vertical distance in pixels between the grid lines on media of the given
+/// size, so there is a square for every inch of a display of the given size in millimeters
+pub fn grid_spacing(width: f32, height: f32, (width_mm, height_mm): (i32, i32)) -> (f32, f32) {
+    let horizontal_squares = (width_mm / SQUARE_MM).max(1);
+    let vertical_squares = (height_mm / SQUARE_MM).max(1);
+    (
+        width / horizontal_squares as f32,
+        height / vertical_squares as f32,
+    )
+}
+
+/// Draw the overlays over a display of the given size, the way the stylesheet lays out the
+/// overlay widgets of a display on a monitor
+pub fn draw_overlays(
+    snapshot: &gtk::Snapshot,
+    context: &pango::Context,
+    overlays: &Overlays,
+    width: f32,
+    height: f32,
+) {
+    if let Some(combat) = &overlays.combat {
+        draw_initiative(snapshot, context, combat, width);
+    }
+    if let Some(card) = &overlays.card {
+        draw_card(snapshot, context, card, width, height);
+    }
+    draw_timers(snapshot, context, &overlays.timers, width, height);
+}
+
+/// Draws a grid in the given snapshot. This function needs the width and height of the current
+/// texture. This width and height should be updated to fit the rotation of the texture. The size
+/// in millimeters of the display that the image is shown on sets the sizes of the squares.
+fn draw_grid(snapshot: &gtk::Snapshot, width: f32, height: f32, grid: &Grid) {
+    // 10 squares, 1080p, image: 2200p 2200/10 = 220
+    let (pix_per_horizontal_square, pix_per_vertical_square) =
+        grid_spacing(width, height, grid.physical_size);
+    let line_width = grid.line_width;
+
+    snapshot.save();
+    let mut line_position = 0.0;
+    while line_position < height {
+        snapshot.append_color(
+            &grid.color,
+            &Rect::new(0.0, line_position - (line_width / 2.0), width, line_width),
+        );
+        line_position += pix_per_vertical_square;
+    }
+    line_position = 0.0;
+    while line_position < width {
+        snapshot.append_color(
+            &grid.color,
+            &Rect::new(line_position - (line_width / 2.0), 0.0, line_width, height),
+        );
+        line_position += pix_per_horizontal_square;
+    }
+    snapshot.restore();
+}
+
+/// Applies the given transform to the snapshot, needs a width and height of the texture to
+/// be rotated. The texture is rotated around its center and moved into the bounding box of
+/// the rotated texture, the size of this box is returned so the picture can contain it
+fn draw_rotation(
+    snapshot: &gtk::Snapshot,
+    width: f32,
+    height: f32,
+    transform: &DisplayTransform,
+) -> (f32, f32) {
+    let (new_width, new_height) = transform.rotation.bounds(width, height);
+    let flip = |flipped: bool| if flipped { -1.0 } else { 1.0 };
+    snapshot.translate(&Point::new(new_width / 2.0, new_height / 2.0));
+    snapshot.scale(
+        flip(transform.flip_horizontal),
+        flip(transform.flip_vertical),
+    );
+    snapshot.rotate(transform.rotation.degrees());
+    snapshot.translate(&Point::new(-width / 2.0, -height / 2.0));
+    (new_width, new_height)
+}
+
+/// Draws the given texture to the snapshot with the color grade applied
+fn draw_texture(snapshot: &gtk::Snapshot, texture: &Texture, grade: &ColorGrade) {
+    let width = texture.width() as f32;
+    let height = texture.height() as f32;
+    let graded = !grade.is_neutral();
+    snapshot.save();
+    if graded {
+        let (matrix, offset) = grade.color_matrix();
+        snapshot.push_color_matrix(&matrix, &offset);
+    }
+    snapshot.append_texture(texture, &Rect::new(0.0, 0.0, width, height));
+    if graded {
+        snapshot.pop();
+    }
+    snapshot.restore();
+}
+
+/// Covers the rectangles of the media that are hidden from the players
+fn draw_fogofwar(snapshot: &gtk::Snapshot, fog_of_war: &[Rect]) {
+    snapshot.save();
+    for rect in fog_of_war {
+        snapshot.append_color(&RGBA::BLACK, rect);
+    }
+    snapshot.restore();
+}
+
+/// Draws the turn order at the top center of the display, the combatant whose turn it is gets
+/// a red background
+fn draw_initiative(
+    snapshot: &gtk::Snapshot,
+    context: &pango::Context,
+    combat: &Combat,
+    width: f32,
+) {
+    let mut items = vec![(
+        text_layout(context, &format!("Round {}", combat.round), 24.0, true),
+        false,
+    )];
+    for (index, combatant) in combat.combatants.iter().enumerate() {
+        if combatant.hidden {
+            continue;
+        }
+        let current = index == combat.turn;
+        items.push((
+            text_layout(context, &combatant.name, 24.0, current),
+            current,
+        ));
+    }
+    // the current turn has 8 pixels of padding at both sides
+    let padding = |current: bool| if current { 8.0 } else { 0.0 };
+    let item_width = |layout: &pango::Layout, current: bool| {
+        layout_size(layout).width() + 2.0 * padding(current)
+    };
+    let content_width = items
+        .iter()
+        .map(|(layout, current)| item_width(layout, *current))
+        .sum::<f32>()
+        + INITIATIVE_SPACING * (items.len() - 1) as f32;
+    let content_height = items
+        .iter()
+        .map(|(layout, _)| layout_size(layout).height())
+        .fold(0.0, f32::max);
+    let strip = Rect::new(
+        (width - content_width) / 2.0 - 24.0,
+        OVERLAY_MARGIN,
+        content_width + 48.0,
+        content_height + 16.0,
+    );
+    draw_box(snapshot, &strip, 12.0, &RGBA::new(0.0, 0.0, 0.0, 0.7));
+    let (mut x, y) = (strip.x() + 24.0, strip.y() + 8.0);
+    for (layout, current) in &items {
+        let item = Rect::new(x, y, item_width(layout, *current), content_height);
+        if *current {
+            draw_box(snapshot, &item, 6.0, &hex_color(0xc62828, 1.0));
+        }
+        let text_y = y + (content_height - layout_size(layout).height()) / 2.0;
+        draw_layout(
+            snapshot,
+            layout,
+            x + padding(*current),
+            text_y,
+            &RGBA::WHITE,
+        );
+        x += item.width() + INITIATIVE_SPACING;
+    }
+}
+
+/// Draws the title and text of the card centered in the box of its layout
+fn draw_card(
+    snapshot: &gtk::Snapshot,
+    context: &pango::Context,
+    card: &TextCard,
+    width: f32,
+    height: f32,
+) {
+    let parchment = card.layout == CardLayout::Parchment;
+    let (padding_x, padding_y) = if parchment {
+        (160.0, 96.0)
+    } else {
+        (48.0, 24.0)
+    };
+    let text_width = (width - 2.0 * padding_x).max(1.0);
+    let layouts: Vec<pango::Layout> = [(&card.title, 48.0, true), (&card.text, 28.0, false)]
+        .into_iter()
+        .filter(|(text, _, _)| !text.is_empty())
+        .map(|(text, size, bold)| {
+            let layout = text_layout(context, text, size, bold);
+            if parchment {
+                let mut font = layout.font_description().unwrap_or_default();
+                font.set_family("serif");
+                layout.set_font_description(Some(&font));
+            }
+            layout.set_width((text_width * pango::SCALE as f32) as i32);
+            layout.set_wrap(pango::WrapMode::WordChar);
+            layout.set_alignment(pango::Alignment::Center);
+            layout
+        })
+        .collect();
+    let content_width = layouts
+        .iter()
+        .map(|layout| layout_size(layout).width())
+        .fold(0.0, f32::max);
+    let content_height = layouts
+        .iter()
+        .map(|layout| layout_size(layout).height())
+        .sum::<f32>()
+        + CARD_SPACING * layouts.len().saturating_sub(1) as f32;
+    let card_height = content_height + 2.0 * padding_y;
+    let (rect, color) = match card.layout {
+        CardLayout::LowerThird => {
+            let rect = Rect::new(
+                0.0,
+                height - LOWER_THIRD_MARGIN - card_height,
+                width,
+                card_height,
+            );
+            draw_box(snapshot, &rect, 0.0, &RGBA::new(0.0, 0.0, 0.0, 0.7));
+            (rect, RGBA::WHITE)
+        }
+        CardLayout::Centered => {
+            let card_width = content_width + 2.0 * padding_x;
+            let rect = Rect::new(
+                (width - card_width) / 2.0,
+                (height - card_height) / 2.0,
+                card_width,
+                card_height,
+            );
+            draw_box(snapshot, &rect, 12.0, &RGBA::new(0.0, 0.0, 0.0, 0.8));
+            (rect, RGBA::WHITE)
+        }
+        CardLayout::Parchment => {
+            let rect = Rect::new(0.0, 0.0, width, height);
+            // a circle that reaches the corners, like a radial gradient in the stylesheet
+            let radius = (width * width + height * height).sqrt() / 2.0;
+            let stops = [
+                gsk::ColorStop::new(0.4, hex_color(0xf6ecd2, 1.0)),
+                gsk::ColorStop::new(1.0, hex_color(0xd9c08e, 1.0)),
+            ];
+            snapshot.append_radial_gradient(
+                &rect,
+                &Point::new(width / 2.0, height / 2.0),
+                radius,
+                radius,
+                0.0,
+                1.0,
+                &stops,
+            );
+            (rect, hex_color(0x3b2a16, 1.0))
+        }
+    };
+    // the layouts are as wide as the display without padding and center their lines
+    let x = (width - text_width) / 2.0;
+    let mut y = rect.y() + (rect.height() - content_height) / 2.0;
+    for layout in &layouts {
+        draw_layout(snapshot, layout, x, y, &color);
+        y += layout_size(layout).height() + CARD_SPACING;
+    }
+}
+
+/// Draws the timers of every position in a column, the timers in a column are equally wide
+fn draw_timers(
+    snapshot: &gtk::Snapshot,
+    context: &pango::Context,
+    timers: &Timers,
+    width: f32,
+    height: f32,
+) {
+    for position in TimerPosition::ALL {
+        let labels: Vec<(pango::Layout, bool, bool)> = timers
+            .0
+            .iter()
+            .filter(|timer| timer.position == position)
+            .map(|timer| {
+                let size = match timer.style {
+                    TimerStyle::Small => 24.0,
+                    TimerStyle::Large => 72.0,
+                };
+                let layout = text_layout(context, &timer.text(), size, true);
+                (layout, timer.is_expired(), timer.flash)
+            })
+            .collect();
+        if labels.is_empty() {
+            continue;
+        }
+        let label_width = labels
+            .iter()
+            .map(|(layout, _, _)| layout_size(layout).width())
+            .fold(0.0, f32::max)
+            + 32.0;
+        let column_height = labels
+            .iter()
+            .map(|(layout, _, _)| layout_size(layout).height() + 8.0)
+            .sum::<f32>()
+            + TIMER_SPACING * (labels.len() - 1) as f32;
+        let (halign, valign) = position.align();
+        let x = align(halign, label_width, width);
+        let mut y = align(valign, column_height, height);
+        for (layout, expired, flash) in &labels {
+            let size = layout_size(layout);
+            let label = Rect::new(x, y, label_width, size.height() + 8.0);
+            // a flashing timer is drawn at the start of its flash
+            let (background, color) = match (expired, flash) {
+                (true, true) => (hex_color(0xc62828, 0.9), RGBA::WHITE),
+                (true, false) => (RGBA::new(0.0, 0.0, 0.0, 0.7), hex_color(0xff5252, 1.0)),
+                _ => (RGBA::new(0.0, 0.0, 0.0, 0.7), RGBA::WHITE),
+            };
+            draw_box(snapshot, &label, 8.0, &background);
+            let text_x = x + (label_width - size.width()) / 2.0;
+            draw_layout(snapshot, layout, text_x, y + 4.0, &color);
+            y += label.height() + TIMER_SPACING;
+        }
+    }
+}
+
+/// The position of an overlay of the given size in a display of the given size
+fn align(align: gtk::Align, size: f32, display_size: f32) -> f32 {
+    match align {
+        gtk::Align::Start => OVERLAY_MARGIN,
+        gtk::Align::End => display_size - size - OVERLAY_MARGIN,
+        _ => (display_size - size) / 2.0,
+    }
+}
+
+/// A layout of the text in the font of the context at a size in pixels
+fn text_layout(context: &pango::Context, text: &str, size: f32, bold: bool) -> pango::Layout {
+    let layout = pango::Layout::new(context);
+    let mut font = context.font_description().unwrap_or_default();
+    font.set_absolute_size(size as f64 * pango::SCALE as f64);
+    if bold {
+        font.set_weight(pango::Weight::Bold);
+    }
+    layout.set_font_description(Some(&font));
+    layout.set_text(text);
+    layout
+}
+
+/// The width and height of the text of the layout in pixels
+fn layout_size(layout: &pango::Layout) -> Size {
+    let (width, height) = layout.pixel_size();
+    Size::new(width as f32, height as f32)
+}
+
+fn draw_layout(snapshot: &gtk::Snapshot, layout: &pango::Layout, x: f32, y: f32, color: &RGBA) {
+    snapshot.save();
+    snapshot.translate(&Point::new(x, y));
+    snapshot.append_layout(layout, color);
+    snapshot.restore();
+}
+
+/// Fills the rectangle with rounded corners of the radius
+fn draw_box(snapshot: &gtk::Snapshot, rect: &Rect, radius: f32, color: &RGBA) {
+    snapshot.push_rounded_clip(&gsk::RoundedRect::from_rect(*rect, radius));
+    snapshot.append_color(color, rect);
+    snapshot.pop();
+}
+
+/// The color of a hexadecimal rgb value like in the stylesheet
+fn hex_color(rgb: u32, alpha: f32) -> RGBA {
+    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+    RGBA::new(channel(16), channel(8), channel(0), alpha)
+}
+
+/// Golden image tests of the drawing of the display. The tests render through gsk, so they need
+/// a display, run them with xvfb-run on a machine without one. A missing golden image fails the
+/// test, run the tests with DD_UPDATE_GOLDEN=1 to write the golden images after an intended
+/// change to the drawing
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Mutex, OnceLock};
+
+    use gtk::gdk_pixbuf::prelude::*;
+    use gtk::gdk_pixbuf::{InterpType, Pixbuf, PixbufLoader};
+    use gtk::gio;
+    use vtt_rust::open_vtt;
+
+    use super::*;
+    use crate::transform::{DisplayTransform, Rotation};
+
+    /// The size that the test media is scaled to, keeps the golden images small
+    const TEST_SIZE: i32 = 128;
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+    type Job = Box<dyn FnOnce() + Send>;
+
+    /// The pixels of a texture in RGBA order
+    struct Pixels {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    }
+
+    impl Pixels {
+        fn of(texture: &Texture) -> Self {
+            let (width, height) = (texture.width() as u32, texture.height() as u32);
+            let stride = width as usize * 4;
+            let mut data = vec![0; stride * height as usize];
+            texture.download(&mut data, stride);
+            // gdk downloads in the native byte order, which is BGRA on little endian machines
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            Self {
+                width,
+                height,
+                data,
+            }
+        }
+
+        fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+            let index = (y as usize * self.width as usize + x as usize) * 4;
+            let mut pixel = [0; 4];
+            pixel.copy_from_slice(&self.data[index..index + 4]);
+            pixel
+        }
+    }
+
+    /// GTK can only be used from the thread that initialized it, so every test draws on the
+    /// same thread
+    fn on_gtk_thread<F: FnOnce() + Send + 'static>(f: F) {
+        static JOBS: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+        let jobs = JOBS.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            std::thread::spawn(move || {
+                gtk::init().expect("Could not initialize gtk, the tests need a display");
+                for job in receiver {
+                    job();
+                }
+            });
+            Mutex::new(sender)
+        });
+        let (result_sender, result) = mpsc::channel();
+        jobs.lock()
+            .expect("Could not reach the gtk thread")
+            .send(Box::new(move || {
+                let _ = result_sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+            }))
+            .expect("Could not reach the gtk thread");
+        if let Err(err) = result.recv().expect("The gtk thread stopped") {
+            panic::resume_unwind(err);
+        }
+    }
+
+    fn repo_path(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+    }
+
+    fn load(path: &str) -> Texture {
+        let pixbuf = Pixbuf::from_file_at_scale(repo_path(path), TEST_SIZE, TEST_SIZE, true)
+            .expect("Could not load test image");
+        Texture::for_pixbuf(&pixbuf)
+    }
+
+    /// Load the image of the vtt scaled to the test size and its fog of war in the pixels of
+    /// the scaled image
+    fn load_vtt(path: &str) -> (Texture, Vec<Rect>) {
+        let mut vtt = open_vtt(repo_path(path).to_str().expect("Expected a valid path"))
+            .expect("Could not open test vtt");
+        let image = vtt
+            .take_image()
+            .expect("Could not get the image of the vtt");
+        let loader = PixbufLoader::new();
+        loader
+            .write(&image)
+            .expect("Could not load the image of the vtt");
+        loader.close().expect("Could not load the image of the vtt");
+        let pixbuf = loader.pixbuf().expect("Expected the image of the vtt");
+        let scale = TEST_SIZE as f32 / pixbuf.width().max(pixbuf.height()) as f32;
+        let scaled = pixbuf
+            .scale_simple(
+                (pixbuf.width() as f32 * scale).round() as i32,
+                (pixbuf.height() as f32 * scale).round() as i32,
+                InterpType::Nearest,
+            )
+            .expect("Could not scale the image of the vtt");
+        let fog = vtt
+            .get_fow()
+            .get_rectangles()
+            .iter()
+            .map(|f| {
+                let width = (f.bottomright.x - f.topleft.x) + 1;
+                let height = (f.bottomright.y - f.topleft.y) + 1;
+                Rect::new(
+                    f.topleft.x as f32 * scale,
+                    f.topleft.y as f32 * scale,
+                    width as f32 * scale,
+                    height as f32 * scale,
+                )
+            })
+            .collect();
+        (Texture::for_pixbuf(&scaled), fog)
+    }
+
+    /// Draw the media the way the display does, at the size of the transformed media
+    fn draw(texture: &Texture, state: &DisplayState) -> Texture {
+        let renderer = offscreen_renderer().expect("Could not create a renderer");
+        let (node, width, height) = compose(texture, state).expect("Expected a render node");
+        renderer.render_texture(&node, Some(&Rect::new(0.0, 0.0, width, height)))
+    }
+
+    /// Draw the media the way a display of the given size shows it
+    fn draw_sized(texture: &Texture, state: &DisplayState, width: i32, height: i32) -> Texture {
+        let renderer = offscreen_renderer().expect("Could not create a renderer");
+        let (node, node_width, node_height) =
+            compose(texture, state).expect("Expected a render node");
+        render(
+            &renderer,
+            &node,
+            (node_width, node_height),
+            width,
+            height,
+            Fit::Contain,
+        )
+        .expect("Expected a frame")
+    }
+
+    fn rotated(degrees: f32) -> DisplayState {
+        DisplayState {
+            transform: DisplayTransform {
+                rotation: Rotation::from_degrees(degrees),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn grid(line_width: f32, physical_size: (i32, i32)) -> Grid {
+        Grid {
+            color: RGBA::new(1.0, 0.0, 0.0, 1.0),
+            line_width,
+            physical_size,
+        }
+    }
+
+    /// Pixels may differ a little because of rounding while blending
+    fn assert_close(a: [u8; 4], b: [u8; 4]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 2),
+            "{:?} differs from {:?}",
+            a,
+            b
+        );
+    }
+
+    /// Compare the frame with the golden image of the name, the golden image is written if
+    /// DD_UPDATE_GOLDEN is set
+    fn assert_golden(name: &str, frame: &Texture) {
+        let path = repo_path(&format!("tests/golden/{}.png", name));
+        if std::env::var_os("DD_UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().expect("Expected a golden folder"))
+                .expect("Could not create the golden folder");
+            frame
+                .save_to_png(&path)
+                .expect("Could not write the golden image");
+            return;
+        }
+        assert!(
+            path.exists(),
+            "The golden image {} is missing, run the tests with DD_UPDATE_GOLDEN=1 to write it",
+            path.display()
+        );
+        let golden = Texture::from_file(&gio::File::for_path(&path))
+            .expect("Could not load the golden image");
+        let (golden, frame) = (Pixels::of(&golden), Pixels::of(frame));
+        assert_eq!(
+            (golden.width, golden.height),
+            (frame.width, frame.height),
+            "size of {} differs from the golden image",
+            name
+        );
+        let different = golden
+            .data
+            .chunks_exact(4)
+            .zip(frame.data.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > 1))
+            .count();
+        assert_eq!(
+            different, 0,
+            "{} pixels of {} differ from the golden image",
+            different, name
+        );
+    }
+
+    #[test]
+    fn right_angle_rotations_move_pixels_exactly() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Ancoria.png");
+            let media = Pixels::of(&texture);
+            let (width, height) = (media.width, media.height);
+            for degrees in [0.0, 90.0, 180.0, 270.0] {
+                let frame = draw(&texture, &rotated(degrees));
+                let pixels = Pixels::of(&frame);
+                let expected = |x: u32, y: u32| match degrees as u32 {
+                    0 => (x, y),
+                    90 => (height - 1 - y, x),
+                    180 => (width - 1 - x, height - 1 - y),
+                    _ => (y, width - 1 - x),
+                };
+                for (x, y) in [
+                    (0, 0),
+                    (width - 1, 0),
+                    (width / 2, height / 3),
+                    (3, height - 1),
+                ] {
+                    let (frame_x, frame_y) = expected(x, y);
+                    assert_close(pixels.pixel(frame_x, frame_y), media.pixel(x, y));
+                }
+                assert_golden(&format!("rotation_{}", degrees as u32), &frame);
+            }
+        });
+    }
+
+    #[test]
+    fn free_rotation_fills_the_bounding_box() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Oton.png");
+            let frame = draw(&texture, &rotated(30.0));
+            let (width, height) = Rotation::from_degrees(30.0)
+                .bounds(texture.width() as f32, texture.height() as f32);
+            assert!((frame.width() as f32 - width).abs() <= 1.0);
+            assert!((frame.height() as f32 - height).abs() <= 1.0);
+            // the corners of the bounding box are not covered by the media
+            let pixels = Pixels::of(&frame);
+            assert_eq!(pixels.pixel(0, 0)[3], 0);
+            assert_eq!(pixels.pixel(pixels.width - 1, pixels.height - 1)[3], 0);
+            assert_golden("rotation_30", &frame);
+        });
+    }
+
+    #[test]
+    fn mirroring_is_applied_after_the_rotation() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Deathworm.jpeg");
+            let media = Pixels::of(&texture);
+            let (width, height) = (media.width, media.height);
+            let state = DisplayState {
+                transform: DisplayTransform {
+                    flip_horizontal: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let frame = draw(&texture, &state);
+            let pixels = Pixels::of(&frame);
+            assert_close(pixels.pixel(0, 0), media.pixel(width - 1, 0));
+            assert_close(
+                pixels.pixel(width - 1, height - 1),
+                media.pixel(0, height - 1),
+            );
+            assert_golden("flip_horizontal", &frame);
+
+            let state = DisplayState {
+                transform: DisplayTransform {
+                    rotation: Rotation::from_degrees(90.0),
+                    flip_horizontal: false,
+                    flip_vertical: true,
+                },
+                ..Default::default()
+            };
+            let frame = draw(&texture, &state);
+            // rotating the top left corner to the top right and mirroring it to the bottom right
+            assert_close(
+                Pixels::of(&frame).pixel(height - 1, width - 1),
+                media.pixel(0, 0),
+            );
+            assert_golden("rotation_90_flip_vertical", &frame);
+        });
+    }
+
+    #[test]
+    fn grid_lines_have_the_line_width() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Mirad_square.jpg");
+            let media = Pixels::of(&texture);
+            let physical_size = (250, 150);
+            for line_width in [0.5, 1.0, 3.0, 8.0] {
+                let state = DisplayState {
+                    grid: Some(grid(line_width, physical_size)),
+                    ..Default::default()
+                };
+                let frame = draw(&texture, &state);
+                let pixels = Pixels::of(&frame);
+                let (horizontal, vertical) =
+                    grid_spacing(pixels.width as f32, pixels.height as f32, physical_size);
+                // the first lines are centered on the edges of the media, so only half of a
+                // line is on the media
+                if line_width / 2.0 >= 1.0 {
+                    assert_eq!(pixels.pixel(0, pixels.height / 2), RED);
+                    assert_eq!(pixels.pixel(pixels.width / 2, 0), RED);
+                    assert_eq!(pixels.pixel(horizontal as u32, pixels.height / 2), RED);
+                }
+                // the middle of a square is not covered by thin lines
+                let (x, y) = ((horizontal * 1.5) as u32, (vertical * 1.5) as u32);
+                if line_width < horizontal.min(vertical) / 2.0 - 1.0 {
+                    assert_close(pixels.pixel(x, y), media.pixel(x, y));
+                }
+                assert_golden(&format!("grid_{}", (line_width * 10.0) as u32), &frame);
+            }
+        });
+    }
+
+    #[test]
+    fn fog_of_war_covers_the_rectangles() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Maled_huis.jpg");
+            let media = Pixels::of(&texture);
+            let fog = vec![
+                Rect::new(0.0, 0.0, 20.0, 30.0),
+                Rect::new(40.0, 10.5, 33.3, 12.0),
+                Rect::new(-10.0, 60.0, 500.0, 5.0),
+            ];
+            let state = DisplayState {
+                fog_of_war: fog,
+                ..Default::default()
+            };
+            let frame = draw(&texture, &state);
+            let pixels = Pixels::of(&frame);
+            assert_eq!(pixels.pixel(0, 0), BLACK);
+            assert_eq!(pixels.pixel(19, 29), BLACK);
+            assert_eq!(pixels.pixel(50, 15), BLACK);
+            assert_eq!(pixels.pixel(pixels.width - 1, 62), BLACK);
+            assert_close(pixels.pixel(30, 40), media.pixel(30, 40));
+            assert_golden("fog_of_war", &frame);
+
+            // the fog is drawn on the media, so it turns with the media
+            let state = DisplayState {
+                fog_of_war: vec![Rect::new(0.0, 0.0, 20.0, 30.0)],
+                ..rotated(90.0)
+            };
+            let frame = draw(&texture, &state);
+            let pixels = Pixels::of(&frame);
+            assert_eq!(pixels.pixel(pixels.width - 1, 0), BLACK);
+            assert_golden("fog_of_war_rotation_90", &frame);
+        });
+    }
+
+    #[test]
+    fn fit_modes_place_the_media_in_the_frame() {
+        on_gtk_thread(|| {
+            let texture = load("test_gd/Zuid_enas.png");
+            let contain = draw_sized(&texture, &DisplayState::default(), 200, 100);
+            assert_eq!((contain.width(), contain.height()), (200, 100));
+            let rect = fit_rect(
+                (texture.width() as f32, texture.height() as f32),
+                (200.0, 100.0),
+                Fit::Contain,
+            );
+            assert!(rect.width() <= 200.0 && rect.height() <= 100.0);
+            if rect.x() >= 1.0 {
+                assert_eq!(Pixels::of(&contain).pixel(0, 50), BLACK);
+            }
+            assert_golden("fit_contain", &contain);
+
+            let renderer = offscreen_renderer().expect("Could not create a renderer");
+            let (node, width, height) =
+                compose(&texture, &DisplayState::default()).expect("Expected a render node");
+            let fill = render(&renderer, &node, (width, height), 200, 100, Fit::Fill)
+                .expect("Expected a frame");
+            assert_eq!((fill.width(), fill.height()), (200, 100));
+            assert_golden("fit_fill", &fill);
+
+            // the corners of freely rotated media are filled with the background
+            let turned = draw_sized(&texture, &rotated(45.0), 160, 160);
+            assert_eq!(Pixels::of(&turned).pixel(0, 0), BLACK);
+            assert_golden("fit_contain_rotation_45", &turned);
+        });
+    }
+
+    #[test]
+    fn vtt_files_draw_with_fog() {
+        on_gtk_thread(|| {
+            for (name, path) in [
+                ("vtt_example1", "vtt_test/example1.dd2vtt"),
+                ("vtt_example2", "vtt_test/example2.dd2vtt"),
+            ] {
+                let (texture, fog_of_war) = load_vtt(path);
+                // the fog of war turns with the vtt and there is no grid, like the display
+                // draws a vtt
+                let state = DisplayState {
+                    fog_of_war,
+                    ..rotated(90.0)
+                };
+                let frame = draw_sized(&texture, &state, 160, 90);
+                assert_eq!((frame.width(), frame.height()), (160, 90));
+                assert_golden(name, &frame);
+            }
+        });
+    }
+}
@@ -2,16 +2,17 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use gtk::glib::clone;
-use gtk::{glib, subclass::prelude::*};
+use gtk::{gio, glib, subclass::prelude::*};
 use gtk::{prelude::*, Window};
 use snafu::{Report, ResultExt};
 
 use crate::config::{
-    read_campaign_from_config, remove_campaign_from_config, write_campaign_to_config, Campaign,
-    SynchronizationOption,
+    latest_backup, migrate_legacy_config, read_campaign_from_config, remove_campaign_from_config,
+    restore_backup, write_campaign_to_config, Campaign, SynchronizationOption,
 };
 use crate::errors::{DragonDisplayError, SendBackendSnafu};
 use crate::gd_client::{DragonDisplayGDClient, GdClientEvent};
+use crate::monitors::{connected_monitors, CampaignMonitors};
 use crate::program::DragonDisplayProgram;
 use crate::ui::add_campaign::AddCampaignWindow;
 use crate::ui::googledrive_connect::GoogledriveConnectWindow;
@@ -22,7 +23,7 @@ use crate::ui::select_campaign::SelectCampaignWindow;
 use crate::ui::select_monitor::SelectMonitorWindow;
 use crate::widgets::google_folder_object::GoogleFolderObject;
 use crate::widgets::progress_bar::DdProgressBar;
-use crate::{runtime, try_emit};
+use crate::{runtime, try_emit, APP_ID};
 
 #[derive(Clone, Debug)]
 pub struct Token {
@@ -110,9 +111,12 @@ mod imp {
     #[derive(Default)]
     pub struct DragonDisplaySetup {
         pub campaign: RefCell<DdCampaign>,
-        pub monitor: OnceCell<Monitor>,
+        /// The monitors that the display windows are opened on
+        pub monitors: OnceCell<Vec<Monitor>>,
         /// a state variable indicating whether the program is in setup mode
         pub setup: Cell<bool>,
+        /// The monitors are selected again, even if the remembered monitors are connected
+        pub choose_monitors: Cell<bool>,
         /// a state variable for remembering between refresh operations of a gdClient
         pub gd_client_state: RefCell<GdClientState>,
         /// The main dragon display program is set when the setup is complete
@@ -151,7 +155,18 @@ impl DragonDisplaySetup {
 
     /// Create and present the select window
     pub fn select_window(&self, app: &adw::Application) {
-        let campaign_list = try_emit!(self, read_campaign_from_config(), true);
+        try_emit!(self, migrate_legacy_config(), true);
+        let campaign_list = match read_campaign_from_config() {
+            Ok(campaign_list) => campaign_list,
+            Err(DragonDisplayError::CorruptConfig { msg }) => {
+                self.restore_backup_window(app, msg);
+                return;
+            }
+            Err(err) => {
+                self.emit_error(err, true);
+                return;
+            }
+        };
         let window = SelectCampaignWindow::new(app, campaign_list);
 
         window.connect_remove_campaign(clone!(@weak self as obj, @weak app => move |window| {
@@ -166,9 +181,10 @@ impl DragonDisplaySetup {
 
         let imp = self.imp();
         window.connect_campaign(
-            clone!(@weak self as obj, @weak imp, @weak app => move |window, campaign| {
+            clone!(@weak self as obj, @weak imp, @weak app => move |window, campaign, choose_monitors| {
                 window.destroy();
                 obj.imp().campaign.replace(campaign);
+                obj.imp().choose_monitors.set(choose_monitors);
                 match imp.campaign.borrow().sync_option() {
                     SynchronizationOption::None => obj.monitor_window(&app),
                     SynchronizationOption::GoogleDrive { .. } => obj.googledrive_synchronize(&app),
@@ -179,6 +195,38 @@ impl DragonDisplaySetup {
         window.present();
     }
 
+    /// Offer to restore the latest backup of the corrupted config file, without backup the
+    /// program can not continue
+    pub fn restore_backup_window(&self, app: &adw::Application, msg: String) {
+        let remove_msg = format!(
+            "{}. Please remove the file and restart the application",
+            msg
+        );
+        let Some(backup) = latest_backup() else {
+            self.emit_error(DragonDisplayError::Other { msg: remove_msg }, true);
+            return;
+        };
+        let message = format!(
+            "{}.\nDo you want to restore the backup {}?",
+            msg,
+            backup.display()
+        );
+        let window = RemoveConfirmWindow::with_message(app, &message);
+
+        window.connect_no(clone!(@weak self as obj => move |window| {
+            window.destroy();
+            obj.emit_error(DragonDisplayError::Other { msg: remove_msg.clone() }, true);
+        }));
+
+        window.connect_yes(clone!(@weak self as obj, @weak app => move |window| {
+            try_emit!(obj, restore_backup(&backup), true);
+            window.destroy();
+            obj.select_window(&app);
+        }));
+
+        window.present();
+    }
+
     /// Create and present the remove window
     pub fn remove_window(&self, app: &adw::Application) {
         let campaign_list = try_emit!(self, read_campaign_from_config(), true);
@@ -522,14 +570,47 @@ impl DragonDisplaySetup {
         window.present();
     }
 
-    /// Present the window to select a monitor
+    /// Present the window to select the monitors. The monitors that were chosen for the campaign
+    /// last time are selected automatically if they are all connected, unless the user chose to
+    /// select the monitors again
     pub fn monitor_window(&self, app: &adw::Application) {
-        let window = try_emit!(self, SelectMonitorWindow::new(&app), true);
+        let campaign_path = self.imp().campaign.borrow().path();
+        let remembered = match CampaignMonitors::read(&campaign_path) {
+            Ok(monitors) => monitors,
+            Err(err) => {
+                self.emit_error(err, false);
+                CampaignMonitors::default()
+            }
+        };
+        let connected = connected_monitors();
+        let virtual_display = gio::Settings::new(APP_ID).boolean("virtual-display");
+        let found = match self.imp().choose_monitors.get() {
+            true => None,
+            false => remembered.find(&connected, virtual_display),
+        };
+        if let Some(monitors) = found {
+            self.imp()
+                .monitors
+                .set(monitors)
+                .expect("Expected monitors to not be set");
+            self.start_program(app);
+            return;
+        }
+        // preselect the remembered monitors that are still connected
+        let preselect = remembered.connected(&connected);
+        let window = try_emit!(self, SelectMonitorWindow::new(&app, &preselect), true);
 
-        window.connect_monitor(
-            clone!(@weak self as obj, @weak app, @weak window => move |_, monitor| {
+        window.connect_monitors(
+            clone!(@weak self as obj, @weak app, @weak window => move |_, monitors| {
                 window.destroy();
-                obj.imp().monitor.set(monitor).expect("Expected monitor to not be set");
+                let campaign_path = obj.imp().campaign.borrow().path();
+                // the virtual display can be switched on or off in the monitor window
+                let virtual_display = gio::Settings::new(APP_ID).boolean("virtual-display");
+                let remembered = CampaignMonitors::from_monitors(&monitors.0, virtual_display);
+                if let Err(err) = remembered.write(&campaign_path) {
+                    obj.emit_error(err, false);
+                }
+                obj.imp().monitors.set(monitors.0).expect("Expected monitors to not be set");
                 obj.start_program(&app);
             }),
         );
@@ -537,17 +618,21 @@ impl DragonDisplaySetup {
         window.present();
     }
 
-    /// This starts a new manager that will manage the two screens (control and display window)
+    /// This starts a new manager that will manage the control window and the display windows
     /// This manager keeps existing to handle possible refresh requests
     pub fn start_program(&self, app: &adw::Application) {
         let dragon_display = DragonDisplayProgram::new();
 
         dragon_display.run(
             &app,
-            self.imp().monitor.get().expect("Expected a monitor"),
+            self.imp().monitors.get().expect("Expected monitors"),
             self.imp().campaign.borrow().path(),
         );
         self.imp().setup.set(false);
+        // synchronized campaigns are updated by the synchronization itself
+        if let SynchronizationOption::None = self.imp().campaign.borrow().sync_option() {
+            dragon_display.watch_campaign_folder();
+        }
 
         dragon_display.connect_refresh(clone!(@weak self as obj, @weak app => move |program| {
             let sync_option = obj.imp().campaign.borrow().sync_option();
@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gdk4::Texture;
+use gtk::glib;
+use gtk::prelude::*;
+use snafu::ResultExt;
+
+use crate::config::campaign_data_path;
+use crate::errors::{DragonDisplayError, IOSnafu};
+
+/// Name of the folder in the campaign data folder that holds the snapshots of the display
+pub const SNAPSHOTS_FOLDER: &str = "snapshots";
+
+/// A file name for a snapshot of the display that is taken now, for example
+/// "snapshot-2024-05-01_20-15-03-display-1.png". Displays are counted from 0
+pub fn snapshot_name(display: usize) -> String {
+    let time = glib::DateTime::now_local()
+        .ok()
+        .and_then(|t| t.format("%Y-%m-%d_%H-%M-%S").ok())
+        .map(|t| t.to_string())
+        .unwrap_or_default();
+    format!("snapshot-{}-display-{}.png", time, display + 1)
+}
+
+/// The path of a new snapshot of the display in the snapshots folder of the campaign, the
+/// folder is created if it does not exist
+pub fn snapshot_path(campaign_path: &str, display: usize) -> Result<PathBuf, DragonDisplayError> {
+    let folder = campaign_data_path(campaign_path, SNAPSHOTS_FOLDER);
+    fs::create_dir_all(&folder).context(IOSnafu {
+        msg: "Could not create the snapshots folder".to_owned(),
+    })?;
+    Ok(folder.join(snapshot_name(display)))
+}
+
+/// Save the frame of the display as png
+pub fn save_snapshot(frame: &Texture, path: &Path) -> Result<(), DragonDisplayError> {
+    frame
+        .save_to_png(path)
+        .map_err(|err| DragonDisplayError::Other {
+            msg: format!("Could not save the snapshot to {}: {}", path.display(), err),
+        })
+}
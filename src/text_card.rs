@@ -0,0 +1,88 @@
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+
+/// Name of the file in the campaign data folder that stores the text cards
+pub const TEXT_CARD_FILE: &str = "text_cards.toml";
+/// The time in milliseconds it takes for a text card to fade in or out
+pub const FADE_DURATION: u32 = 600;
+
+/// Structure representing the text cards of a campaign as they are stored in the data folder
+#[derive(Serialize, Deserialize, Default)]
+struct TextCards {
+    cards: Vec<TextCard>,
+}
+
+/// The way a text card is placed on the display
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum CardLayout {
+    /// A bar over the bottom of the display, for location titles
+    #[default]
+    LowerThird,
+    /// A card in the middle of the display, for quotes
+    Centered,
+    /// A sheet of parchment covering the display, for read-aloud text
+    Parchment,
+}
+
+impl CardLayout {
+    pub const ALL: [CardLayout; 3] = [
+        CardLayout::LowerThird,
+        CardLayout::Centered,
+        CardLayout::Parchment,
+    ];
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn index(&self) -> u32 {
+        Self::ALL.iter().position(|l| l == self).unwrap_or(0) as u32
+    }
+
+    /// The name of the layout as it is shown to the user
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardLayout::LowerThird => "Lower third",
+            CardLayout::Centered => "Centered card",
+            CardLayout::Parchment => "Full-screen parchment",
+        }
+    }
+
+    /// The style class of the card on the display
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            CardLayout::LowerThird => "lower-third",
+            CardLayout::Centered => "centered-card",
+            CardLayout::Parchment => "parchment",
+        }
+    }
+}
+
+/// Text that is shown over the media on the display. The name is only used to find the card in
+/// the control window
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdTextCard")]
+pub struct TextCard {
+    pub name: String,
+    pub title: String,
+    pub text: String,
+    pub layout: CardLayout,
+}
+
+/// Read the text cards of the campaign at campaign_path, returns an empty vector if the campaign
+/// has no text cards
+pub fn read_text_cards(campaign_path: &str) -> Result<Vec<TextCard>, DragonDisplayError> {
+    let cards: TextCards = read_campaign_data(campaign_path, TEXT_CARD_FILE)?;
+    Ok(cards.cards)
+}
+
+/// Write the text cards to the data folder of the campaign at campaign_path
+pub fn write_text_cards(campaign_path: &str, cards: &[TextCard]) -> Result<(), DragonDisplayError> {
+    let cards = TextCards {
+        cards: cards.to_vec(),
+    };
+    write_campaign_data(campaign_path, TEXT_CARD_FILE, &cards)
+}
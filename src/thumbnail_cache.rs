@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib;
+use snafu::{OptionExt, ResultExt};
+
+use crate::errors::{DragonDisplayError, GlibSnafu, IOSnafu, OtherSnafu};
+
+/// The maximum width and height in pixels of a cached thumbnail
+pub const THUMBNAIL_SIZE: i32 = 256;
+
+/// Returns the png data of the thumbnail of the image. The thumbnail is read from the cache if it
+/// exists, otherwise the image is decoded, scaled down and added to the cache. This function
+/// blocks, so it should not be called on the main thread
+pub fn image_thumbnail(path: &Path) -> Result<Vec<u8>, DragonDisplayError> {
+    let cache_file = cache_file(path, "image")?;
+    if let Ok(data) = fs::read(&cache_file) {
+        return Ok(data);
+    }
+    let pixbuf = Pixbuf::from_file_at_scale(path, THUMBNAIL_SIZE, THUMBNAIL_SIZE, true).context(
+        GlibSnafu {
+            msg: format!("Could not load the image {}", path.display()),
+        },
+    )?;
+    store(&cache_file, &pixbuf)
+}
+
+/// Encode the thumbnail as png and write it to the cache file, a thumbnail that can not be
+/// cached is still returned
+pub fn store(cache_file: &Path, pixbuf: &Pixbuf) -> Result<Vec<u8>, DragonDisplayError> {
+    let data = pixbuf.save_to_bufferv("png", &[]).context(GlibSnafu {
+        msg: "Could not encode the thumbnail".to_string(),
+    })?;
+    if let Some(folder) = cache_file.parent() {
+        if fs::create_dir_all(folder).is_ok() {
+            // write to a temporary file first so a half written thumbnail is never read
+            let temporary = cache_file.with_extension("tmp");
+            if fs::write(&temporary, &data).is_ok() {
+                let _ = fs::rename(&temporary, cache_file);
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// The file in the cache folder for the thumbnail of the media file. The name depends on the
+/// path, modification time and size of the file, so a changed file gets a new thumbnail. The
+/// variant seperates thumbnails of the same file that were made with different settings
+pub fn cache_file(path: &Path, variant: &str) -> Result<PathBuf, DragonDisplayError> {
+    let metadata = fs::metadata(path).context(IOSnafu {
+        msg: format!("Could not read the file {}", path.display()),
+    })?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |m| m.as_nanos());
+    let path = path.to_str().context(OtherSnafu {
+        msg: "The file path is not valid unicode".to_string(),
+    })?;
+    let key = format!("{}\n{}\n{}\n{}", path, modified, metadata.len(), variant);
+    let checksum = glib::compute_checksum_for_string(glib::ChecksumType::Sha256, &key).context(
+        OtherSnafu {
+            msg: "Could not compute the thumbnail name".to_string(),
+        },
+    )?;
+    Ok(cache_folder().join(format!("{}.png", checksum)))
+}
+
+/// The folder for cached thumbnails in the XDG cache directory
+fn cache_folder() -> PathBuf {
+    glib::user_cache_dir()
+        .join("dragon-display")
+        .join("thumbnails")
+}
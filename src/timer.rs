@@ -0,0 +1,224 @@
+use gstreamer::prelude::*;
+use gstreamer::{ElementFactory, MessageView, Pipeline, State};
+use gtk::glib;
+
+use crate::runtime;
+use crate::video_thumbnail::format_duration;
+
+/// The pitch in hertz of the alarm that sounds when a timer expires
+const ALARM_FREQUENCY: f64 = 880.0;
+/// The amount of audio buffers of the alarm, one buffer holds about 23 milliseconds of sound
+const ALARM_BUFFERS: i32 = 40;
+
+/// What a timer counts down
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimerUnit {
+    #[default]
+    Seconds,
+    Minutes,
+    /// Rounds of combat, these count down when the initiative tracker starts a new round
+    Rounds,
+}
+
+/// The corner or center of the display where a timer is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimerPosition {
+    #[default]
+    TopRight,
+    TopLeft,
+    BottomRight,
+    BottomLeft,
+    Center,
+}
+
+/// The size at which a timer is shown on the display
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimerStyle {
+    #[default]
+    Small,
+    Large,
+}
+
+/// A countdown timer. The remaining time is in seconds for timers that count wall-clock time
+/// and in rounds for round timers
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timer {
+    pub name: String,
+    pub rounds: bool,
+    pub remaining: u32,
+    pub position: TimerPosition,
+    pub style: TimerStyle,
+    pub flash: bool,
+    pub sound: bool,
+    pub running: bool,
+}
+
+/// All timers as they are shown on the display
+#[derive(Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdTimers")]
+pub struct Timers(pub Vec<Timer>);
+
+impl TimerUnit {
+    pub const ALL: [TimerUnit; 3] = [TimerUnit::Seconds, TimerUnit::Minutes, TimerUnit::Rounds];
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimerUnit::Seconds => "Seconds",
+            TimerUnit::Minutes => "Minutes",
+            TimerUnit::Rounds => "Rounds",
+        }
+    }
+}
+
+impl TimerPosition {
+    pub const ALL: [TimerPosition; 5] = [
+        TimerPosition::TopRight,
+        TimerPosition::TopLeft,
+        TimerPosition::BottomRight,
+        TimerPosition::BottomLeft,
+        TimerPosition::Center,
+    ];
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|p| p == self).unwrap_or(0)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimerPosition::TopRight => "Top right",
+            TimerPosition::TopLeft => "Top left",
+            TimerPosition::BottomRight => "Bottom right",
+            TimerPosition::BottomLeft => "Bottom left",
+            TimerPosition::Center => "Center",
+        }
+    }
+
+    /// The horizontal and vertical alignment of the timers at this position
+    pub fn align(&self) -> (gtk::Align, gtk::Align) {
+        match self {
+            TimerPosition::TopRight => (gtk::Align::End, gtk::Align::Start),
+            TimerPosition::TopLeft => (gtk::Align::Start, gtk::Align::Start),
+            TimerPosition::BottomRight => (gtk::Align::End, gtk::Align::End),
+            TimerPosition::BottomLeft => (gtk::Align::Start, gtk::Align::End),
+            TimerPosition::Center => (gtk::Align::Center, gtk::Align::Center),
+        }
+    }
+}
+
+impl TimerStyle {
+    pub const ALL: [TimerStyle; 2] = [TimerStyle::Small, TimerStyle::Large];
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimerStyle::Small => "Small",
+            TimerStyle::Large => "Large",
+        }
+    }
+
+    /// The style class of the timer on the display
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            TimerStyle::Small => "timer-small",
+            TimerStyle::Large => "timer-large",
+        }
+    }
+}
+
+impl Timer {
+    /// Create a running timer that counts down amount of the unit
+    pub fn new(name: String, amount: u32, unit: TimerUnit) -> Self {
+        let remaining = match unit {
+            TimerUnit::Minutes => amount * 60,
+            TimerUnit::Seconds | TimerUnit::Rounds => amount,
+        };
+        Self {
+            name,
+            rounds: unit == TimerUnit::Rounds,
+            remaining,
+            running: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Count down one second or round, returns true if the timer expired because of it
+    pub fn count_down(&mut self) -> bool {
+        if !self.running || self.is_expired() {
+            return false;
+        }
+        self.remaining -= 1;
+        self.is_expired()
+    }
+
+    /// The remaining time as it is shown to the players
+    pub fn remaining_text(&self) -> String {
+        match (self.rounds, self.remaining) {
+            (true, 1) => "1 round".to_string(),
+            (true, rounds) => format!("{} rounds", rounds),
+            (false, seconds) => format_duration(seconds as u64),
+        }
+    }
+
+    /// The name of the timer followed by the remaining time
+    pub fn text(&self) -> String {
+        match self.name.is_empty() {
+            true => self.remaining_text(),
+            false => format!("{}: {}", self.name, self.remaining_text()),
+        }
+    }
+}
+
+/// Play a short beep to signal that a timer expired. Does nothing if no audio output is
+/// available
+pub fn play_alarm() {
+    if gstreamer::init().is_err() {
+        return;
+    }
+    let Ok(source) = ElementFactory::make("audiotestsrc")
+        .property("freq", ALARM_FREQUENCY)
+        .property("num-buffers", ALARM_BUFFERS)
+        .build()
+    else {
+        return;
+    };
+    let (Ok(convert), Ok(sink)) = (
+        ElementFactory::make("audioconvert").build(),
+        ElementFactory::make("autoaudiosink").build(),
+    ) else {
+        return;
+    };
+    let pipeline = Pipeline::new();
+    if pipeline.add_many([&source, &convert, &sink]).is_err()
+        || gstreamer::Element::link_many([&source, &convert, &sink]).is_err()
+        || pipeline.set_state(State::Playing).is_err()
+    {
+        let _ = pipeline.set_state(State::Null);
+        return;
+    }
+    // the pipeline is stopped when the beep finished playing
+    runtime().spawn_blocking(move || {
+        if let Some(bus) = pipeline.bus() {
+            for message in bus.iter_timed(gstreamer::ClockTime::NONE) {
+                if matches!(message.view(), MessageView::Eos(_) | MessageView::Error(_)) {
+                    break;
+                }
+            }
+        }
+        let _ = pipeline.set_state(State::Null);
+    });
+}
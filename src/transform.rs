@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use gtk::gio::prelude::SettingsExt;
+use gtk::{gio, glib};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{read_campaign_data, write_campaign_data};
+use crate::errors::DragonDisplayError;
+use crate::media::relative_path;
+
+/// Name of the file in the campaign data folder that holds the rotations of the media files
+const ROTATION_FILE: &str = "rotations.toml";
+
+/// A clockwise rotation in degrees, always between 0 (inclusive) and 360 (exclusive)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(from = "f32", into = "f32")]
+pub struct Rotation(f32);
+
+/// The rotation and mirroring of everything that is shown on a display. Mirroring is applied
+/// after the rotation, so a mirrored display keeps turning clockwise
+#[derive(Clone, Copy, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdDisplayTransform")]
+pub struct DisplayTransform {
+    pub rotation: Rotation,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self(degrees.rem_euclid(360.0))
+    }
+
+    pub fn degrees(&self) -> f32 {
+        self.0
+    }
+
+    /// The rotation turned further clockwise by the given degrees
+    pub fn rotated(&self, degrees: f32) -> Self {
+        Self::from_degrees(self.0 + degrees)
+    }
+
+    /// The size of the bounding box of a rectangle of the given size after the rotation. Right
+    /// angles are handled exactly, so rotated textures are not blurred by a fraction of a pixel
+    pub fn bounds(&self, width: f32, height: f32) -> (f32, f32) {
+        match self.0 {
+            d if d == 0.0 || d == 180.0 => (width, height),
+            d if d == 90.0 || d == 270.0 => (height, width),
+            d => {
+                let (sin, cos) = d.to_radians().sin_cos();
+                let (sin, cos) = (sin.abs(), cos.abs());
+                (width * cos + height * sin, width * sin + height * cos)
+            }
+        }
+    }
+}
+
+impl From<f32> for Rotation {
+    fn from(degrees: f32) -> Self {
+        Self::from_degrees(degrees)
+    }
+}
+
+impl From<Rotation> for f32 {
+    fn from(rotation: Rotation) -> Self {
+        rotation.0
+    }
+}
+
+impl DisplayTransform {
+    /// Read the transform of the display from the settings of the application
+    pub fn from_settings(settings: &gio::Settings) -> Self {
+        Self {
+            rotation: Rotation::from_degrees(settings.double("display-rotation") as f32),
+            flip_horizontal: settings.boolean("display-flip-horizontal"),
+            flip_vertical: settings.boolean("display-flip-vertical"),
+        }
+    }
+
+    /// Store the transform in the settings so it is used the next time the display opens
+    pub fn write_settings(&self, settings: &gio::Settings) -> Result<(), glib::BoolError> {
+        settings.set_double("display-rotation", self.rotation.degrees() as f64)?;
+        settings.set_boolean("display-flip-horizontal", self.flip_horizontal)?;
+        settings.set_boolean("display-flip-vertical", self.flip_vertical)
+    }
+
+    /// The transform of the display combined with the rotation of the media that is shown
+    pub fn with_media_rotation(&self, rotation: Rotation) -> Self {
+        Self {
+            rotation: self.rotation.rotated(rotation.degrees()),
+            ..*self
+        }
+    }
+
+    /// Returns true if the transform leaves the media unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The rotations of the media files in a campaign, stored by their path relative to the
+/// campaign folder. Files without a rotation are shown upright
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CampaignRotations {
+    #[serde(default)]
+    media: BTreeMap<String, Rotation>,
+}
+
+impl CampaignRotations {
+    /// Read the rotations of the campaign, a campaign without rotation file has no rotations
+    pub fn read(campaign_path: &str) -> Result<Self, DragonDisplayError> {
+        read_campaign_data(campaign_path, ROTATION_FILE)
+    }
+
+    /// Write the rotations to the campaign data folder
+    pub fn write(&self, campaign_path: &str) -> Result<(), DragonDisplayError> {
+        write_campaign_data(campaign_path, ROTATION_FILE, self)
+    }
+
+    /// The rotation of the file, no rotation if the file has none
+    pub fn get(&self, campaign_path: &str, file: &Path) -> Rotation {
+        self.media
+            .get(&relative_path(campaign_path, file))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Set the rotation of the file, a file without rotation is removed
+    pub fn set(&mut self, campaign_path: &str, file: &Path, rotation: Rotation) {
+        let key = relative_path(campaign_path, file);
+        if rotation == Rotation::default() {
+            self.media.remove(&key);
+        } else {
+            self.media.insert(key, rotation);
+        }
+    }
+}
@@ -1,16 +1,15 @@
 use adw::Application;
 use gtk::prelude::ObjectExt;
 use gtk::{gio, glib};
-use snafu::{ensure, OptionExt as _, Report, ResultExt};
-use std::env;
+use snafu::{ensure, Report};
+use std::path::Path;
 
 use crate::campaign::DdCampaign;
-use crate::config::read_campaign_from_config;
-use crate::errors::{DragonDisplayError, IOSnafu, InvalidNameSnafu, InvalidPathSnafu, OtherSnafu};
+use crate::config::{config_dir, read_campaign_from_config};
+use crate::errors::{DragonDisplayError, InvalidNameSnafu, InvalidPathSnafu};
 
 mod imp {
     use std::cell::RefCell;
-    use std::env;
     use std::rc::Rc;
     use std::sync::OnceLock;
 
@@ -23,11 +22,11 @@ mod imp {
         Label, Stack,
     };
     use gtk::{prelude::*, ResponseType};
-    use snafu::{OptionExt, ResultExt};
+    use snafu::OptionExt;
 
     use crate::campaign::DdCampaign;
-    use crate::config::SynchronizationOption;
-    use crate::errors::{IOSnafu, OtherSnafu};
+    use crate::config::{default_campaign_dir, SynchronizationOption};
+    use crate::errors::OtherSnafu;
     use crate::try_emit;
 
     // Object holding the state
@@ -164,22 +163,15 @@ mod imp {
         #[template_callback]
         fn handle_default(&self, button: Button) {
             let obj = self.obj();
-            let working_dir = try_emit!(
+            let default_dir = default_campaign_dir().join(self.entry.text().trim());
+            let default_path = try_emit!(
                 obj,
-                env::current_dir().context(IOSnafu {
-                    msg: "Could not get current working directory".to_owned()
+                default_dir.to_str().context(OtherSnafu {
+                    msg: "The default campaign folder is not a valid path".to_owned()
                 }),
                 true
-            );
-            let working_dir = try_emit!(
-                obj,
-                working_dir.to_str().context(OtherSnafu {
-                    msg: { "Could not get current working directory".to_owned() }
-                }),
-                true
-            );
-
-            let default_path = format!("{}/{}", working_dir, self.entry.text().to_string());
+            )
+            .to_string();
             try_emit!(obj, valid_path(&default_path), false);
             self.path.replace(default_path);
             self.handle_finish(button);
@@ -362,27 +354,44 @@ pub fn valid_name(name: &str) -> Result<(), DragonDisplayError> {
     Ok(())
 }
 
+/// Check if the folder can hold the images of a new campaign. Removing a campaign removes the
+/// media in its folder, so the folder can not be shared with another campaign, the config or
+/// the home folder
 pub fn valid_path(path: &str) -> Result<(), DragonDisplayError> {
+    let path = Path::new(path);
+    ensure!(
+        path.is_absolute(),
+        InvalidPathSnafu {
+            msg: "The folder for campaign images should be an absolute path".to_owned()
+        }
+    );
     let campaign_list = read_campaign_from_config()?;
     for campaign in campaign_list {
+        let campaign_path = Path::new(&campaign.path);
         ensure!(
-            campaign.path != path,
+            campaign_path != path,
             InvalidPathSnafu {
                 msg: "Another campaign already uses this folder".to_owned()
             }
         );
+        ensure!(
+            !path.starts_with(campaign_path) && !campaign_path.starts_with(path),
+            InvalidPathSnafu {
+                msg: format!("The folder overlaps with the folder of {}", campaign.name)
+            }
+        );
     }
-    let current_dir = env::current_dir().context(IOSnafu {
-        msg: "Could not get current working directory".to_owned(),
-    })?;
-    let current_dir_str = current_dir.to_str().context(OtherSnafu {
-        msg: "Could not get current working directory".to_owned(),
-    })?;
+    let config_dir = config_dir();
+    ensure!(
+        !path.starts_with(&config_dir) && !config_dir.starts_with(path),
+        InvalidPathSnafu {
+            msg: "Cannot use the config folder as a folder for campaign images".to_owned()
+        }
+    );
     ensure!(
-        path != current_dir_str,
+        path != glib::home_dir(),
         InvalidPathSnafu {
-            msg: "Cannot use the current working directory as a folder for campaign images"
-                .to_owned()
+            msg: "Cannot use the home folder as a folder for campaign images".to_owned()
         }
     );
 
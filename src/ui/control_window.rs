@@ -1,36 +1,72 @@
-use std::fs::read_dir;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use gtk::gio::ListStore;
 use gtk::glib::clone;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
-use gtk::{gio, glib, StringObject};
+use gtk::{gio, glib, FileChooserDialog, ResponseType, StringObject, ToggleButton};
 use gtk::{prelude::*, Label};
 use snafu::ResultExt;
 use snafu::{OptionExt, Report};
 
-use crate::config::{IMAGE_EXTENSIONS, VIDEO_EXTENSIONS, VTT_EXTENSIONS};
+use crate::color_grade::{CampaignColorGrades, ColorGrade};
 use crate::fogofwar::DdFogOfWar;
+use crate::initiative::Combat;
+use crate::media::{
+    campaign_folders, categories, in_category, matches_search, seperate_media, MediaOrder, SortMode,
+};
+use crate::metadata::CampaignMetadata;
+use crate::pdf::PdfPage;
+use crate::snapshot::snapshot_name;
+use crate::text_card::TextCard;
+use crate::timer::Timers;
+use crate::transform::{CampaignRotations, Rotation};
+use crate::weather::WeatherSettings;
+use crate::widgets::initiative_panel::DdInitiativePanel;
+use crate::widgets::metadata_editor::DdMetadataEditor;
+use crate::widgets::playlist_panel::DdPlaylistPanel;
+use crate::widgets::text_card_panel::DdTextCardPanel;
 use crate::widgets::thumbnail::MediaType;
 use crate::widgets::thumbnail_grid::DdThumbnailGrid;
 use crate::widgets::vtt_area::DdVttArea;
 use crate::{errors::*, try_emit};
 
+/// The time to wait for more changes in the campaign folder before updating the thumbnails
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
 mod imp {
 
     use std::cell::{Cell, OnceCell, RefCell};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
     use std::sync::OnceLock;
 
     use glib::subclass::InitializingObject;
     use gtk::glib::subclass::Signal;
     use gtk::subclass::prelude::*;
     use gtk::{
-        glib, template_callbacks, Box, Button, CompositeTemplate, Stack, StackSwitcher,
-        StringObject,
+        gio, glib, template_callbacks, Box, Button, CompositeTemplate, SearchEntry, SpinButton,
+        Stack, StackSwitcher, StringObject, ToggleButton,
     };
     use gtk::{prelude::*, DropDown};
 
+    use crate::color_grade::{CampaignColorGrades, ColorGrade};
     use crate::fogofwar::DdFogOfWar;
+    use crate::initiative::Combat;
+    use crate::media::MediaOrder;
+    use crate::metadata::CampaignMetadata;
+    use crate::pdf::PdfPage;
+    use crate::text_card::TextCard;
+    use crate::timer::Timers;
+    use crate::transform::{CampaignRotations, Rotation};
+    use crate::weather::WeatherSettings;
+    use crate::widgets::category_tree::DdCategoryTree;
+    use crate::widgets::color_grade_controls::DdColorGradeControls;
+    use crate::widgets::pdf_controls::DdPdfControls;
+    use crate::widgets::playlist_panel::DdPlaylistPanel;
+    use crate::widgets::timer_panel::DdTimerPanel;
+    use crate::widgets::weather_controls::DdWeatherControls;
 
     // Object holding the state
     #[derive(CompositeTemplate, Default)]
@@ -41,20 +77,77 @@ mod imp {
         #[template_child]
         pub stackswitcher: TemplateChild<StackSwitcher>,
         #[template_child]
+        pub search_entry: TemplateChild<SearchEntry>,
+        #[template_child]
+        pub sort_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub favorites_button: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub record_button: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub category_tree: TemplateChild<DdCategoryTree>,
+        #[template_child]
+        pub display_tools: TemplateChild<Box>,
+        #[template_child]
+        pub display_targets: TemplateChild<Box>,
+        /// A toggle button for every display window, the active displays show the media
+        pub display_buttons: RefCell<Vec<ToggleButton>>,
+        #[template_child]
         pub images: TemplateChild<Box>,
         #[template_child]
         pub videos: TemplateChild<Box>,
         #[template_child]
         pub vtts: TemplateChild<Box>,
         #[template_child]
+        pub pdfs: TemplateChild<Box>,
+        #[template_child]
+        pub pdf_controls: TemplateChild<DdPdfControls>,
+        #[template_child]
+        pub rotation_spin: TemplateChild<SpinButton>,
+        #[template_child]
         pub options_button: TemplateChild<Button>,
         #[template_child]
         pub vtt_dropdown: TemplateChild<DropDown>,
         #[template_child]
         pub update_button: TemplateChild<Button>,
+        #[template_child]
+        pub vtt_metadata_button: TemplateChild<Button>,
+        #[template_child]
+        pub playlists: TemplateChild<Box>,
+        pub playlist_panel: OnceCell<DdPlaylistPanel>,
+        #[template_child]
+        pub text_cards: TemplateChild<Box>,
+        #[template_child]
+        pub initiative: TemplateChild<Box>,
+        #[template_child]
+        pub timer_panel: TemplateChild<DdTimerPanel>,
+        #[template_child]
+        pub weather_controls: TemplateChild<DdWeatherControls>,
+        #[template_child]
+        pub color_grade_controls: TemplateChild<DdColorGradeControls>,
+        /// The last known round of the initiative tracker, round timers count down when it
+        /// increases
+        pub combat_round: Cell<u32>,
         pub fow: RefCell<Option<DdFogOfWar>>,
         pub campaign_path: OnceCell<String>,
+        pub metadata: RefCell<CampaignMetadata>,
+        pub color_grades: RefCell<CampaignColorGrades>,
+        pub rotations: RefCell<CampaignRotations>,
+        /// The media file on the display that the color grade and rotation belong to
+        pub shown_media: RefCell<Option<PathBuf>>,
+        /// The rotation of the media on the display
+        pub media_rotation: Cell<Rotation>,
+        pub order: RefCell<MediaOrder>,
         pub has_images: Cell<bool>,
+        /// All vtts in the campaign folder, the dropdown only holds the vtts that pass the filter
+        pub vtt_files: RefCell<Vec<PathBuf>>,
+        /// The vtt that is shown in the vtt area, None if no vtt is selected
+        pub selected_vtt: RefCell<Option<String>>,
+        /// True when the campaign folder and its subfolders are watched for changes
+        pub watching: Cell<bool>,
+        pub file_monitors: RefCell<HashMap<PathBuf, gio::FileMonitor>>,
+        /// The pending update of the thumbnails after a change in the campaign folder
+        pub update_source: RefCell<Option<glib::SourceId>>,
     }
 
     // The central trait for subclassing a GObject
@@ -67,6 +160,11 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             Button::ensure_type();
+            DdCategoryTree::ensure_type();
+            DdColorGradeControls::ensure_type();
+            DdPdfControls::ensure_type();
+            DdTimerPanel::ensure_type();
+            DdWeatherControls::ensure_type();
 
             klass.bind_template();
             klass.bind_template_callbacks()
@@ -94,19 +192,63 @@ mod imp {
             self.obj().emit_by_name::<()>("reset-display", &[]);
         }
 
+        #[template_callback]
+        fn handle_back(&self, _: Button) {
+            self.obj().emit_by_name::<()>("back", &[]);
+        }
+
+        #[template_callback]
+        fn handle_forward(&self, _: Button) {
+            self.obj().emit_by_name::<()>("forward", &[]);
+        }
+
+        #[template_callback]
+        fn handle_export_recap(&self, _: Button) {
+            self.obj().emit_by_name::<()>("export-recap", &[]);
+        }
+
+        #[template_callback]
+        fn handle_snapshot(&self, _: Button) {
+            self.obj().emit_by_name::<()>("snapshot", &[]);
+        }
+
+        #[template_callback]
+        fn handle_snapshot_as(&self, _: Button) {
+            self.obj().choose_snapshot_location();
+        }
+
+        #[template_callback]
+        fn handle_record(&self, button: ToggleButton) {
+            let recording = button.is_active();
+            match recording {
+                true => button.set_label("Stop recording"),
+                false => button.set_label("Record"),
+            }
+            self.obj().emit_by_name::<()>("record", &[&recording]);
+        }
+
         #[template_callback]
         fn handle_rotate90(&self, _: Button) {
-            self.obj().emit_by_name::<()>("rotate90", &[]);
+            self.obj().rotate_media(90.0);
         }
 
         #[template_callback]
         fn handle_rotate180(&self, _: Button) {
-            self.obj().emit_by_name::<()>("rotate180", &[]);
+            self.obj().rotate_media(180.0);
         }
 
         #[template_callback]
         fn handle_rotate270(&self, _: Button) {
-            self.obj().emit_by_name::<()>("rotate270", &[]);
+            self.obj().rotate_media(270.0);
+        }
+
+        #[template_callback]
+        fn handle_rotation(&self, spin: SpinButton) {
+            let rotation = Rotation::from_degrees(spin.value() as f32);
+            // the spin button is also updated when other media is shown
+            if rotation != self.media_rotation.get() {
+                self.obj().save_rotation(rotation);
+            }
         }
 
         #[template_callback]
@@ -119,6 +261,23 @@ mod imp {
             self.obj().emit_by_name::<()>("grid", &[]);
         }
 
+        #[template_callback]
+        fn handle_favorites(&self, _: ToggleButton) {
+            self.obj().apply_filter();
+        }
+
+        #[template_callback]
+        fn handle_vtt_metadata(&self, button: Button) {
+            let path = match self.vtt_dropdown.selected_item() {
+                Some(p) if self.vtt_dropdown.selected() > 0 => p,
+                _ => return,
+            };
+            let path = path
+                .downcast_ref::<StringObject>()
+                .expect("Should be a stringobject");
+            self.obj().edit_metadata(path.string().as_str(), &button);
+        }
+
         #[template_callback]
         fn handle_update(&self, button: Button) {
             button.set_sensitive(false);
@@ -136,8 +295,11 @@ mod imp {
                 Some(fow) => fow.clone(),
                 None => return,
             };
+            self.obj().playlist_panel().stop();
+            self.obj().load_media_settings(path.string().as_str());
             self.obj()
                 .emit_by_name::<()>("update", &[&path.string().to_string(), &fow]);
+            self.obj().apply_media_settings();
         }
     }
 
@@ -150,14 +312,46 @@ mod imp {
                     Signal::builder("refresh").build(),
                     Signal::builder("options").build(),
                     Signal::builder("reset-display").build(),
-                    Signal::builder("rotate90").build(),
-                    Signal::builder("rotate180").build(),
-                    Signal::builder("rotate270").build(),
+                    Signal::builder("back").build(),
+                    Signal::builder("forward").build(),
+                    Signal::builder("export-recap").build(),
+                    Signal::builder("snapshot").build(),
+                    Signal::builder("record")
+                        .param_types([bool::static_type()])
+                        .build(),
+                    Signal::builder("snapshot-as")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("rotation")
+                        .param_types([f32::static_type(), bool::static_type()])
+                        .build(),
                     Signal::builder("image")
                         .param_types([String::static_type()])
                         .build(),
                     Signal::builder("video")
-                        .param_types([String::static_type()])
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                    Signal::builder("pdf")
+                        .param_types([PdfPage::static_type()])
+                        .build(),
+                    Signal::builder("show-card")
+                        .param_types([TextCard::static_type()])
+                        .build(),
+                    Signal::builder("hide-card").build(),
+                    Signal::builder("initiative")
+                        .param_types([Combat::static_type()])
+                        .build(),
+                    Signal::builder("timers")
+                        .param_types([Timers::static_type()])
+                        .build(),
+                    Signal::builder("weather")
+                        .param_types([WeatherSettings::static_type()])
+                        .build(),
+                    Signal::builder("grade")
+                        .param_types([ColorGrade::static_type(), bool::static_type()])
+                        .build(),
+                    Signal::builder("paused")
+                        .param_types([bool::static_type()])
                         .build(),
                     Signal::builder("fit").build(),
                     Signal::builder("grid").build(),
@@ -210,39 +404,178 @@ impl DdControlWindow {
             .campaign_path
             .set(campaign_path.clone())
             .expect("Expected campaign path to not be set");
+        object
+            .imp()
+            .metadata
+            .replace(CampaignMetadata::read(&campaign_path)?);
+        object
+            .imp()
+            .color_grades
+            .replace(CampaignColorGrades::read(&campaign_path)?);
+        object
+            .imp()
+            .rotations
+            .replace(CampaignRotations::read(&campaign_path)?);
+        object
+            .imp()
+            .order
+            .replace(MediaOrder::read(&campaign_path)?);
         object
             .imp()
             .stackswitcher
             .set_stack(Some(&object.imp().stack));
-        let (images, vtts, videos) = object.seperate_media(campaign_path)?;
-        // ---- Image page setup ------
-        if images.len() == 0 {
-            let label = Label::builder()
-                .label("You have no images")
-                .vexpand(true)
-                .hexpand(true)
-                .valign(gtk::Align::Center)
-                .halign(gtk::Align::Center)
-                .build();
-            object.imp().images.append(&label);
-        } else {
-            let image_grid = DdThumbnailGrid::new(images, &MediaType::Image);
-            object.imp().images.append(&image_grid);
-            image_grid.connect_path(clone!(@weak object => move |_, path|{
-                object.emit_by_name::<()>("image", &[&path]);
+        object
+            .imp()
+            .category_tree
+            .connect_category(clone!(@weak object => move |_, _| {
+                object.apply_filter();
+            }));
+        // rotation, fit and grid only apply to images and pdfs
+        object
+            .imp()
+            .stack
+            .connect_visible_child_name_notify(clone!(@weak object => move |stack| {
+                let visible = matches!(stack.visible_child_name().as_deref(), Some("images" | "pdfs"));
+                object.imp().display_tools.set_visible(visible);
+            }));
+        // ---- Sort setup ------
+        let labels: Vec<&str> = SortMode::ALL.iter().map(|m| m.label()).collect();
+        let sort_dropdown = &object.imp().sort_dropdown;
+        sort_dropdown.set_model(Some(&gtk::StringList::new(&labels)));
+        let mode = object.imp().order.borrow().mode;
+        let index = SortMode::ALL.iter().position(|m| *m == mode).unwrap_or(0);
+        sort_dropdown.set_selected(index as u32);
+        sort_dropdown.connect_selected_notify(clone!(@weak object => move |dropdown| {
+            let mode = match SortMode::ALL.get(dropdown.selected() as usize) {
+                Some(m) => *m,
+                None => return,
+            };
+            object.imp().order.borrow_mut().mode = mode;
+            object.sort_pages();
+            let result = object.imp().order.borrow().write(object.campaign_path());
+            try_emit!(object, result, false);
+        }));
+        // ---- Search setup ------
+        let search_entry = &object.imp().search_entry;
+        search_entry.connect_search_changed(clone!(@weak object => move |_| {
+            object.apply_filter();
+        }));
+        search_entry.connect_activate(clone!(@weak object => move |_| {
+            object.activate_first_match();
+        }));
+        search_entry.connect_next_match(clone!(@weak object => move |_| {
+            if let Some(grid) = object.visible_grid() {
+                grid.focus_next(true);
+            }
+        }));
+        search_entry.connect_previous_match(clone!(@weak object => move |_| {
+            if let Some(grid) = object.visible_grid() {
+                grid.focus_next(false);
+            }
+        }));
+        search_entry.connect_stop_search(|entry| {
+            entry.set_text("");
+        });
+        // the arrow down key moves from the search entry to the results
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(
+            clone!(@weak object => @default-return glib::Propagation::Proceed, move |_, key, _, _| {
+                if key != gtk::gdk::Key::Down {
+                    return glib::Propagation::Proceed;
+                }
+                match object.visible_grid() {
+                    Some(grid) => {
+                        grid.focus_next(true);
+                        glib::Propagation::Stop
+                    }
+                    None => glib::Propagation::Proceed,
+                }
+            }),
+        );
+        search_entry.add_controller(key_controller);
+        let search_action = gio::SimpleAction::new("search", None);
+        search_action.connect_activate(clone!(@weak object => move |_, _| {
+            object.imp().search_entry.grab_focus();
+        }));
+        object.add_action(&search_action);
+        app.set_accels_for_action("win.search", &["<Control>f"]);
+        // ---- Playlist page setup ------
+        let playlist_panel = DdPlaylistPanel::new(campaign_path.clone())?;
+        playlist_panel.connect_image(clone!(@weak object => move |_, path| {
+            object.media_shown(&path);
+            object.emit_by_name::<()>("image", &[&path]);
+            object.apply_media_settings();
+        }));
+        playlist_panel.connect_video(clone!(@weak object => move |_, path| {
+            object.media_shown(&path);
+            object.emit_by_name::<()>("video", &[&path, &false]);
+            object.apply_media_settings();
+        }));
+        playlist_panel.connect_paused(clone!(@weak object => move |_, paused| {
+            object.emit_by_name::<()>("paused", &[&paused]);
+        }));
+        playlist_panel.connect_error(clone!(@weak object => move |_, msg, fatal| {
+            object.emit_by_name::<()>("error", &[&msg, &fatal]);
+        }));
+        object.imp().playlists.append(&playlist_panel);
+        object
+            .imp()
+            .playlist_panel
+            .set(playlist_panel)
+            .expect("Expected playlist panel to not be set");
+        // ---- Text card page setup ------
+        let text_card_panel = DdTextCardPanel::new(campaign_path.clone())?;
+        text_card_panel.connect_show(clone!(@weak object => move |_, card| {
+            object.emit_by_name::<()>("show-card", &[&card]);
+        }));
+        text_card_panel.connect_hide(clone!(@weak object => move |_| {
+            object.emit_by_name::<()>("hide-card", &[]);
+        }));
+        text_card_panel.connect_error(clone!(@weak object => move |_, msg, fatal| {
+            object.emit_by_name::<()>("error", &[&msg, &fatal]);
+        }));
+        object.imp().text_cards.append(&text_card_panel);
+        // ---- Initiative page setup ------
+        let initiative_panel = DdInitiativePanel::new(campaign_path)?;
+        object.imp().combat_round.set(initiative_panel.round());
+        initiative_panel.connect_changed(clone!(@weak object => move |_, combat| {
+            let previous = object.imp().combat_round.replace(combat.round);
+            if previous > 0 && combat.round > previous {
+                object.imp().timer_panel.next_round();
+            }
+            object.emit_by_name::<()>("initiative", &[&combat]);
+        }));
+        initiative_panel.connect_error(clone!(@weak object => move |_, msg, fatal| {
+            object.emit_by_name::<()>("error", &[&msg, &fatal]);
+        }));
+        object.imp().initiative.append(&initiative_panel);
+        // ---- Timer page setup ------
+        object
+            .imp()
+            .timer_panel
+            .connect_changed(clone!(@weak object => move |_, timers| {
+                object.emit_by_name::<()>("timers", &[&timers]);
+            }));
+        // ---- Weather page setup ------
+        object
+            .imp()
+            .weather_controls
+            .connect_changed(clone!(@weak object => move |_, settings| {
+                object.emit_by_name::<()>("weather", &[&settings]);
+            }));
+        // ---- Color grade setup ------
+        object
+            .imp()
+            .color_grade_controls
+            .connect_changed(clone!(@weak object => move |_, grade| {
+                object.save_color_grade(grade);
+                object.emit_by_name::<()>("grade", &[&grade, &true]);
             }));
-        }
         // ---- Vtt page setup ------
-        // create the list model
-        let vtts = vtts
-            .iter()
-            .flat_map(|f| f.to_str())
-            .map(|s| StringObject::new(s))
-            .collect::<Vec<_>>();
+        // create the list model, the vtts are added when the media is shown
         let vtt_model = ListStore::new::<StringObject>();
         let no_selection = StringObject::new("<No selection>");
         vtt_model.append(&no_selection);
-        vtt_model.extend_from_slice(vtts.as_slice());
 
         object.imp().vtt_dropdown.set_model(Some(&vtt_model));
         object.imp().vtts.allocation();
@@ -250,9 +583,21 @@ impl DdControlWindow {
             .imp()
             .vtt_dropdown
             .connect_selected_notify(clone!(@weak object => move |dropdown| {
+                // the position of the selected vtt changes when the dropdown is reordered, the
+                // vtt area is only replaced when another vtt is selected
+                let selected = dropdown
+                    .selected_item()
+                    .and_downcast::<StringObject>()
+                    .filter(|_| dropdown.selected() > 0)
+                    .map(|s| s.string().to_string());
+                if *object.imp().selected_vtt.borrow() == selected {
+                    return;
+                }
+                object.imp().selected_vtt.replace(selected);
                 if let Some(child) = object.imp().vtts.first_child() {
                     object.imp().vtts.remove(&child);
                 }
+                object.imp().vtt_metadata_button.set_sensitive(dropdown.selected() > 0);
                 if dropdown.selected() == 0 {
                     return;
                 }
@@ -268,104 +613,611 @@ impl DdControlWindow {
                 object.imp().vtts.append(&vtt_area);
             }));
 
-        // ---- video page setup ------
-        if videos.len() == 0 {
-            let label = Label::builder()
-                .label("You have no videos")
-                .vexpand(true)
-                .hexpand(true)
-                .valign(gtk::Align::Center)
-                .halign(gtk::Align::Center)
-                .build();
-            object.imp().videos.append(&label);
-        } else {
-            let video_grid = DdThumbnailGrid::new(videos, &MediaType::Video);
-            object.imp().videos.append(&video_grid);
-            video_grid.connect_path(clone!(@weak object => move |_, path| {
-                object.emit_by_name::<()>("video", &[&path]);
-            }));
-        }
+        // ---- Pdf page setup ------
+        let pdf_controls = &object.imp().pdf_controls;
+        pdf_controls.connect_changed(clone!(@weak object => move |_, page| {
+            object.emit_by_name::<()>("pdf", &[&page]);
+        }));
+        pdf_controls.connect_error(clone!(@weak object => move |_, msg, fatal| {
+            object.emit_by_name::<()>("error", &[&msg, &fatal]);
+        }));
+
+        // ---- Image and video page setup ------
+        object.update()?;
         Ok(object)
     }
 
-    /// Update the grid of thumbnails
+    /// Read the media in the campaign folder and its subfolders, update the categories and
+    /// show the media that passes the category and search filter
     pub fn update(&self) -> Result<(), DragonDisplayError> {
-        let campaign_path = self
+        let campaign_path = self.campaign_path();
+        let mut media = seperate_media(campaign_path)?;
+        {
+            let order = self.imp().order.borrow();
+            order.sort(campaign_path, &mut media.images);
+            order.sort(campaign_path, &mut media.vtts);
+            order.sort(campaign_path, &mut media.videos);
+            order.sort(campaign_path, &mut media.pdfs);
+        }
+        let categories = categories(
+            campaign_path,
+            media
+                .images
+                .iter()
+                .chain(&media.vtts)
+                .chain(&media.videos)
+                .chain(&media.pdfs),
+        );
+        self.update_page(&self.imp().images, media.images, &MediaType::Image);
+        self.update_page(&self.imp().videos, media.videos, &MediaType::Video);
+        // the pdf that the controls belong to could have been removed
+        let pdf_controls = &self.imp().pdf_controls;
+        if !media
+            .pdfs
+            .iter()
+            .any(|p| pdf_controls.is_showing(&p.to_string_lossy()))
+        {
+            pdf_controls.set_visible(false);
+        }
+        self.update_page(&self.imp().pdfs, media.pdfs, &MediaType::Pdf);
+        self.imp().vtt_files.replace(media.vtts);
+        self.show_metadata();
+        if self.imp().watching.get() {
+            self.watch_folders()?;
+        }
+        self.imp().category_tree.set_categories(categories);
+        self.apply_filter();
+        Ok(())
+    }
+
+    /// Sort the thumbnails in the order of the sort mode, the thumbnails are reordered without
+    /// reloading them
+    fn sort_pages(&self) {
+        let campaign_path = self.campaign_path();
+        let order = self.imp().order.borrow();
+        for (page, t) in [
+            (&self.imp().images, MediaType::Image),
+            (&self.imp().videos, MediaType::Video),
+            (&self.imp().pdfs, MediaType::Pdf),
+        ] {
+            if let Some(grid) = page.first_child().and_downcast::<DdThumbnailGrid>() {
+                let mut files: Vec<PathBuf> = grid.files().into_iter().map(PathBuf::from).collect();
+                order.sort(campaign_path, &mut files);
+                grid.update(files, &t);
+            }
+        }
+        order.sort(campaign_path, &mut self.imp().vtt_files.borrow_mut());
+        drop(order);
+        // the vtt dropdown is filled from the sorted vtts by the filter
+        self.apply_filter();
+    }
+
+    /// Move the file to the position of the target file in the grid, this switches to the
+    /// manual sort mode
+    fn reorder(&self, grid: &DdThumbnailGrid, file: &str, target: &str) {
+        let files: Vec<PathBuf> = grid.files().into_iter().map(PathBuf::from).collect();
+        self.imp().order.borrow_mut().move_file(
+            self.campaign_path(),
+            &files,
+            Path::new(file),
+            Path::new(target),
+        );
+        let manual = SortMode::ALL
+            .iter()
+            .position(|m| *m == SortMode::Manual)
+            .unwrap_or_default() as u32;
+        if self.imp().sort_dropdown.selected() != manual {
+            // the dropdown sorts the pages and saves the order
+            self.imp().sort_dropdown.set_selected(manual);
+            return;
+        }
+        self.sort_pages();
+        let result = self.imp().order.borrow().write(self.campaign_path());
+        try_emit!(self, result, false);
+    }
+
+    /// Remember the media as recently shown, the order is only written when it changed
+    fn media_shown(&self, path: &str) {
+        self.load_media_settings(path);
+        let mut order = self.imp().order.borrow_mut();
+        if !order.shown(self.campaign_path(), Path::new(path)) {
+            return;
+        }
+        let result = order.write(self.campaign_path());
+        drop(order);
+        try_emit!(self, result, false);
+    }
+
+    /// Show the color grade and rotation of the media in the controls, they are applied to the
+    /// display with apply_media_settings once the media is shown
+    fn load_media_settings(&self, path: &str) {
+        let campaign_path = self.campaign_path();
+        let path = PathBuf::from(path);
+        let grade = self.imp().color_grades.borrow().get(campaign_path, &path);
+        let rotation = self.imp().rotations.borrow().get(campaign_path, &path);
+        self.imp().shown_media.replace(Some(path));
+        self.imp().color_grade_controls.set_grade(&grade);
+        self.show_rotation(rotation);
+    }
+
+    /// Apply the color grade and rotation of the shown media to the display without animating,
+    /// this is done after the media signal so the previous media never gets the new settings
+    fn apply_media_settings(&self) {
+        let path = match &*self.imp().shown_media.borrow() {
+            Some(p) => p.clone(),
+            None => return,
+        };
+        let grade = self
+            .imp()
+            .color_grades
+            .borrow()
+            .get(self.campaign_path(), &path);
+        self.emit_by_name::<()>("grade", &[&grade, &false]);
+        let rotation = self.imp().media_rotation.get();
+        self.emit_by_name::<()>("rotation", &[&rotation.degrees(), &false]);
+    }
+
+    /// The rotation of the media that is shown, as it is set in the controls
+    pub fn media_rotation(&self) -> Rotation {
+        self.imp().media_rotation.get()
+    }
+
+    /// Show the rotation of the media on the display in the controls without saving it
+    pub fn show_rotation(&self, rotation: Rotation) {
+        self.imp().media_rotation.set(rotation);
+        self.imp()
+            .rotation_spin
+            .set_value(rotation.degrees() as f64);
+    }
+
+    /// Turn the media on the display further clockwise by the given degrees
+    fn rotate_media(&self, degrees: f32) {
+        let rotation = self.imp().media_rotation.get().rotated(degrees);
+        // the spin button saves the rotation when its value changes
+        self.imp()
+            .rotation_spin
+            .set_value(rotation.degrees() as f64);
+    }
+
+    /// Remember the rotation for the media on the display and rotate the display
+    fn save_rotation(&self, rotation: Rotation) {
+        self.imp().media_rotation.set(rotation);
+        self.emit_by_name::<()>("rotation", &[&rotation.degrees(), &true]);
+        let Some(path) = self.imp().shown_media.borrow().clone() else {
+            return;
+        };
+        let campaign_path = self.campaign_path();
+        let mut rotations = self.imp().rotations.borrow_mut();
+        rotations.set(campaign_path, &path, rotation);
+        let result = rotations.write(campaign_path);
+        drop(rotations);
+        try_emit!(self, result, false);
+    }
+
+    /// Remember the color grade for the media on the display
+    fn save_color_grade(&self, grade: ColorGrade) {
+        let Some(path) = self.imp().shown_media.borrow().clone() else {
+            return;
+        };
+        let campaign_path = self.campaign_path();
+        let mut color_grades = self.imp().color_grades.borrow_mut();
+        color_grades.set(campaign_path, &path, grade);
+        let result = color_grades.write(campaign_path);
+        drop(color_grades);
+        try_emit!(self, result, false);
+    }
+
+    /// Only show the media in the selected category that matches the search query. The
+    /// thumbnails are filtered in place and the match counts are shown in the page titles
+    fn apply_filter(&self) {
+        let campaign_path = self.campaign_path();
+        let category = self.imp().category_tree.selected();
+        let query = self.imp().search_entry.text().to_string();
+        let favorites = self.imp().favorites_button.is_active();
+        let metadata = self.imp().metadata.borrow();
+        let matches = |f: &Path| {
+            let file_metadata = metadata.get(campaign_path, f);
+            in_category(campaign_path, f, &category)
+                && matches_search(f, &file_metadata.tags, &query)
+                && (!favorites || file_metadata.favorite)
+        };
+
+        for (name, title, page) in [
+            ("images", "Images", &self.imp().images),
+            ("videos", "Videos", &self.imp().videos),
+            ("pdfs", "PDFs", &self.imp().pdfs),
+        ] {
+            let count = match page.first_child().and_downcast::<DdThumbnailGrid>() {
+                Some(grid) => {
+                    grid.set_filter(Some(
+                        grid.files()
+                            .into_iter()
+                            .filter(|f| matches(Path::new(f)))
+                            .collect(),
+                    ));
+                    grid.match_count()
+                }
+                None => 0,
+            };
+            self.set_page_title(name, title, count);
+        }
+
+        let vtts: Vec<PathBuf> = self
+            .imp()
+            .vtt_files
+            .borrow()
+            .iter()
+            .filter(|f| matches(f))
+            .cloned()
+            .collect();
+        self.set_page_title("vtts", "VTTs", vtts.len());
+        // the selected vtt is kept in the dropdown, so its fog of war is not lost by searching
+        let mut vtts = vtts;
+        if self.imp().vtt_dropdown.selected() > 0 {
+            let selected = self
+                .imp()
+                .vtt_dropdown
+                .selected_item()
+                .and_downcast::<StringObject>()
+                .map(|s| PathBuf::from(s.string().as_str()));
+            if let Some(selected) = selected {
+                if !vtts.contains(&selected) && self.imp().vtt_files.borrow().contains(&selected) {
+                    vtts.push(selected);
+                }
+            }
+        }
+        self.update_vtts(vtts);
+    }
+
+    /// Show the favorite status, tags and notes on the thumbnails
+    fn show_metadata(&self) {
+        let campaign_path = self.campaign_path();
+        let metadata = self.imp().metadata.borrow();
+        for page in [&self.imp().images, &self.imp().videos, &self.imp().pdfs] {
+            if let Some(grid) = page.first_child().and_downcast::<DdThumbnailGrid>() {
+                for thumbnail in grid.thumbnails() {
+                    thumbnail
+                        .set_metadata(&metadata.get(campaign_path, Path::new(&thumbnail.file())));
+                }
+            }
+        }
+    }
+
+    /// Open an editor for the tags, favorite status and notes of the file next to the widget
+    fn edit_metadata(&self, file: &str, widget: &impl IsA<gtk::Widget>) {
+        let path = PathBuf::from(file);
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(file)
+            .to_string();
+        let metadata = self
             .imp()
+            .metadata
+            .borrow()
+            .get(self.campaign_path(), &path);
+        let editor = DdMetadataEditor::new(widget, &file_name, &metadata);
+        editor.connect_saved(clone!(@weak self as obj => move |editor| {
+            let campaign_path = obj.campaign_path();
+            let mut metadata = obj.imp().metadata.borrow_mut();
+            metadata.set(campaign_path, &path, editor.metadata());
+            let result = metadata.write(campaign_path);
+            drop(metadata);
+            obj.show_metadata();
+            obj.apply_filter();
+            try_emit!(obj, result, false);
+        }));
+        editor.popup();
+    }
+
+    fn set_page_title(&self, name: &str, title: &str, count: usize) {
+        let stack = &self.imp().stack;
+        if let Some(child) = stack.child_by_name(name) {
+            stack
+                .page(&child)
+                .set_title(&format!("{} ({})", title, count));
+        }
+    }
+
+    /// The thumbnail grid of the page that is shown, if the page has one
+    fn visible_grid(&self) -> Option<DdThumbnailGrid> {
+        let page = match self.imp().stack.visible_child_name()?.as_str() {
+            "images" => &self.imp().images,
+            "videos" => &self.imp().videos,
+            "pdfs" => &self.imp().pdfs,
+            _ => return None,
+        };
+        page.first_child().and_downcast::<DdThumbnailGrid>()
+    }
+
+    /// Show the first media that matches the search on the page that is shown
+    fn activate_first_match(&self) {
+        if let Some(grid) = self.visible_grid() {
+            grid.activate_first();
+            return;
+        }
+        if self.imp().stack.visible_child_name().as_deref() == Some("vtts") {
+            let dropdown = &self.imp().vtt_dropdown;
+            // the first item is the <No selection> item
+            if dropdown.model().map_or(0, |m| m.n_items()) > 1 {
+                dropdown.set_selected(1);
+            }
+        }
+    }
+
+    fn campaign_path(&self) -> &str {
+        self.imp()
             .campaign_path
             .get()
             .expect("Expected a campaign path")
-            .clone();
-        let (images, _vtts, videos) = self.seperate_media(campaign_path)?;
-        if images.len() > 0 {
-            let image_child = self.imp().images.first_child().expect("Expected a child");
-            if let Some(grid) = image_child.downcast_ref::<DdThumbnailGrid>() {
-                grid.update(images, &MediaType::Image);
+    }
+
+    /// Watch the campaign folder and its subfolders and update the thumbnails and vtts when
+    /// files are added, removed or renamed. Changes are collected for a short time before
+    /// updating
+    pub fn watch_campaign_folder(&self) -> Result<(), DragonDisplayError> {
+        self.imp().watching.set(true);
+        self.watch_folders()
+    }
+
+    /// Start watching folders that are not watched yet and stop watching folders that no longer
+    /// exist, gio only reports changes of the direct children of a folder
+    fn watch_folders(&self) -> Result<(), DragonDisplayError> {
+        let folders = campaign_folders(Path::new(self.campaign_path()));
+        let mut monitors = self.imp().file_monitors.borrow_mut();
+        let mut old_monitors = std::mem::take(&mut *monitors);
+        for folder in folders {
+            if let Some(monitor) = old_monitors.remove(&folder) {
+                monitors.insert(folder, monitor);
+                continue;
             }
-        } else {
-            let child = self.imp().images.first_child().expect("Expected a child");
-            self.imp().images.remove(&child);
-            let label = Label::builder()
-                .label("You have no images")
-                .halign(gtk::Align::Center)
-                .hexpand(true)
-                .valign(gtk::Align::Center)
-                .vexpand(true)
-                .build();
-            self.imp().images.append(&label);
-        }
-        // update vtts
-
-        if videos.len() > 0 {
-            let video_child = self.imp().videos.first_child().expect("Expected a child");
-            if let Some(grid) = video_child.downcast_ref::<DdThumbnailGrid>() {
-                grid.update(videos, &MediaType::Video);
+            let monitor = self.watch_folder(&folder)?;
+            monitors.insert(folder, monitor);
+        }
+        for monitor in old_monitors.values() {
+            monitor.cancel();
+        }
+        Ok(())
+    }
+
+    fn watch_folder(&self, folder: &Path) -> Result<gio::FileMonitor, DragonDisplayError> {
+        let monitor = gio::File::for_path(folder)
+            .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+            .context(GlibSnafu {
+                msg: format!("Could not watch the folder {}", folder.display()),
+            })?;
+        monitor.connect_changed(
+            clone!(@weak self as obj => move |_, file, other_file, event| {
+                use gio::FileMonitorEvent as E;
+                if !matches!(
+                    event,
+                    E::Created | E::Deleted | E::MovedIn | E::MovedOut | E::Renamed | E::ChangesDoneHint
+                ) {
+                    return;
+                }
+                // hidden files like the campaign data folder are never shown
+                if is_hidden(file) && other_file.map_or(true, is_hidden) {
+                    return;
+                }
+                obj.schedule_update();
+            }),
+        );
+        Ok(monitor)
+    }
+
+    /// Update the thumbnails after REFRESH_DELAY, if an update was already scheduled it is
+    /// postponed
+    fn schedule_update(&self) {
+        let source = glib::timeout_add_local_once(
+            REFRESH_DELAY,
+            clone!(@weak self as obj => move || {
+                obj.imp().update_source.replace(None);
+                try_emit!(obj, obj.update(), false);
+            }),
+        );
+        if let Some(old_source) = self.imp().update_source.replace(Some(source)) {
+            old_source.remove();
+        }
+    }
+
+    /// Show the files in a thumbnail grid on the page, or a label if there are no files. An
+    /// existing grid is updated so the selected thumbnail is kept
+    fn update_page(&self, page: &gtk::Box, files: Vec<PathBuf>, t: &MediaType) {
+        let grid = page.first_child().and_downcast::<DdThumbnailGrid>();
+        match (grid, files.is_empty()) {
+            (Some(grid), false) => grid.update(files, t),
+            (Some(grid), true) => {
+                page.remove(&grid);
+                page.append(&Self::empty_label(t));
+            }
+            (None, false) => {
+                if let Some(child) = page.first_child() {
+                    page.remove(&child);
+                }
+                page.append(&self.new_thumbnail_grid(files, t));
+            }
+            (None, true) => {
+                if page.first_child().is_none() {
+                    page.append(&Self::empty_label(t));
+                }
             }
-        } else {
-            let child = self.imp().videos.first_child().expect("Expected a child");
-            self.imp().videos.remove(&child);
-            let label = Label::builder()
-                .label("You have no videos")
-                .halign(gtk::Align::Center)
-                .hexpand(true)
-                .valign(gtk::Align::Center)
-                .vexpand(true)
-                .build();
-            self.imp().videos.append(&label);
         }
+    }
 
-        Ok(())
+    /// Create a thumbnail grid that emits the image or video signal when a thumbnail is clicked
+    fn new_thumbnail_grid(&self, files: Vec<PathBuf>, t: &MediaType) -> DdThumbnailGrid {
+        let grid = DdThumbnailGrid::new(files, t);
+        grid.connect_context_menu(clone!(@weak self as obj => move |_, thumbnail| {
+            obj.edit_metadata(&thumbnail.file(), &thumbnail);
+        }));
+        grid.connect_reorder(clone!(@weak self as obj => move |grid, file, target| {
+            obj.reorder(grid, &file, &target);
+        }));
+        match t {
+            MediaType::Image => grid.connect_path(clone!(@weak self as obj => move |_, path| {
+                obj.media_shown(&path);
+                obj.playlist_panel().set_shown_media(path.clone());
+                obj.emit_by_name::<()>("image", &[&path]);
+                obj.apply_media_settings();
+            })),
+            MediaType::Video => grid.connect_path(clone!(@weak self as obj => move |_, path| {
+                obj.media_shown(&path);
+                obj.playlist_panel().set_shown_media(path.clone());
+                obj.emit_by_name::<()>("video", &[&path, &true]);
+                obj.apply_media_settings();
+            })),
+            MediaType::Pdf => grid.connect_path(clone!(@weak self as obj => move |_, path| {
+                obj.media_shown(&path);
+                obj.playlist_panel().stop();
+                obj.show_pdf(&path);
+                obj.apply_media_settings();
+            })),
+        };
+        grid
     }
 
-    /// Reads all files in the given folder and seperates images, vtt files and videos,
-    /// returns three vectors of path variables (images, vtts, videos)
-    fn seperate_media(
-        &self,
-        campaign_path: String,
-    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>), DragonDisplayError> {
-        let files = read_dir(campaign_path).context(IOSnafu {
-            msg: "Could not read the campaign directory".to_string(),
-        })?;
-
-        let files: Vec<PathBuf> = files
-            .filter_map(|f| f.ok())
-            .map(|f| f.path())
-            .filter(|f| f.to_str().is_some() && f.extension().is_some())
-            .filter(|f| f.extension().unwrap().to_str().is_some())
+    fn empty_label(t: &MediaType) -> Label {
+        let text = match t {
+            MediaType::Image => "You have no images",
+            MediaType::Video => "You have no videos",
+            MediaType::Pdf => "You have no pdfs",
+        };
+        Label::builder()
+            .label(text)
+            .halign(gtk::Align::Center)
+            .hexpand(true)
+            .valign(gtk::Align::Center)
+            .vexpand(true)
+            .build()
+    }
+
+    /// Show the first page of the pdf on the display and the controls to go through its pages
+    fn show_pdf(&self, path: &str) {
+        let controls = &self.imp().pdf_controls;
+        try_emit!(self, controls.set_document(path), false);
+        controls.set_visible(true);
+        self.emit_by_name::<()>("pdf", &[&PdfPage::new(path.to_string())]);
+    }
+
+    /// Fill the vtt dropdown with the vtts in the order of the list. The selected vtt is never
+    /// removed from the dropdown if it is in the list, so it stays selected
+    fn update_vtts(&self, vtts: Vec<PathBuf>) {
+        let model = match self.imp().vtt_dropdown.model().and_downcast::<ListStore>() {
+            Some(m) => m,
+            None => return,
+        };
+        let vtts: Vec<String> = vtts
+            .iter()
+            .filter_map(|f| f.to_str())
+            .map(|f| f.to_string())
             .collect();
+        let selected = self
+            .imp()
+            .selected_vtt
+            .borrow()
+            .clone()
+            .filter(|s| vtts.contains(s));
+        // the first item is the <No selection> item
+        let mut i = 1;
+        while let Some(item) = model.item(i).and_downcast::<StringObject>() {
+            if selected.as_ref() == Some(&item.string().to_string()) {
+                i += 1;
+            } else {
+                model.remove(i);
+            }
+        }
+        // the vtts before the selected vtt are inserted in front of it
+        let mut position = 1;
+        for vtt in vtts {
+            if selected.as_ref() != Some(&vtt) {
+                model.insert(position, &StringObject::new(&vtt));
+            }
+            position += 1;
+        }
+    }
+
+    /// Set the record button, for example when a recording stopped because of an error
+    pub fn set_recording(&self, recording: bool) {
+        self.imp().record_button.set_active(recording);
+    }
 
-        let (images, other): (Vec<PathBuf>, Vec<PathBuf>) = files
-            .into_iter()
-            .partition(|f| IMAGE_EXTENSIONS.contains(&f.extension().unwrap().to_str().unwrap()));
-        let (vtts, other): (Vec<PathBuf>, Vec<PathBuf>) = other
-            .into_iter()
-            .partition(|f| VTT_EXTENSIONS.contains(&f.extension().unwrap().to_str().unwrap()));
-        let videos: Vec<PathBuf> = other
-            .into_iter()
-            .filter(|f| VIDEO_EXTENSIONS.contains(&f.extension().unwrap().to_str().unwrap()))
+    /// Add a toggle button with the name of every display window to choose which displays show
+    /// the media. The buttons are only shown when there is more than one display
+    pub fn set_displays(&self, names: &[String]) {
+        let targets = &self.imp().display_targets;
+        let buttons: Vec<ToggleButton> = names
+            .iter()
+            .map(|name| {
+                let button = ToggleButton::builder().label(name).active(true).build();
+                // the media has to be shown somewhere, so the last target can not be switched off
+                button.connect_toggled(clone!(@weak self as obj => move |button| {
+                    let buttons = obj.imp().display_buttons.borrow();
+                    if !button.is_active() && !buttons.iter().any(|b| b.is_active()) {
+                        button.set_active(true);
+                    }
+                }));
+                targets.append(&button);
+                button
+            })
             .collect();
+        let all_button = gtk::Button::with_label("All");
+        all_button.connect_clicked(clone!(@weak self as obj => move |_| {
+            for button in obj.imp().display_buttons.borrow().iter() {
+                button.set_active(true);
+            }
+        }));
+        targets.append(&all_button);
+        targets.set_visible(names.len() > 1);
+        self.imp().display_buttons.replace(buttons);
+    }
+
+    /// Let the user choose where to save a snapshot of the display
+    fn choose_snapshot_location(&self) {
+        let file_chooser = FileChooserDialog::new(
+            Some("Save what players see"),
+            Some(self),
+            gtk::FileChooserAction::Save,
+            &[
+                ("Save", ResponseType::Accept),
+                ("Cancel", ResponseType::Cancel),
+            ],
+        );
+        let display = self.targets().first().copied().unwrap_or_default();
+        file_chooser.set_current_name(&snapshot_name(display));
+        file_chooser.connect_response(clone!(@weak self as obj => move |file_chooser, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = file_chooser.file().and_then(|f| f.path()) {
+                    let path = path.to_string_lossy().to_string();
+                    obj.emit_by_name::<()>("snapshot-as", &[&path]);
+                }
+            }
+            file_chooser.close();
+        }));
+        file_chooser.present();
+    }
+
+    /// The numbers of the displays that should show the media, counting from 0
+    pub fn targets(&self) -> Vec<usize> {
+        self.imp()
+            .display_buttons
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, button)| button.is_active())
+            .map(|(display, _)| display)
+            .collect()
+    }
 
-        Ok((images, vtts, videos))
+    /// Notify the control window that the video on the display finished playing
+    pub fn video_finished(&self) {
+        self.playlist_panel().video_finished();
+    }
+
+    fn playlist_panel(&self) -> &DdPlaylistPanel {
+        self.imp()
+            .playlist_panel
+            .get()
+            .expect("Expected a playlist panel")
     }
 
     /// Set the options button of the control panel to sensitive (true or false)
@@ -414,10 +1266,21 @@ impl DdControlWindow {
         )
     }
 
-    /// Signal emitted when an rotate90 button is pressed
-    pub fn connect_rotate90<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+    /// Signal emitted when the back button is pressed
+    pub fn connect_back<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "back",
+            true,
+            glib::closure_local!(|window| {
+                f(window);
+            }),
+        )
+    }
+
+    /// Signal emitted when the forward button is pressed
+    pub fn connect_forward<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
-            "rotate90",
+            "forward",
             true,
             glib::closure_local!(|window| {
                 f(window);
@@ -425,10 +1288,10 @@ impl DdControlWindow {
         )
     }
 
-    /// Signal emitted when an rotate180 button is pressed
-    pub fn connect_rotate180<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+    /// Signal emitted when the export recap button is pressed
+    pub fn connect_export_recap<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
-            "rotate180",
+            "export-recap",
             true,
             glib::closure_local!(|window| {
                 f(window);
@@ -436,10 +1299,10 @@ impl DdControlWindow {
         )
     }
 
-    /// Signal emitted when an rotate270 button is pressed
-    pub fn connect_rotate270<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+    /// Signal emitted when the display should be saved to the snapshots folder of the campaign
+    pub fn connect_snapshot<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
-            "rotate270",
+            "snapshot",
             true,
             glib::closure_local!(|window| {
                 f(window);
@@ -447,6 +1310,48 @@ impl DdControlWindow {
         )
     }
 
+    /// Signal emitted when the display should be saved to the chosen path
+    pub fn connect_snapshot_as<F: Fn(&Self, String) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "snapshot-as",
+            true,
+            glib::closure_local!(|window, path| {
+                f(window, path);
+            }),
+        )
+    }
+
+    /// Signal emitted when the record button is toggled, the boolean indicates whether the
+    /// display should be recorded
+    pub fn connect_record<F: Fn(&Self, bool) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "record",
+            true,
+            glib::closure_local!(|window, recording| {
+                f(window, recording);
+            }),
+        )
+    }
+
+    /// Signal emitted when the rotation of the media on the display changed. The boolean
+    /// indicates whether the rotation was changed by the user, otherwise other media with its
+    /// own rotation is shown
+    pub fn connect_rotation<F: Fn(&Self, Rotation, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "rotation",
+            true,
+            glib::closure_local!(|window, degrees, changed| {
+                f(window, Rotation::from_degrees(degrees), changed);
+            }),
+        )
+    }
+
     /// Signal emitted when an fit to size button is pressed
     pub fn connect_fit<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
@@ -494,13 +1399,118 @@ impl DdControlWindow {
         )
     }
 
-    /// Signal emitted video in the grid is clicked
-    pub fn connect_video<F: Fn(&Self, String) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+    /// Signal emitted video in the grid is clicked or a playlist shows a video. The boolean
+    /// indicates whether the video should loop
+    pub fn connect_video<F: Fn(&Self, String, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
         self.connect_closure(
             "video",
             true,
-            glib::closure_local!(|window, path| {
-                f(window, path);
+            glib::closure_local!(|window, path, looping| {
+                f(window, path, looping);
+            }),
+        )
+    }
+
+    /// Signal emitted when a page of a pdf should be shown on the display
+    pub fn connect_pdf<F: Fn(&Self, PdfPage) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "pdf",
+            true,
+            glib::closure_local!(|window, page| {
+                f(window, page);
+            }),
+        )
+    }
+
+    /// Signal emitted when a text card should be shown over the media on the display
+    pub fn connect_show_card<F: Fn(&Self, TextCard) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "show-card",
+            true,
+            glib::closure_local!(|window, card| {
+                f(window, card);
+            }),
+        )
+    }
+
+    /// Signal emitted when the text card on the display should be hidden
+    pub fn connect_hide_card<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "hide-card",
+            true,
+            glib::closure_local!(|window| {
+                f(window);
+            }),
+        )
+    }
+
+    /// Signal emitted when the combat of the initiative tracker changed
+    pub fn connect_initiative<F: Fn(&Self, Combat) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "initiative",
+            true,
+            glib::closure_local!(|window, combat| {
+                f(window, combat);
+            }),
+        )
+    }
+
+    /// Signal emitted when the timers that are shown on the display changed
+    pub fn connect_timers<F: Fn(&Self, Timers) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "timers",
+            true,
+            glib::closure_local!(|window, timers| {
+                f(window, timers);
+            }),
+        )
+    }
+
+    /// Signal emitted when the weather over the display changed
+    pub fn connect_weather<F: Fn(&Self, WeatherSettings) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "weather",
+            true,
+            glib::closure_local!(|window, settings| {
+                f(window, settings);
+            }),
+        )
+    }
+
+    /// Signal emitted when the color grade of the display changed. The boolean indicates
+    /// whether the change should be animated
+    pub fn connect_grade<F: Fn(&Self, ColorGrade, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "grade",
+            true,
+            glib::closure_local!(|window, grade, animate| {
+                f(window, grade, animate);
+            }),
+        )
+    }
+
+    /// Signal emitted when the playlist pauses (true) or resumes (false) a video
+    pub fn connect_paused<F: Fn(&Self, bool) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "paused",
+            true,
+            glib::closure_local!(|window, paused| {
+                f(window, paused);
             }),
         )
     }
@@ -525,3 +1535,10 @@ impl DdControlWindow {
         )
     }
 }
+
+/// Returns whether the file is hidden (the file name starts with a dot)
+fn is_hidden(file: &gio::File) -> bool {
+    file.basename()
+        .and_then(|name| name.to_str().map(|n| n.starts_with('.')))
+        .unwrap_or(false)
+}
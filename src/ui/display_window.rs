@@ -1,56 +1,60 @@
-use gdk4::builders::RGBABuilder;
-use gdk4::{Monitor, Texture, RGBA};
+use gdk4::{Display, Monitor, Texture, RGBA};
 use gtk::gdk_pixbuf::Colorspace;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::glib::{clone, Bytes};
-use gtk::graphene::{Point, Rect, Size};
+use gtk::graphene::{Rect, Size};
 use gtk::prelude::*;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
-use gtk::{gio, glib, MediaFile};
+use gtk::{gio, glib, gsk, Label, MediaFile, Orientation};
 use snafu::{OptionExt, Report, ResultExt};
+use std::path::Path;
+use std::time::Duration;
 use vtt_rust::open_vtt;
 
+use crate::color_grade::ColorGrade;
 use crate::errors::{DragonDisplayError, GlibSnafu, OtherSnafu};
-use crate::videopipeline::VideoPipeline;
+use crate::image_loader::{load_image, DisplayImage};
+use crate::initiative::Combat;
+use crate::monitors::{connected_monitors, MonitorId};
+use crate::pdf::{render_page, PdfPage};
+use crate::renderer::{
+    compose, draw_fitted, draw_media, draw_overlays, offscreen_renderer, render, DisplayState, Fit,
+    Grid, Overlays,
+};
+use crate::text_card::{CardLayout, TextCard, FADE_DURATION};
+use crate::timer::{TimerPosition, Timers};
+use crate::transform::{DisplayTransform, Rotation};
+use crate::videopipeline::{VideoEvent, VideoPipeline};
+use crate::weather::WeatherSettings;
 use crate::{try_emit, APP_ID};
 
-use super::options::ColorPreset;
-pub enum Rotation {
-    None,
-    Clockwise,
-    UpsideDown,
-    Counterclockwise,
-}
-
-impl Rotation {
-    fn get_angle_degree(&self) -> i32 {
-        match self {
-            Rotation::None => 0,
-            Rotation::Clockwise => 90,
-            Rotation::UpsideDown => 180,
-            Rotation::Counterclockwise => 270,
-        }
-    }
-}
+use super::options::{ColorPreset, DisplayMode};
 
-impl Default for Rotation {
-    fn default() -> Self {
-        Rotation::None
-    }
-}
+/// The time it takes to change from one color grade to another
+const GRADE_DURATION: Duration = Duration::from_millis(500);
+/// The pixel density that is assumed for the virtual display to size the grid squares
+const VIRTUAL_DPI: f32 = 96.0;
 
 mod imp {
 
-    use crate::ui::display_window::Rotation;
+    use crate::color_grade::ColorGrade;
+    use crate::image_loader::Animation;
+    use crate::initiative::Combat;
+    use crate::monitors::MonitorId;
+    use crate::text_card::TextCard;
+    use crate::timer::Timers;
+    use crate::transform::{DisplayTransform, Rotation};
     use crate::videopipeline::VideoPipeline;
+    use crate::widgets::weather_layer::DdWeatherLayer;
     use std::cell::{Cell, OnceCell, RefCell};
     use std::sync::OnceLock;
 
     use gdk4::{Monitor, Texture, RGBA};
     use glib::subclass::InitializingObject;
     use gtk::glib::subclass::Signal;
+    use gtk::graphene::Rect;
     use gtk::subclass::prelude::*;
-    use gtk::{glib, CompositeTemplate, MediaFile};
+    use gtk::{glib, gsk, CompositeTemplate, Label, MediaFile, Overlay, Revealer};
     use gtk::{prelude::*, Picture};
 
     // Object holding the state
@@ -59,15 +63,60 @@ mod imp {
     pub struct DdDisplayWindow {
         #[template_child]
         pub content: TemplateChild<Picture>,
+        #[template_child]
+        pub overlay: TemplateChild<Overlay>,
+        #[template_child]
+        pub weather_layer: TemplateChild<DdWeatherLayer>,
+        #[template_child]
+        pub initiative_strip: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub card_revealer: TemplateChild<Revealer>,
+        #[template_child]
+        pub card: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub card_title: TemplateChild<Label>,
+        #[template_child]
+        pub card_text: TemplateChild<Label>,
         pub fit: Cell<bool>,
         pub grid: Cell<bool>,
-        pub rotation: RefCell<Rotation>,
+        /// The rotation of the media that is shown, added to the rotation of the display
+        pub rotation: Cell<Rotation>,
+        /// The rotation and mirroring of the display itself
+        pub transform: Cell<DisplayTransform>,
         pub texture: RefCell<Option<Texture>>,
         pub media_file: OnceCell<MediaFile>,
-        pub monitor: OnceCell<Monitor>,
+        /// The monitor the window is shown on, or was last shown on while it is disconnected
+        pub monitor: RefCell<Option<Monitor>>,
+        /// Identifies the monitor, so the window can return to it when it is reconnected
+        pub monitor_id: OnceCell<MonitorId>,
+        /// The resolution of a virtual display, which is rendered offscreen instead of shown
+        pub virtual_size: Cell<Option<(i32, i32)>>,
+        /// The renderer that renders the frames of the display offscreen
+        pub renderer: RefCell<Option<gsk::Renderer>>,
         pub color: RefCell<Option<RGBA>>,
         pub gridline_width: Cell<f32>,
         pub pipeline: RefCell<Option<VideoPipeline>>,
+        pub animation: RefCell<Option<Animation>>,
+        /// The timer that shows the next frame of the animation
+        pub animation_source: RefCell<Option<glib::SourceId>>,
+        /// The text card that is shown when the current text card has faded out
+        pub pending_card: RefCell<Option<TextCard>>,
+        /// The text card in the card overlay, it is only shown while the card is revealed
+        pub card: RefCell<Option<TextCard>>,
+        /// The combat in the initiative strip, None when the strip is hidden
+        pub combat: RefCell<Option<Combat>>,
+        pub timers: RefCell<Timers>,
+        /// The boxes holding the timers, in the order of the timer positions
+        pub timer_boxes: RefCell<Vec<gtk::Box>>,
+        /// The color grade that is applied to the media, changes while the grade is animated
+        pub grade: Cell<ColorGrade>,
+        pub grade_tick: RefCell<Option<gtk::TickCallbackId>>,
+        /// The frame time in microseconds at which the animation of the grade started
+        pub grade_start: Cell<Option<i64>>,
+        /// The fog of war of the shown vtt, kept to redraw the vtt with another grade
+        pub fog_of_war: RefCell<Option<Vec<Rect>>>,
+        /// The last frame of the playing video, kept to redraw the frame with another grade
+        pub video_frame: RefCell<Option<Texture>>,
     }
 
     // The central trait for subclassing a GObject
@@ -79,6 +128,7 @@ mod imp {
         type ParentType = gtk::ApplicationWindow;
 
         fn class_init(klass: &mut Self::Class) {
+            DdWeatherLayer::ensure_type();
             klass.bind_template();
         }
 
@@ -92,9 +142,12 @@ mod imp {
         fn signals() -> &'static [glib::subclass::Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
             SIGNALS.get_or_init(|| {
-                vec![Signal::builder("error")
-                    .param_types([String::static_type(), bool::static_type()])
-                    .build()]
+                vec![
+                    Signal::builder("error")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                    Signal::builder("video-finished").build(),
+                ]
             })
         }
 
@@ -122,18 +175,41 @@ glib::wrapper! {
 }
 
 impl DdDisplayWindow {
+    /// Create a display window that is shown on the monitor
     pub fn new(monitor: &Monitor) -> Self {
+        let object = Self::build();
+        object
+            .imp()
+            .monitor_id
+            .set(MonitorId::from_monitor(monitor))
+            .expect("Expected monitor to not be set");
+        object.set_monitor(monitor);
+        object.watch_monitors();
+        object
+    }
+
+    /// Create a virtual display of the given resolution. A virtual display is never shown, its
+    /// frames are rendered offscreen with render_frame
+    pub fn new_virtual(width: i32, height: i32) -> Self {
+        let object = Self::build();
+        object.imp().virtual_size.set(Some((width, height)));
+        object.set_default_size(width, height);
+        // the frame clock of a window that is not shown does not run, so text cards are shown
+        // without fading
+        object
+            .imp()
+            .card_revealer
+            .set_transition_type(gtk::RevealerTransitionType::None);
+        object
+    }
+
+    fn build() -> Self {
         let object = glib::Object::new::<Self>();
         object
             .imp()
             .media_file
             .set(MediaFile::new())
             .expect("Expected media file to not be set");
-        object
-            .imp()
-            .monitor
-            .set(monitor.to_owned())
-            .expect("Expected monitor to not be set");
         let settings = gtk::gio::Settings::new(APP_ID);
         let color_index = settings.int("grid-color-preset") as u32;
         let color = ColorPreset::from_index(color_index).to_rgba();
@@ -141,98 +217,430 @@ impl DdDisplayWindow {
 
         let gridline_width = settings.double("grid-line-width") as f32;
         object.imp().gridline_width.set(gridline_width);
+        object
+            .imp()
+            .transform
+            .set(DisplayTransform::from_settings(&settings));
         let video_pipeline = VideoPipeline::new();
         object.imp().pipeline.replace(Some(video_pipeline));
 
+        let provider = gtk::CssProvider::new();
+        provider.load_from_resource("/dragon/display/display.css");
+        gtk::style_context_add_provider_for_display(
+            &WidgetExt::display(&object),
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+        let timer_boxes = TimerPosition::ALL
+            .iter()
+            .map(|position| {
+                let (halign, valign) = position.align();
+                let timer_box = gtk::Box::builder()
+                    .orientation(Orientation::Vertical)
+                    .spacing(6)
+                    .halign(halign)
+                    .valign(valign)
+                    .can_target(false)
+                    .css_classes(["timers"])
+                    .build();
+                object.imp().overlay.add_overlay(&timer_box);
+                timer_box
+            })
+            .collect();
+        object.imp().timer_boxes.replace(timer_boxes);
+
+        let revealer = &object.imp().card_revealer;
+        revealer.set_transition_duration(FADE_DURATION);
+        revealer.connect_child_revealed_notify(clone!(@weak object => move |revealer| {
+            if revealer.reveals_child() || revealer.is_child_revealed() {
+                return;
+            }
+            if let Some(card) = object.imp().pending_card.take() {
+                object.set_card(&card);
+                revealer.set_reveal_child(true);
+            }
+        }));
+
         object
     }
 
     /// Disconnect the media that the display is holding on to and set the content to nothing
     pub fn reset(&self) {
         self.disconnect_media();
+        self.hide_card();
         self.imp().texture.replace(None);
         self.imp().content.set_paintable(None::<&Texture>);
     }
 
-    /// Tries to rotate the current texture by 90 degrees, if there is no current texture the
-    /// internal rotation value is still updated
-    pub fn rotate_90(&self) {
-        self.imp().rotation.replace_with(|rotation| match rotation {
-            Rotation::None => Rotation::Clockwise,
-            Rotation::Clockwise => Rotation::UpsideDown,
-            Rotation::UpsideDown => Rotation::Counterclockwise,
-            Rotation::Counterclockwise => Rotation::None,
-        });
-        self.redraw();
+    /// Returns the rotation of the media that is shown
+    pub fn rotation(&self) -> Rotation {
+        self.imp().rotation.get()
     }
 
-    /// Tries to rotate the current texture by 180 degrees, if there is no current texture the
-    /// internal rotation value is still updated
-    pub fn rotate_180(&self) {
-        self.imp().rotation.replace_with(|rotation| match rotation {
-            Rotation::None => Rotation::UpsideDown,
-            Rotation::Clockwise => Rotation::Counterclockwise,
-            Rotation::UpsideDown => Rotation::None,
-            Rotation::Counterclockwise => Rotation::Clockwise,
-        });
-        self.redraw();
+    /// Set the rotation of the media and redraw the current texture, if there is no current
+    /// texture the rotation is used for the next image
+    pub fn set_rotation(&self, rotation: Rotation) {
+        self.imp().rotation.set(rotation);
+        self.refresh();
     }
 
-    /// Tries to rotate the current texture by 270 degrees, if there is no current texture the
-    /// internal rotation value is still updated
-    pub fn rotate_270(&self) {
-        self.imp().rotation.replace_with(|rotation| match rotation {
-            Rotation::None => Rotation::Counterclockwise,
-            Rotation::Clockwise => Rotation::None,
-            Rotation::UpsideDown => Rotation::Clockwise,
-            Rotation::Counterclockwise => Rotation::UpsideDown,
-        });
-        self.redraw();
+    /// Set the rotation and mirroring of the display, this applies to all media
+    pub fn set_transform(&self, transform: DisplayTransform) {
+        self.imp().transform.set(transform);
+        self.refresh();
+    }
+
+    /// The transform of the display combined with the rotation of the media that is shown, this
+    /// is how every kind of media is drawn
+    fn media_transform(&self) -> DisplayTransform {
+        self.imp()
+            .transform
+            .get()
+            .with_media_rotation(self.imp().rotation.get())
     }
 
-    /// Update the texture of the display window and set it to an image that is at the given path
+    /// Update the texture of the display window and set it to an image that is at the given path.
+    /// Animated images play until other media is shown
     pub fn set_image(&self, path_to_image: String) {
         self.disconnect_media();
-        let texture = try_emit!(
+        // scalable images are rendered to fill the monitor in every rotation
+        let size = self.monitor_size();
+        let image = try_emit!(
             self,
-            Texture::from_filename(&path_to_image)
-                .ok()
-                .context(OtherSnafu {
-                    msg: format!("Could not load image at {}", &path_to_image)
-                }),
+            load_image(Path::new(&path_to_image), size, size),
             false
         );
-        self.imp().texture.replace(Some(texture));
+        let animated = match image {
+            DisplayImage::Static(texture) => {
+                self.imp().texture.replace(Some(texture));
+                false
+            }
+            DisplayImage::Animated(animation) => {
+                self.imp().animation.replace(Some(animation));
+                true
+            }
+        };
+
+        self.apply_fit();
+        if animated {
+            self.show_animation_frame();
+        } else {
+            self.redraw();
+        }
+    }
+
+    /// Show a page of a pdf. The page is rendered at the size of the monitor, so it can be
+    /// rotated, fitted and gridded like an image
+    pub fn set_pdf(&self, page: &PdfPage) {
+        self.disconnect_media();
+        let pixbuf = try_emit!(
+            self,
+            render_page(
+                Path::new(&page.path),
+                page.page,
+                page.crop,
+                self.monitor_size()
+            ),
+            false
+        );
+        self.imp()
+            .texture
+            .replace(Some(Texture::for_pixbuf(&pixbuf)));
+        self.apply_fit();
+        self.redraw();
+    }
 
-        // set the fit
+    /// Set the fit of the shown image
+    fn apply_fit(&self) {
         if self.imp().fit.get() {
             self.imp().content.set_content_fit(gtk::ContentFit::Fill);
         } else {
             self.imp().content.set_content_fit(gtk::ContentFit::Contain);
         }
+    }
 
+    /// Show the current frame of the animation and schedule the next frame
+    fn show_animation_frame(&self) {
+        let imp = self.imp();
+        let delay = match imp.animation.borrow().as_ref() {
+            Some(animation) => {
+                imp.texture
+                    .replace(Some(Texture::for_pixbuf(&animation.pixbuf())));
+                animation.delay()
+            }
+            None => return,
+        };
         self.redraw();
+        // a frame without delay is the last frame of the animation
+        let Some(delay) = delay else {
+            return;
+        };
+        let source = glib::timeout_add_local_once(
+            delay,
+            clone!(@weak self as obj => move || {
+                obj.imp().animation_source.take();
+                if let Some(animation) = obj.imp().animation.borrow_mut().as_mut() {
+                    animation.advance();
+                }
+                obj.show_animation_frame();
+            }),
+        );
+        imp.animation_source.replace(Some(source));
+    }
+
+    /// Returns true if the display is rendered offscreen instead of shown on a monitor
+    pub fn is_virtual(&self) -> bool {
+        self.imp().virtual_size.get().is_some()
+    }
+
+    /// Show the window on its monitor in the display mode of the settings. Windows that are not
+    /// fullscreen are placed by the window manager, virtual displays are not shown
+    pub fn show_on_monitor(&self) {
+        let Some(monitor) = self.monitor() else {
+            return;
+        };
+        let settings = gtk::gio::Settings::new(APP_ID);
+        match DisplayMode::from_index(settings.int("display-mode") as u32) {
+            DisplayMode::Fullscreen => {
+                self.present();
+                self.fullscreen_on_monitor(&monitor);
+            }
+            mode => {
+                self.unfullscreen();
+                self.set_decorated(mode == DisplayMode::Windowed);
+                self.set_default_size(settings.int("window-width"), settings.int("window-height"));
+                self.present();
+            }
+        }
+    }
+
+    /// The monitor the window is shown on, or was last shown on while it is disconnected. None
+    /// for a virtual display
+    fn monitor(&self) -> Option<Monitor> {
+        self.imp().monitor.borrow().clone()
+    }
+
+    /// Use the monitor for the window and hide the fullscreen window when the monitor is
+    /// disconnected, so the media does not end up on the screen of the dungeon master
+    fn set_monitor(&self, monitor: &Monitor) {
+        self.imp().monitor.replace(Some(monitor.clone()));
+        monitor.connect_invalidate(clone!(@weak self as obj => move |monitor| {
+            if obj.monitor().as_ref() == Some(monitor) && obj.is_fullscreened() {
+                obj.set_visible(false);
+            }
+        }));
+    }
+
+    /// Move the window back to its monitor when the monitor is reconnected
+    fn watch_monitors(&self) {
+        let Some(display) = Display::default() else {
+            return;
+        };
+        display
+            .monitors()
+            .connect_items_changed(clone!(@weak self as obj => move |_, _, _, _| {
+                if obj.monitor().is_some_and(|m| m.is_valid()) {
+                    return;
+                }
+                let id = obj.imp().monitor_id.get().expect("Expected monitor to be set");
+                // the monitors of the other display windows are not available
+                let taken: Vec<Monitor> = gtk::Window::list_toplevels()
+                    .into_iter()
+                    .filter_map(|w| w.downcast::<DdDisplayWindow>().ok())
+                    .filter(|w| w != &obj)
+                    .filter_map(|w| w.monitor())
+                    .filter(|m| m.is_valid())
+                    .collect();
+                let Some(monitor) = id.find(&connected_monitors(), &taken) else {
+                    return;
+                };
+                obj.set_monitor(&monitor);
+                obj.show_on_monitor();
+                obj.refresh();
+            }));
+    }
+
+    /// The largest side of the monitor in pixels
+    fn monitor_size(&self) -> i32 {
+        if let Some((width, height)) = self.imp().virtual_size.get() {
+            return width.max(height);
+        }
+        let monitor = self.monitor().expect("Expected a monitor to be set");
+        let geometry = monitor.geometry();
+        geometry.width().max(geometry.height()) * monitor.scale_factor()
+    }
+
+    /// The width and height of the display in millimeters
+    fn physical_size(&self) -> (i32, i32) {
+        if let Some((width, height)) = self.imp().virtual_size.get() {
+            let to_mm = |pixels: i32| (pixels as f32 * 25.4 / VIRTUAL_DPI) as i32;
+            return (to_mm(width), to_mm(height));
+        }
+        let monitor = self.monitor().expect("Expected a monitor to be set");
+        (monitor.width_mm(), monitor.height_mm())
+    }
+
+    /// Render what the display shows to a texture, the media together with its overlays. A shown
+    /// window is rendered at the size of the window, a virtual display at its resolution
+    pub fn render_frame(&self) -> Option<Texture> {
+        let (node, width, height) = self.frame_node()?;
+        let renderer = self.renderer()?;
+        Some(renderer.render_texture(&node, Some(&Rect::new(0.0, 0.0, width, height))))
+    }
+
+    /// Render what the display shows to a texture of the given size. The frame is fitted inside
+    /// the texture and the rest of the texture is black
+    pub fn render_frame_sized(&self, width: i32, height: i32) -> Option<Texture> {
+        let (node, node_width, node_height) = self.frame_node()?;
+        let renderer = self.renderer()?;
+        render(
+            &renderer,
+            &node,
+            (node_width, node_height),
+            width,
+            height,
+            Fit::Contain,
+        )
     }
 
-    /// Set the content of the display window to a video
-    pub fn set_video(&self, path_to_video: String) {
+    /// The render node of what the display shows together with its width and height
+    fn frame_node(&self) -> Option<(gsk::RenderNode, f32, f32)> {
+        let imp = self.imp();
+        let snapshot = gtk::Snapshot::new();
+        let (width, height) = match imp.virtual_size.get() {
+            Some((width, height)) => {
+                // a virtual display is never shown, so its widgets are not laid out. The media
+                // and the overlays are drawn from the state of the display instead
+                let (width, height) = (width as f32, height as f32);
+                self.draw_state(&snapshot, width, height);
+                (width, height)
+            }
+            None => {
+                let (width, height) = (imp.overlay.width(), imp.overlay.height());
+                if width <= 0 || height <= 0 {
+                    return None;
+                }
+                gtk::WidgetPaintable::new(Some(&*imp.overlay)).snapshot(
+                    &snapshot,
+                    width as f64,
+                    height as f64,
+                );
+                (width as f32, height as f32)
+            }
+        };
+        Some((snapshot.to_node()?, width, height))
+    }
+
+    /// Draw the media and the overlays of the display at the given size from the state of the
+    /// display, without its widgets
+    fn draw_state(&self, snapshot: &gtk::Snapshot, width: f32, height: f32) {
+        let imp = self.imp();
+        let fit = match imp.fit.get() {
+            true => Fit::Fill,
+            false => Fit::Contain,
+        };
+        match self.media_node() {
+            Some((node, node_width, node_height)) => draw_fitted(
+                snapshot,
+                &node,
+                (node_width, node_height),
+                (width, height),
+                fit,
+            ),
+            None => snapshot.append_color(&RGBA::BLACK, &Rect::new(0.0, 0.0, width, height)),
+        }
+        imp.weather_layer.advance(glib::monotonic_time());
+        imp.weather_layer.draw(snapshot, width, height);
+        let context = self.pango_context();
+        draw_overlays(snapshot, &context, &self.overlays(), width, height);
+    }
+
+    /// The media that is shown composed with the state of the display, the way the content of
+    /// the display draws it. Videos and vtts are drawn without grid
+    fn media_node(&self) -> Option<(gsk::RenderNode, f32, f32)> {
+        let imp = self.imp();
+        let video_frame = imp.video_frame.borrow().clone();
+        let fog_of_war = imp.fog_of_war.borrow().clone();
+        let grid = match video_frame.is_none() && fog_of_war.is_none() {
+            true => self.grid(),
+            false => None,
+        };
+        let texture = video_frame.or_else(|| imp.texture.borrow().clone())?;
+        let state = DisplayState {
+            transform: self.media_transform(),
+            grade: imp.grade.get(),
+            grid,
+            fog_of_war: fog_of_war.unwrap_or_default(),
+        };
+        compose(&texture, &state)
+    }
+
+    /// The overlays that the display shows over the media
+    fn overlays(&self) -> Overlays {
+        let imp = self.imp();
+        let card = match imp.card_revealer.reveals_child() {
+            true => imp.card.borrow().clone(),
+            false => None,
+        };
+        Overlays {
+            card,
+            combat: imp.combat.borrow().clone(),
+            timers: imp.timers.borrow().clone(),
+        }
+    }
+
+    /// The renderer for offscreen frames, it does not need a window so it works for virtual
+    /// displays as well
+    fn renderer(&self) -> Option<gsk::Renderer> {
+        let imp = self.imp();
+        if let Some(renderer) = imp.renderer.borrow().as_ref() {
+            return Some(renderer.clone());
+        }
+        let renderer = match offscreen_renderer() {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                self.emit_error(err, false);
+                return None;
+            }
+        };
+        imp.renderer.replace(Some(renderer.clone()));
+        Some(renderer)
+    }
+
+    /// Set the content of the display window to a video. If looping is false the video-finished
+    /// signal is emitted when the video ends
+    pub fn set_video(&self, path_to_video: String, looping: bool) {
         let (sender, receiver) = async_channel::unbounded();
         self.disconnect_media();
 
         let mut borrow = self.imp().pipeline.borrow_mut();
         let pipeline = borrow.as_mut().expect("No pipeline found");
 
-        let (width, height) = pipeline.play_video(&path_to_video, sender);
+        let (width, height) = pipeline.play_video(&path_to_video, sender, looping);
         let stride = width * 3;
-        VideoPipeline::connect_frame(
+        VideoPipeline::connect_event(
             receiver,
-            clone!(@weak self as obj => move |frame| {
-                let pixbuf = Pixbuf::from_mut_slice(frame, Colorspace::Rgb, false, 8, width, height, stride);
-                obj.imp().content.set_pixbuf(Some(&pixbuf));
+            clone!(@weak self as obj => move |event| match event {
+                VideoEvent::Frame { data } => {
+                    let pixbuf = Pixbuf::from_mut_slice(data, Colorspace::Rgb, false, 8, width, height, stride);
+                    obj.show_video_frame(Texture::for_pixbuf(&pixbuf));
+                }
+                VideoEvent::Finished => {
+                    obj.emit_by_name::<()>("video-finished", &[]);
+                }
             }),
         );
     }
 
+    /// Pause or resume the video that is currently displayed, does nothing for other media
+    pub fn set_paused(&self, paused: bool) {
+        let result = match &*self.imp().pipeline.borrow() {
+            Some(pipeline) => pipeline.set_paused(paused),
+            None => return,
+        };
+        try_emit!(self, result, false);
+    }
+
     /// Set the vtt file and fog of war
     pub fn set_vtt(&self, path_to_vtt: String, fog_of_war: Vec<Rect>) {
         self.disconnect_media();
@@ -241,9 +649,181 @@ impl DdDisplayWindow {
         let bytes = Bytes::from(&image);
         let texture = try_emit!(self, Texture::from_bytes(&bytes).context(GlibSnafu {msg: "Failed to create texture from image in vtt".to_string()}), false);
         self.imp().texture.replace(Some(texture));
+        self.imp().fog_of_war.replace(Some(fog_of_war.clone()));
         self.redraw_vtt(fog_of_war);
     }
 
+    /// Show a frame of the video with the color grade and the transform of the display applied
+    fn show_video_frame(&self, frame: Texture) {
+        let grade = self.imp().grade.get();
+        let transform = self.media_transform();
+        if grade.is_neutral() && transform.is_identity() {
+            self.imp().content.set_paintable(Some(&frame));
+        } else {
+            let state = DisplayState {
+                transform,
+                grade,
+                ..Default::default()
+            };
+            let snapshot = gtk::Snapshot::new();
+            let (new_width, new_height) = draw_media(&snapshot, &frame, &state);
+            if let Some(paintable) = snapshot.to_paintable(Some(&Size::new(new_width, new_height)))
+            {
+                self.imp().content.set_paintable(Some(&paintable));
+            }
+        }
+        self.imp().video_frame.replace(Some(frame));
+    }
+
+    /// Apply the color grade to the media on the display. An animated change fades from the
+    /// current grade to the new grade in GRADE_DURATION
+    pub fn set_color_grade(&self, grade: ColorGrade, animate: bool) {
+        let imp = self.imp();
+        if let Some(tick) = imp.grade_tick.take() {
+            tick.remove();
+        }
+        let from = imp.grade.get();
+        if !animate || from == grade {
+            imp.grade.set(grade);
+            self.refresh();
+            return;
+        }
+        imp.grade_start.set(None);
+        let tick = self.add_tick_callback(move |window, clock| {
+            let imp = window.imp();
+            let now = clock.frame_time();
+            let start = match imp.grade_start.get() {
+                Some(start) => start,
+                None => {
+                    imp.grade_start.set(Some(now));
+                    now
+                }
+            };
+            let progress =
+                ((now - start) as f32 / GRADE_DURATION.as_micros() as f32).clamp(0.0, 1.0);
+            imp.grade.set(from.lerp(&grade, progress));
+            window.refresh();
+            if progress < 1.0 {
+                return glib::ControlFlow::Continue;
+            }
+            // the callback is removed by breaking, so it should not be removed again
+            imp.grade_tick.take();
+            glib::ControlFlow::Break
+        });
+        imp.grade_tick.replace(Some(tick));
+    }
+
+    /// Redraw the media that is shown, used when the color grade changes
+    fn refresh(&self) {
+        let frame = self.imp().video_frame.borrow().clone();
+        if let Some(frame) = frame {
+            self.show_video_frame(frame);
+            return;
+        }
+        let fog_of_war = self.imp().fog_of_war.borrow().clone();
+        match fog_of_war {
+            Some(fog_of_war) => self.redraw_vtt(fog_of_war),
+            None => self.redraw(),
+        }
+    }
+
+    /// Show the turn order of the combat at the top of the display. Hidden combatants are left
+    /// out, the strip is hidden when the combat is not shown or has not started
+    pub fn set_initiative(&self, combat: &Combat) {
+        let strip = &self.imp().initiative_strip;
+        while let Some(child) = strip.first_child() {
+            strip.remove(&child);
+        }
+        if !combat.shown || combat.round == 0 {
+            self.imp().combat.replace(None);
+            strip.set_visible(false);
+            return;
+        }
+        self.imp().combat.replace(Some(combat.clone()));
+        let round = Label::new(Some(&format!("Round {}", combat.round)));
+        round.add_css_class("round");
+        strip.append(&round);
+        for (index, combatant) in combat.combatants.iter().enumerate() {
+            if combatant.hidden {
+                continue;
+            }
+            let label = Label::new(Some(&combatant.name));
+            if index == combat.turn {
+                label.add_css_class("current-turn");
+            }
+            strip.append(&label);
+        }
+        strip.set_visible(true);
+    }
+
+    /// Draw the weather over everything that is shown on the display
+    pub fn set_weather(&self, settings: WeatherSettings) {
+        self.imp().weather_layer.set_weather(settings);
+    }
+
+    /// Show the timers at their positions on the display
+    pub fn set_timers(&self, timers: &Timers) {
+        self.imp().timers.replace(timers.clone());
+        let timer_boxes = self.imp().timer_boxes.borrow();
+        for timer_box in timer_boxes.iter() {
+            while let Some(child) = timer_box.first_child() {
+                timer_box.remove(&child);
+            }
+        }
+        for timer in &timers.0 {
+            let label = Label::new(Some(&timer.text()));
+            label.add_css_class("timer");
+            label.add_css_class(timer.style.css_class());
+            if timer.is_expired() {
+                label.add_css_class("expired");
+                if timer.flash {
+                    label.add_css_class("flash");
+                }
+            }
+            timer_boxes[timer.position.index()].append(&label);
+        }
+    }
+
+    /// Fade in the text card over the media on the display. A text card that is already shown
+    /// fades out first
+    pub fn show_card(&self, card: TextCard) {
+        let revealer = &self.imp().card_revealer;
+        if revealer.reveals_child() || revealer.is_child_revealed() {
+            self.imp().pending_card.replace(Some(card));
+            revealer.set_reveal_child(false);
+        } else {
+            self.set_card(&card);
+            revealer.set_reveal_child(true);
+        }
+    }
+
+    /// Fade out the text card that is shown on the display
+    pub fn hide_card(&self) {
+        self.imp().pending_card.replace(None);
+        self.imp().card_revealer.set_reveal_child(false);
+    }
+
+    /// Fill the text card overlay with the text and place it according to its layout
+    fn set_card(&self, card: &TextCard) {
+        let imp = self.imp();
+        imp.card.replace(Some(card.clone()));
+        for layout in CardLayout::ALL {
+            imp.card.remove_css_class(layout.css_class());
+        }
+        imp.card.add_css_class(card.layout.css_class());
+        let (halign, valign) = match card.layout {
+            CardLayout::LowerThird => (gtk::Align::Fill, gtk::Align::End),
+            CardLayout::Centered => (gtk::Align::Center, gtk::Align::Center),
+            CardLayout::Parchment => (gtk::Align::Fill, gtk::Align::Fill),
+        };
+        imp.card_revealer.set_halign(halign);
+        imp.card_revealer.set_valign(valign);
+        imp.card_title.set_label(&card.title);
+        imp.card_title.set_visible(!card.title.is_empty());
+        imp.card_text.set_label(&card.text);
+        imp.card_text.set_visible(!card.text.is_empty());
+    }
+
     /// Toggle the content fit of the image, if there is no picture it will update the value but
     /// silently fail to update the picture
     pub fn toggle_fit(&self) {
@@ -279,81 +859,6 @@ impl DdDisplayWindow {
         self.redraw();
     }
 
-    /// Draws a grid in the given snapshot. This function needs the width and height of the current
-    /// texture. This width and height should be updated to fit the rotation of the texture. It
-    /// also needs the monitor that the image is displayed on to calculate the sizes of the
-    /// squares.
-    fn draw_grid(
-        snapshot: &gtk::Snapshot,
-        width: f32,
-        height: f32,
-        line_width: f32,
-        color: &RGBA,
-        monitor: &Monitor,
-    ) {
-        let total_vertical_squares = monitor.height_mm() / 25; // 25mm is about 1 inch
-        let total_horizontal_squares = monitor.width_mm() / 25;
-
-        // 10 squares, 1080p, image: 2200p 2200/10 = 220
-        let pix_per_horizontal_square = width / total_horizontal_squares as f32;
-        let pix_per_vertical_square = height / total_vertical_squares as f32;
-
-        snapshot.save();
-        let mut line_position = 0.0;
-        while line_position < height as f32 {
-            snapshot.append_color(
-                color,
-                &Rect::new(0.0, line_position - (line_width / 2.0), width, line_width),
-            );
-            line_position += pix_per_vertical_square as f32;
-        }
-        line_position = 0.0;
-        while line_position < width as f32 {
-            snapshot.append_color(
-                color,
-                &Rect::new(line_position - (line_width / 2.0), 0.0, line_width, height),
-            );
-            line_position += pix_per_horizontal_square as f32;
-        }
-        snapshot.restore();
-    }
-
-    /// Applies the given rotation to the snapshot needs a width and height of the texture to be
-    /// rotated
-    fn draw_rotation(snapshot: &gtk::Snapshot, width: f32, height: f32, rotation: &Rotation) {
-        let angle_degree = rotation.get_angle_degree();
-        let (new_width, new_height) = match angle_degree {
-            90 | 270 => (height, width),
-            _ => (width, height),
-        };
-        snapshot.translate(&Point::new(new_width / 2.0, new_height / 2.0));
-        snapshot.rotate(angle_degree as f32);
-        snapshot.translate(&Point::new(-width / 2.0, -height / 2.0));
-    }
-
-    /// Draws the given texture to the snapshot
-    fn draw_texture(snapshot: &gtk::Snapshot, texture: &Texture) {
-        let width = texture.width() as f32;
-        let height = texture.height() as f32;
-        snapshot.save();
-        snapshot.append_texture(texture, &Rect::new(0.0, 0.0, width, height));
-        snapshot.restore();
-    }
-
-    fn draw_fogofwar(snapshot: &gtk::Snapshot, fog_of_war: Vec<Rect>) {
-        snapshot.save();
-        let black = RGBABuilder::new()
-            .red(0.)
-            .blue(0.)
-            .green(0.)
-            .alpha(1.)
-            .build();
-        for rect in fog_of_war {
-            snapshot.append_color(&black, &rect);
-        }
-        snapshot.restore();
-    }
-
     /// Function called when the image needs to be redrawn. Creates a new snapshot, sets it up
     /// according to all the current settings and sets the content to the current texture.
     fn redraw(&self) {
@@ -365,31 +870,15 @@ impl DdDisplayWindow {
             }
         };
 
-        let width = texture.width() as f32;
-        let height = texture.height() as f32;
-        let rotation = &self.imp().rotation.borrow();
-        let (new_width, new_height) = match rotation.get_angle_degree() {
-            90 | 270 => (height, width),
-            _ => (width, height),
+        let state = DisplayState {
+            transform: self.media_transform(),
+            grade: self.imp().grade.get(),
+            grid: self.grid(),
+            fog_of_war: Vec::new(),
         };
 
         let snapshot = gtk::Snapshot::new();
-    
-        Self::draw_rotation(&snapshot, width, height, rotation);
-
-        Self::draw_texture(&snapshot, texture);
-
-        if self.imp().grid.get() {
-            let monitor = self
-                .imp()
-                .monitor
-                .get()
-                .expect("Expected a monitor to be set");
-            let color = &self.imp().color.borrow().expect("Expected color to be set");
-            let line_width = self.imp().gridline_width.get();
-            Self::draw_grid(&snapshot, new_width, new_height, line_width, color, monitor);
-        }
-
+        let (new_width, new_height) = draw_media(&snapshot, texture, &state);
         let paintable = match snapshot.to_paintable(Some(&Size::new(new_width, new_height))) {
             Some(t) => t,
             None => {
@@ -399,6 +888,18 @@ impl DdDisplayWindow {
         self.imp().content.set_paintable(Some(&paintable));
     }
 
+    /// The grid that is drawn over images and pdfs, None when the grid is off
+    fn grid(&self) -> Option<Grid> {
+        if !self.imp().grid.get() {
+            return None;
+        }
+        Some(Grid {
+            color: self.imp().color.borrow().expect("Expected color to be set"),
+            line_width: self.imp().gridline_width.get(),
+            physical_size: self.physical_size(),
+        })
+    }
+
     fn redraw_vtt(&self, fog_of_war: Vec<Rect>) {
         let binding = &*self.imp().texture.borrow();
         let texture = match binding {
@@ -407,14 +908,16 @@ impl DdDisplayWindow {
                 return;
             }
         };
+        // the fog of war is drawn on the vtt, so it turns with the vtt, there is no grid on a vtt
+        let state = DisplayState {
+            transform: self.media_transform(),
+            grade: self.imp().grade.get(),
+            grid: None,
+            fog_of_war,
+        };
         let snapshot = gtk::Snapshot::new();
-
-        Self::draw_texture(&snapshot, texture); 
-
-        Self::draw_fogofwar(&snapshot, fog_of_war);
-        let width = texture.width() as f32;
-        let height = texture.height() as f32;
-        let paintable = match snapshot.to_paintable(Some(&Size::new(width, height))) {
+        let (new_width, new_height) = draw_media(&snapshot, texture, &state);
+        let paintable = match snapshot.to_paintable(Some(&Size::new(new_width, new_height))) {
             Some(t) => t,
             None => {
                 return;
@@ -428,6 +931,23 @@ impl DdDisplayWindow {
         let borrow = self.imp().pipeline.borrow_mut();
         let pipeline = borrow.as_ref().expect("Expected a pipeline");
         pipeline.stop_video();
+        if let Some(source) = self.imp().animation_source.take() {
+            source.remove();
+        }
+        self.imp().animation.replace(None);
+        self.imp().fog_of_war.replace(None);
+        self.imp().video_frame.replace(None);
+    }
+
+    /// Signal emitted when a video that is not looping reached its end
+    pub fn connect_video_finished<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "video-finished",
+            true,
+            glib::closure_local!(|window| {
+                f(window);
+            }),
+        )
     }
 
     /// Emit an error message based on the input error
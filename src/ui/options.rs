@@ -8,11 +8,18 @@ use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{gio, glib};
 use gtk::{prelude::*, Adjustment};
 
+use crate::transform::{DisplayTransform, Rotation};
 use crate::APP_ID;
 pub const MAX_COLUMN_ROW_AMOUNT: f64 = 20.0;
 pub const MIN_COLUMN_ROW_AMOUNT: f64 = 1.0;
 pub const MIN_GRID_WIDTH: f64 = 0.1;
 pub const MAX_GRID_WIDTH: f64 = 10.0;
+pub const MAX_VIDEO_OFFSET: f64 = 90.0;
+pub const DEFAULT_VIDEO_OFFSET: f64 = 10.0;
+pub const MIN_WINDOW_SIZE: f64 = 100.0;
+pub const MAX_WINDOW_SIZE: f64 = 7680.0;
+pub const MIN_FRAMERATE: f64 = 1.0;
+pub const MAX_FRAMERATE: f64 = 60.0;
 
 /// To avoid errors the order of this list should be equal to the order of the dropdown list
 /// defined in the options.ui
@@ -53,15 +60,37 @@ impl ColorPreset {
     }
 }
 
+/// How the display windows are shown on their monitors, the order of this list should be equal
+/// to the order of the dropdown list defined in the options.ui
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayMode {
+    Fullscreen,
+    Windowed,
+    Borderless,
+}
+
+impl DisplayMode {
+    /// Create a new display mode based on the dropdown index
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            0 => Self::Fullscreen,
+            1 => Self::Windowed,
+            2 => Self::Borderless,
+            _ => panic!("Found invalid index"),
+        }
+    }
+}
+
 mod imp {
     use std::sync::OnceLock;
 
     use glib::subclass::InitializingObject;
     use gtk::glib::subclass::Signal;
     use gtk::subclass::prelude::*;
-    use gtk::{glib, Button, CompositeTemplate, DropDown, SpinButton};
+    use gtk::{glib, Button, CheckButton, CompositeTemplate, DropDown, SpinButton};
     use gtk::{prelude::*, template_callbacks};
 
+    use crate::transform::DisplayTransform;
     use crate::APP_ID;
 
     // Object holding the state
@@ -76,6 +105,38 @@ mod imp {
         pub color_dropdown: TemplateChild<DropDown>,
         #[template_child]
         pub gridline_width: TemplateChild<SpinButton>,
+        #[template_child]
+        pub display_rotation: TemplateChild<SpinButton>,
+        #[template_child]
+        pub flip_horizontal: TemplateChild<CheckButton>,
+        #[template_child]
+        pub flip_vertical: TemplateChild<CheckButton>,
+        #[template_child]
+        pub display_mode: TemplateChild<DropDown>,
+        #[template_child]
+        pub window_width: TemplateChild<SpinButton>,
+        #[template_child]
+        pub window_height: TemplateChild<SpinButton>,
+        #[template_child]
+        pub virtual_display: TemplateChild<CheckButton>,
+        #[template_child]
+        pub virtual_width: TemplateChild<SpinButton>,
+        #[template_child]
+        pub virtual_height: TemplateChild<SpinButton>,
+        #[template_child]
+        pub recording_format: TemplateChild<DropDown>,
+        #[template_child]
+        pub recording_width: TemplateChild<SpinButton>,
+        #[template_child]
+        pub recording_height: TemplateChild<SpinButton>,
+        #[template_child]
+        pub recording_framerate: TemplateChild<SpinButton>,
+        #[template_child]
+        pub recording_audio: TemplateChild<CheckButton>,
+        #[template_child]
+        pub video_offset: TemplateChild<SpinButton>,
+        #[template_child]
+        pub hover_preview: TemplateChild<CheckButton>,
     }
 
     // The central trait for subclassing a GObject
@@ -115,6 +176,52 @@ mod imp {
             settings
                 .set_double("grid-line-width", self.gridline_width.value())
                 .expect("Could not color preset");
+            self.obj()
+                .transform()
+                .write_settings(&settings)
+                .expect("Could not update display transform");
+            settings
+                .set_int("display-mode", self.display_mode.selected() as i32)
+                .expect("Could not update display mode");
+            settings
+                .set_int("window-width", self.window_width.value() as i32)
+                .expect("Could not update window width");
+            settings
+                .set_int("window-height", self.window_height.value() as i32)
+                .expect("Could not update window height");
+            settings
+                .set_boolean("virtual-display", self.virtual_display.is_active())
+                .expect("Could not update virtual display");
+            settings
+                .set_int("virtual-display-width", self.virtual_width.value() as i32)
+                .expect("Could not update virtual display width");
+            settings
+                .set_int("virtual-display-height", self.virtual_height.value() as i32)
+                .expect("Could not update virtual display height");
+            settings
+                .set_int("recording-format", self.recording_format.selected() as i32)
+                .expect("Could not update recording format");
+            settings
+                .set_int("recording-width", self.recording_width.value() as i32)
+                .expect("Could not update recording width");
+            settings
+                .set_int("recording-height", self.recording_height.value() as i32)
+                .expect("Could not update recording height");
+            settings
+                .set_int(
+                    "recording-framerate",
+                    self.recording_framerate.value() as i32,
+                )
+                .expect("Could not update recording framerate");
+            settings
+                .set_boolean("recording-audio", self.recording_audio.is_active())
+                .expect("Could not update recording audio");
+            settings
+                .set_int("video-thumbnail-offset", self.video_offset.value() as i32)
+                .expect("Could not update video thumbnail position");
+            settings
+                .set_boolean("video-hover-preview", self.hover_preview.is_active())
+                .expect("Could not update video preview");
             self.obj().emit_by_name::<()>("confirm", &[]);
         }
 
@@ -123,6 +230,22 @@ mod imp {
             self.row.set_value(3.0);
             self.column.set_value(3.0);
             self.color_dropdown.set_selected(0);
+            self.display_rotation.set_value(0.0);
+            self.flip_horizontal.set_active(false);
+            self.flip_vertical.set_active(false);
+            self.display_mode.set_selected(0);
+            self.window_width.set_value(1280.0);
+            self.window_height.set_value(720.0);
+            self.virtual_display.set_active(false);
+            self.virtual_width.set_value(1920.0);
+            self.virtual_height.set_value(1080.0);
+            self.recording_format.set_selected(0);
+            self.recording_width.set_value(1280.0);
+            self.recording_height.set_value(720.0);
+            self.recording_framerate.set_value(24.0);
+            self.recording_audio.set_active(true);
+            self.video_offset.set_value(super::DEFAULT_VIDEO_OFFSET);
+            self.hover_preview.set_active(true);
         }
     }
 
@@ -139,6 +262,9 @@ mod imp {
                     Signal::builder("grid-line-width")
                         .param_types([f32::static_type()])
                         .build(),
+                    Signal::builder("transform")
+                        .param_types([DisplayTransform::static_type()])
+                        .build(),
                 ]
             })
         }
@@ -223,9 +349,85 @@ impl DdOptionsWindow {
             object.emit_by_name::<()>("grid-line-width", &[&(adjustment.value() as f32)]);
         }));
 
+        let transform = DisplayTransform::from_settings(&settings);
+        let rotation_adjustment = Adjustment::new(
+            transform.rotation.degrees() as f64,
+            0.0,
+            359.0,
+            1.0,
+            15.0,
+            0.0,
+        );
+        imp.display_rotation.set_adjustment(&rotation_adjustment);
+        imp.flip_horizontal.set_active(transform.flip_horizontal);
+        imp.flip_vertical.set_active(transform.flip_vertical);
+        rotation_adjustment.connect_value_changed(clone!(@weak object => move |_| {
+            object.emit_by_name::<()>("transform", &[&object.transform()]);
+        }));
+        for check_button in [&imp.flip_horizontal, &imp.flip_vertical] {
+            check_button.connect_toggled(clone!(@weak object => move |_| {
+                object.emit_by_name::<()>("transform", &[&object.transform()]);
+            }));
+        }
+
+        imp.display_mode
+            .set_selected(settings.int("display-mode") as u32);
+        let sizes = [
+            (&imp.window_width, "window-width"),
+            (&imp.window_height, "window-height"),
+            (&imp.virtual_width, "virtual-display-width"),
+            (&imp.virtual_height, "virtual-display-height"),
+        ];
+        for (spin_button, key) in sizes {
+            let size = settings.int(key) as f64;
+            let adjustment =
+                Adjustment::new(size, MIN_WINDOW_SIZE, MAX_WINDOW_SIZE, 1.0, 100.0, 0.0);
+            spin_button.set_adjustment(&adjustment);
+        }
+        imp.virtual_display
+            .set_active(settings.boolean("virtual-display"));
+
+        imp.recording_format
+            .set_selected(settings.int("recording-format") as u32);
+        // video encoders need an even number of pixels in both directions
+        let recording_sizes = [
+            (&imp.recording_width, "recording-width"),
+            (&imp.recording_height, "recording-height"),
+        ];
+        for (spin_button, key) in recording_sizes {
+            let size = settings.int(key) as f64;
+            let adjustment =
+                Adjustment::new(size, MIN_WINDOW_SIZE, MAX_WINDOW_SIZE, 2.0, 100.0, 0.0);
+            spin_button.set_adjustment(&adjustment);
+        }
+        let framerate = settings.int("recording-framerate") as f64;
+        let framerate_adjustment =
+            Adjustment::new(framerate, MIN_FRAMERATE, MAX_FRAMERATE, 1.0, 10.0, 0.0);
+        imp.recording_framerate
+            .set_adjustment(&framerate_adjustment);
+        imp.recording_audio
+            .set_active(settings.boolean("recording-audio"));
+
+        let video_offset = settings.int("video-thumbnail-offset") as f64;
+        let video_offset_adjustment =
+            Adjustment::new(video_offset, 0.0, MAX_VIDEO_OFFSET, 1.0, 10.0, 0.0);
+        imp.video_offset.set_adjustment(&video_offset_adjustment);
+        imp.hover_preview
+            .set_active(settings.boolean("video-hover-preview"));
+
         object
     }
 
+    /// The transform of the display as it is set in the options
+    pub fn transform(&self) -> DisplayTransform {
+        let imp = self.imp();
+        DisplayTransform {
+            rotation: Rotation::from_degrees(imp.display_rotation.value() as f32),
+            flip_horizontal: imp.flip_horizontal.is_active(),
+            flip_vertical: imp.flip_vertical.is_active(),
+        }
+    }
+
     /// Signal emitted when the confirm button is pressed
     pub fn connect_confirm<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
@@ -251,6 +453,20 @@ impl DdOptionsWindow {
         )
     }
 
+    /// Signal emitted when the rotation or mirroring of the display is changed
+    pub fn connect_transform<F: Fn(&Self, DisplayTransform) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "transform",
+            true,
+            glib::closure_local!(|window, transform| {
+                f(window, transform);
+            }),
+        )
+    }
+
     /// Signal emitted when a new color is selected
     pub fn connect_color<F: Fn(&Self, u32) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
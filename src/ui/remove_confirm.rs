@@ -92,11 +92,15 @@ glib::wrapper! {
 
 impl RemoveConfirmWindow {
     pub fn new(app: &Application, campaign: &DdCampaign) -> Self {
+        let message = format!("Are you sure you want to delete {}?", campaign.name());
+        Self::with_message(app, &message)
+    }
+
+    /// Ask the user to confirm something else than removing a campaign
+    pub fn with_message(app: &Application, message: &str) -> Self {
         let object = glib::Object::new::<Self>();
         object.set_property("application", app);
-
-        let message = format!("Are you sure you want to delete {}?", campaign.name());
-        object.imp().message_label.set_text(&message);
+        object.imp().message_label.set_text(message);
 
         object
     }
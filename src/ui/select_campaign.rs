@@ -16,7 +16,7 @@ mod imp {
     use gtk::glib::object::ObjectExt;
     use gtk::prelude::*;
     use gtk::subclass::prelude::*;
-    use gtk::{glib, template_callbacks, Box, Button, CompositeTemplate, Grid, Label};
+    use gtk::{glib, template_callbacks, Box, Button, CheckButton, CompositeTemplate, Grid, Label};
 
     use crate::campaign::DdCampaign;
 
@@ -29,6 +29,8 @@ mod imp {
         #[template_child]
         pub campaign_grid: TemplateChild<Grid>,
         #[template_child]
+        pub choose_monitors: TemplateChild<CheckButton>,
+        #[template_child]
         pub remove_add_box: TemplateChild<Box>,
         pub message: RefCell<String>,
     }
@@ -77,7 +79,7 @@ mod imp {
                     Signal::builder("remove-campaign").build(),
                     Signal::builder("add-campaign").build(),
                     Signal::builder("campaign")
-                        .param_types([DdCampaign::static_type()])
+                        .param_types([DdCampaign::static_type(), bool::static_type()])
                         .build(),
                 ]
             })
@@ -114,6 +116,7 @@ impl SelectCampaignWindow {
 
         if campaign_list.is_empty() {
             imp.select_message.set_text("You have no campaigns yet");
+            imp.choose_monitors.set_visible(false);
             imp.remove_add_box.remove(
                 &imp.remove_add_box
                     .first_child()
@@ -129,9 +132,10 @@ impl SelectCampaignWindow {
             imp.campaign_grid
                 .attach(&button, index % 4, index / 4, 1, 1);
             let campaign = DdCampaign::from(campaign);
-            button.connect_clicked(
-                clone!(@weak object => move |_| object.emit_by_name::<()>("campaign", &[&campaign])),
-            );
+            button.connect_clicked(clone!(@weak object => move |_| {
+                let choose_monitors = object.imp().choose_monitors.is_active();
+                object.emit_by_name::<()>("campaign", &[&campaign, &choose_monitors]);
+            }));
             index += 1;
         }
         object
@@ -159,16 +163,17 @@ impl SelectCampaignWindow {
         )
     }
 
-    /// Signal emitted when a campaign button is clicked
-    pub fn connect_campaign<F: Fn(&Self, DdCampaign) + 'static>(
+    /// Signal emitted when a campaign button is clicked, the bool is true if the monitors should
+    /// be selected again
+    pub fn connect_campaign<F: Fn(&Self, DdCampaign, bool) + 'static>(
         &self,
         f: F,
     ) -> glib::SignalHandlerId {
         self.connect_closure(
             "campaign",
             true,
-            glib::closure_local!(|window, campaign| {
-                f(window, campaign);
+            glib::closure_local!(|window, campaign, choose_monitors| {
+                f(window, campaign, choose_monitors);
             }),
         )
     }
@@ -3,21 +3,32 @@ use gdk4::{Display, Monitor};
 use gtk::glib::clone;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{gio, glib};
-use gtk::{prelude::*, Button};
+use gtk::{prelude::*, ToggleButton};
 use snafu::OptionExt;
 
 use crate::errors::{DragonDisplayError, OtherSnafu};
+use crate::monitors::{connected_monitors, monitor_label};
+use crate::APP_ID;
+
+/// The monitors that display windows are opened on, in the order they were selected
+#[derive(Clone, Debug, Default, glib::Boxed)]
+#[boxed_type(name = "DdMonitors")]
+pub struct Monitors(pub Vec<Monitor>);
 
 mod imp {
+    use std::cell::RefCell;
     use std::sync::OnceLock;
 
     use gdk4::Monitor;
     use glib::subclass::InitializingObject;
     use gtk::glib::subclass::Signal;
-    use gtk::prelude::StaticType;
+    use gtk::prelude::*;
     use gtk::subclass::prelude::*;
-    use gtk::Grid;
-    use gtk::{glib, CompositeTemplate};
+    use gtk::{
+        glib, template_callbacks, Button, CheckButton, CompositeTemplate, Grid, ToggleButton,
+    };
+
+    use super::Monitors;
 
     // Object holding the state
     #[derive(CompositeTemplate, Default)]
@@ -25,6 +36,14 @@ mod imp {
     pub struct SelectMonitorWindow {
         #[template_child]
         pub monitor_grid: TemplateChild<Grid>,
+        #[template_child]
+        pub virtual_display: TemplateChild<CheckButton>,
+        #[template_child]
+        pub start_button: TemplateChild<Button>,
+        /// The selected monitors, in the order they were selected
+        pub selected: RefCell<Vec<Monitor>>,
+        /// The connected monitors and their buttons
+        pub monitor_buttons: RefCell<Vec<(Monitor, ToggleButton)>>,
     }
 
     // The central trait for subclassing a GObject
@@ -37,6 +56,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -44,13 +64,25 @@ mod imp {
         }
     }
 
+    #[template_callbacks]
+    impl SelectMonitorWindow {
+        #[template_callback]
+        fn handle_start(&self, _: Button) {
+            if self.selected.borrow().is_empty() && !self.virtual_display.is_active() {
+                return;
+            }
+            let monitors = Monitors(self.selected.borrow().clone());
+            self.obj().emit_by_name::<()>("monitors", &[&monitors]);
+        }
+    }
+
     // Trait shared by all GObjects
     impl ObjectImpl for SelectMonitorWindow {
         fn signals() -> &'static [glib::subclass::Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
             SIGNALS.get_or_init(|| {
-                vec![Signal::builder("monitor")
-                    .param_types([Monitor::static_type()])
+                vec![Signal::builder("monitors")
+                    .param_types([Monitors::static_type()])
                     .build()]
             })
         }
@@ -78,44 +110,103 @@ glib::wrapper! {
 }
 
 impl SelectMonitorWindow {
-    pub fn new(app: &Application) -> Result<Self, DragonDisplayError> {
+    /// Create the window with a button for every connected monitor. The preselected monitors
+    /// are selected in their order, so they keep their display numbers
+    pub fn new(app: &Application, preselect: &[Monitor]) -> Result<Self, DragonDisplayError> {
         // set all properties
         let object = glib::Object::new::<Self>();
         object.set_property("application", app);
-        let display = Display::default().context(OtherSnafu {
+        Display::default().context(OtherSnafu {
             msg: "Could not find a display".to_string(),
         })?;
-        let mut i = 0;
-        while let Some(monitor) = display.monitors().item(i) {
-            let monitor = monitor
-                .to_value()
-                .get::<Monitor>()
-                .expect("Value needs to be monitor");
-
-            let label = format! {"{}cmx{}cm", monitor.width_mm()/10, monitor.height_mm()/10};
-            let button = Button::builder().label(label).build();
+        for (i, monitor) in connected_monitors().into_iter().enumerate() {
+            let button = ToggleButton::builder()
+                .label(monitor_label(&monitor))
+                .build();
             let index = i as i32;
             object
                 .imp()
                 .monitor_grid
                 .attach(&button, index % 4, index / 4, 1, 1);
 
-            button.connect_clicked(clone!(@weak object, @weak monitor => move |_| {
-                object.emit_by_name::<()>("monitor", &[&monitor]);
+            button.connect_toggled(clone!(@weak object, @weak monitor => move |button| {
+                object.toggle_monitor(&monitor, button.is_active());
             }));
-
-            i += 1;
+            object
+                .imp()
+                .monitor_buttons
+                .borrow_mut()
+                .push((monitor, button));
+        }
+        // the setting is read when the window opens, it may have changed since the last campaign
+        let settings = gio::Settings::new(APP_ID);
+        settings
+            .bind("virtual-display", &*object.imp().virtual_display, "active")
+            .build();
+        object
+            .imp()
+            .virtual_display
+            .connect_toggled(clone!(@weak object => move |_| object.update_start()));
+        object.update_start();
+        for monitor in preselect {
+            let button = object
+                .imp()
+                .monitor_buttons
+                .borrow()
+                .iter()
+                .find(|(m, _)| m == monitor)
+                .map(|(_, b)| b.clone());
+            if let Some(button) = button {
+                button.set_active(true);
+            }
         }
         Ok(object)
     }
 
-    /// Signal emitted when monitor button is pressed
-    pub fn connect_monitor<F: Fn(&Self, Monitor) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+    /// Add the monitor to the selected monitors or remove it, the display windows are numbered
+    /// in the order the monitors are selected
+    fn toggle_monitor(&self, monitor: &Monitor, selected: bool) {
+        let mut monitors = self.imp().selected.borrow_mut();
+        if selected {
+            monitors.push(monitor.clone());
+        } else {
+            monitors.retain(|m| m != monitor);
+        }
+        drop(monitors);
+        self.update_start();
+        self.update_labels();
+    }
+
+    /// The program can start without monitors when the virtual display is enabled
+    fn update_start(&self) {
+        let sensitive =
+            !self.imp().selected.borrow().is_empty() || self.imp().virtual_display.is_active();
+        self.imp().start_button.set_sensitive(sensitive);
+    }
+
+    /// Show the number of the display window on the buttons of the selected monitors
+    fn update_labels(&self) {
+        let selected = self.imp().selected.borrow();
+        for (monitor, button) in self.imp().monitor_buttons.borrow().iter() {
+            let label = monitor_label(monitor);
+            let label = match selected.iter().position(|m| m == monitor) {
+                Some(number) => format!("Display {}: {}", number + 1, label),
+                None => label,
+            };
+            button.set_label(&label);
+        }
+    }
+
+    /// Signal emitted when the start button is pressed with the selected monitors
+    pub fn connect_monitors<F: Fn(&Self, Monitors) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
         self.connect_closure(
-            "monitor",
+            "monitors",
             true,
-            glib::closure_local!(|window, monitor| {
-                f(window, monitor);
+            glib::closure_local!(|window, monitors| {
+                f(window, monitors);
             }),
         )
     }
@@ -0,0 +1,358 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use gdk4::{MemoryFormat, MemoryTexture};
+use gstreamer::prelude::*;
+use gstreamer::{Caps, ClockTime, Element, ElementFactory, Pipeline, Sample, SeekFlags, State};
+use gstreamer_app::{AppSink, AppSinkCallbacks};
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+use gtk::{gio, glib};
+use snafu::OptionExt;
+
+use crate::errors::{DragonDisplayError, OtherSnafu};
+use crate::thumbnail_cache::{cache_file, store, THUMBNAIL_SIZE};
+use crate::{runtime, APP_ID};
+
+/// The maximum time to wait for a video to load
+const STATE_TIMEOUT: ClockTime = ClockTime::from_seconds(10);
+
+/// The poster frame of a video together with the duration of the video in seconds
+pub struct VideoPoster {
+    pub data: Vec<u8>,
+    pub duration: Option<u64>,
+}
+
+/// A decoded video frame in RGB format
+struct Frame {
+    width: i32,
+    height: i32,
+    stride: i32,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn texture(self) -> MemoryTexture {
+        MemoryTexture::new(
+            self.width,
+            self.height,
+            MemoryFormat::R8g8b8,
+            &glib::Bytes::from_owned(self.data),
+            self.stride as usize,
+        )
+    }
+
+    fn pixbuf(self) -> Pixbuf {
+        Pixbuf::from_mut_slice(
+            self.data,
+            Colorspace::Rgb,
+            false,
+            8,
+            self.width,
+            self.height,
+            self.stride,
+        )
+    }
+}
+
+/// An event that the preview pipeline sends to the main thread
+enum PreviewEvent {
+    Frame(Frame),
+    Finished,
+}
+
+/// Returns the png data of the frame at offset percent of the video and the duration of the
+/// video. The poster is read from the cache if it exists, otherwise it is decoded in its own
+/// pipeline so it never interferes with a video that is being displayed. This function blocks,
+/// so it should not be called on the main thread
+pub fn video_poster(path: &Path, offset: u32) -> Result<VideoPoster, DragonDisplayError> {
+    let cache_file = cache_file(path, &format!("video-{}", offset))?;
+    let duration_file = cache_file.with_extension("duration");
+    if let Ok(data) = fs::read(&cache_file) {
+        let duration = fs::read_to_string(&duration_file)
+            .ok()
+            .and_then(|d| d.trim().parse().ok());
+        return Ok(VideoPoster { data, duration });
+    }
+    let (pipeline, appsink) = thumbnail_pipeline(path)?;
+    let result = poster_frame(&pipeline, &appsink, offset);
+    let _ = pipeline.set_state(State::Null);
+    let (frame, duration) = result?;
+    let data = store(&cache_file, &frame.pixbuf())?;
+    if let Some(duration) = duration {
+        let _ = fs::write(&duration_file, duration.to_string());
+    }
+    Ok(VideoPoster { data, duration })
+}
+
+/// The position of the poster frame in percent of the video, as set in the preferences
+pub fn video_offset() -> u32 {
+    gio::Settings::new(APP_ID)
+        .int("video-thumbnail-offset")
+        .clamp(0, 90) as u32
+}
+
+/// Format a duration in seconds as hours:minutes:seconds, the hours are left out for videos
+/// shorter than an hour
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match hours {
+        0 => format!("{}:{:02}", minutes, seconds),
+        _ => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+/// A muted, looping preview of a video at thumbnail size. The preview stops when it is dropped
+pub struct VideoPreview {
+    pipeline: Pipeline,
+    /// Set when the preview is dropped, guards the state changes of the starting thread
+    stopped: Arc<Mutex<bool>>,
+}
+
+impl VideoPreview {
+    /// Start playing the video from offset percent of the video, every frame is passed to f on
+    /// the main thread
+    pub fn start<F: Fn(MemoryTexture) + 'static>(
+        path: &Path,
+        offset: u32,
+        f: F,
+    ) -> Result<Self, DragonDisplayError> {
+        let (pipeline, appsink) = thumbnail_pipeline(path)?;
+        // the frames are paced by the pipeline, so the channel does not need a bound
+        let (sender, receiver) = async_channel::unbounded();
+        let eos_sender = sender.clone();
+        appsink.set_callbacks(
+            AppSinkCallbacks::builder()
+                .new_sample(move |appsink| {
+                    let sample = appsink
+                        .pull_sample()
+                        .map_err(|_| gstreamer::FlowError::Eos)?;
+                    if let Ok(frame) = sample_frame(&sample) {
+                        let _ = sender.try_send(PreviewEvent::Frame(frame));
+                    }
+                    Ok(gstreamer::FlowSuccess::Ok)
+                })
+                .eos(move |_| {
+                    let _ = eos_sender.try_send(PreviewEvent::Finished);
+                })
+                .build(),
+        );
+
+        // loading the video blocks, so the preview is started in the background
+        let stopped = Arc::new(Mutex::new(false));
+        let start = Arc::new(Mutex::new(ClockTime::ZERO));
+        runtime().spawn_blocking({
+            let pipeline = pipeline.clone();
+            let stopped = stopped.clone();
+            let start = start.clone();
+            move || {
+                if pipeline.set_state(State::Paused).is_err() || wait_for_state(&pipeline).is_err()
+                {
+                    return;
+                }
+                if let Some(position) = offset_position(&pipeline, offset) {
+                    *start.lock().expect("Could not lock start position") = position;
+                    let _ = pipeline.seek_simple(SeekFlags::FLUSH | SeekFlags::KEY_UNIT, position);
+                }
+                let stopped = stopped.lock().expect("Could not lock preview state");
+                if !*stopped {
+                    let _ = pipeline.set_state(State::Playing);
+                }
+            }
+        });
+
+        let weak_pipeline = pipeline.downgrade();
+        let preview_stopped = stopped.clone();
+        gstreamer::glib::spawn_future_local(async move {
+            while let Ok(event) = receiver.recv().await {
+                // frames that were still queued when the preview stopped are not shown
+                if *preview_stopped
+                    .lock()
+                    .expect("Could not lock preview state")
+                {
+                    break;
+                }
+                match event {
+                    PreviewEvent::Frame(frame) => f(frame.texture()),
+                    PreviewEvent::Finished => {
+                        let Some(pipeline) = weak_pipeline.upgrade() else {
+                            break;
+                        };
+                        let position = *start.lock().expect("Could not lock start position");
+                        let _ =
+                            pipeline.seek_simple(SeekFlags::FLUSH | SeekFlags::KEY_UNIT, position);
+                    }
+                }
+            }
+        });
+        Ok(Self { pipeline, stopped })
+    }
+}
+
+impl Drop for VideoPreview {
+    fn drop(&mut self) {
+        let mut stopped = self.stopped.lock().expect("Could not lock preview state");
+        *stopped = true;
+        let _ = self.pipeline.set_state(State::Null);
+    }
+}
+
+/// Create a pipeline that decodes the video stream of the file to RGB frames of thumbnail
+/// width, other streams such as audio are not decoded
+fn thumbnail_pipeline(path: &Path) -> Result<(Pipeline, AppSink), DragonDisplayError> {
+    gstreamer::init().ok().context(OtherSnafu {
+        msg: "Could not initialize gstreamer".to_string(),
+    })?;
+    let location = path.to_str().context(OtherSnafu {
+        msg: "The file path is not valid unicode".to_string(),
+    })?;
+    let pipeline = Pipeline::new();
+    let source = make_element("filesrc")?;
+    source.set_property("location", location);
+    let decodebin = make_element("decodebin")?;
+    let videoconvert = make_element("videoconvert")?;
+    let videoscale = make_element("videoscale")?;
+    // only the width is fixed, so the height follows the aspect ratio of the video
+    let caps = Caps::builder("video/x-raw")
+        .field("format", "RGB")
+        .field("width", THUMBNAIL_SIZE)
+        .field("pixel-aspect-ratio", gstreamer::Fraction::new(1, 1))
+        .build();
+    let appsink = ElementFactory::make("appsink")
+        .property("caps", caps)
+        .property("max-buffers", 1u32)
+        .property("drop", true)
+        .build()
+        .ok()
+        .and_then(|e| e.dynamic_cast::<AppSink>().ok())
+        .context(OtherSnafu {
+            msg: "Could not create the video thumbnail sink".to_string(),
+        })?;
+    let elements = [&source, &decodebin, &videoconvert, &videoscale];
+    pipeline
+        .add_many(elements)
+        .and_then(|_| pipeline.add(&appsink))
+        .and_then(|_| Element::link_many([&source, &decodebin]))
+        .and_then(|_| Element::link_many([&videoconvert, &videoscale, appsink.upcast_ref()]))
+        .ok()
+        .context(OtherSnafu {
+            msg: "Could not create the video thumbnail pipeline".to_string(),
+        })?;
+
+    let videoconvert_weak = videoconvert.downgrade();
+    decodebin.connect_pad_added(move |_, src| {
+        let is_video = src
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("video/")))
+            .unwrap_or(false);
+        if !is_video {
+            return;
+        }
+        if let Some(videoconvert) = videoconvert_weak.upgrade() {
+            if let Some(sink_pad) = videoconvert.static_pad("sink") {
+                if !sink_pad.is_linked() {
+                    let _ = src.link(&sink_pad);
+                }
+            }
+        }
+    });
+    Ok((pipeline, appsink))
+}
+
+/// Create a gstreamer element from the factory with the given name
+fn make_element(name: &str) -> Result<Element, DragonDisplayError> {
+    ElementFactory::make(name).build().ok().context(OtherSnafu {
+        msg: format!("Could not create the gstreamer element {}", name),
+    })
+}
+
+/// Load the video, seek to offset percent of the video and return that frame with the duration
+/// of the video in seconds
+fn poster_frame(
+    pipeline: &Pipeline,
+    appsink: &AppSink,
+    offset: u32,
+) -> Result<(Frame, Option<u64>), DragonDisplayError> {
+    pipeline.set_state(State::Paused).ok().context(OtherSnafu {
+        msg: "Could not load the video".to_string(),
+    })?;
+    wait_for_state(pipeline)?;
+    let duration = pipeline.query_duration::<ClockTime>();
+    if let Some(position) = offset_position(pipeline, offset) {
+        // videos that can not seek show their first frame
+        if pipeline
+            .seek_simple(SeekFlags::FLUSH | SeekFlags::ACCURATE, position)
+            .is_ok()
+        {
+            wait_for_state(pipeline)?;
+        }
+    }
+    let sample = appsink
+        .try_pull_preroll(STATE_TIMEOUT)
+        .context(OtherSnafu {
+            msg: "Could not decode a frame of the video".to_string(),
+        })?;
+    Ok((sample_frame(&sample)?, duration.map(|d| d.seconds())))
+}
+
+/// The position at offset percent of the video, None if the duration is unknown
+fn offset_position(pipeline: &Pipeline, offset: u32) -> Option<ClockTime> {
+    let duration = pipeline.query_duration::<ClockTime>()?;
+    Some(ClockTime::from_nseconds(
+        duration.nseconds() / 100 * u64::from(offset.min(100)),
+    ))
+}
+
+/// Wait until the pipeline finished changing its state
+fn wait_for_state(pipeline: &Pipeline) -> Result<(), DragonDisplayError> {
+    let (result, _, _) = pipeline.state(STATE_TIMEOUT);
+    result.ok().context(OtherSnafu {
+        msg: "Could not load the video".to_string(),
+    })?;
+    Ok(())
+}
+
+/// Copy the frame out of the sample
+fn sample_frame(sample: &Sample) -> Result<Frame, DragonDisplayError> {
+    let structure = sample
+        .caps()
+        .and_then(|caps| caps.structure(0))
+        .context(OtherSnafu {
+            msg: "The video frame has no format".to_string(),
+        })?;
+    let (width, height) = match (
+        structure.get::<i32>("width"),
+        structure.get::<i32>("height"),
+    ) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => (width, height),
+        _ => {
+            return Err(DragonDisplayError::Other {
+                msg: "The video frame has no size".to_string(),
+            })
+        }
+    };
+    let buffer = sample.buffer().context(OtherSnafu {
+        msg: "The video sample has no frame".to_string(),
+    })?;
+    let data = buffer
+        .map_readable()
+        .ok()
+        .context(OtherSnafu {
+            msg: "Could not read the video frame".to_string(),
+        })?
+        .as_slice()
+        .to_vec();
+    // the rows of a frame are padded, so the stride is not always three times the width
+    let stride = data.len() as i32 / height;
+    if stride < width * 3 {
+        return Err(DragonDisplayError::Other {
+            msg: "The video frame is incomplete".to_string(),
+        });
+    }
+    Ok(Frame {
+        width,
+        height,
+        stride,
+        data,
+    })
+}
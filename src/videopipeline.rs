@@ -7,15 +7,24 @@ use gstreamer::Pipeline;
 use gstreamer::{Caps, Element, ElementFactory};
 use gstreamer::{SeekFlags, SeekType};
 use gstreamer_app::AppSink;
-use gtk::gdk_pixbuf::Pixbuf;
+use snafu::OptionExt;
 
+use crate::errors::{DragonDisplayError, OtherSnafu};
 use crate::runtime;
 
+/// An event that the video pipeline sends while playing a video
+pub enum VideoEvent {
+    /// A new frame of the video in RGB format
+    Frame { data: Vec<u8> },
+    /// The video reached its end and is not looping
+    Finished,
+}
+
 pub struct VideoPipeline {
     pipeline: Pipeline,
     appsink: AppSink,
     source: Element,
-    sender: Option<Sender<Vec<u8>>>,
+    sender: Option<Sender<VideoEvent>>,
 }
 
 impl VideoPipeline {
@@ -72,8 +81,14 @@ impl VideoPipeline {
     }
 
     /// Play the video  file and start sending frames through the sender, call
-    /// connect_frames. This function returns the width and height of the video
-    pub fn play_video(&mut self, path: &str, sender: Sender<Vec<u8>>) -> (i32, i32) {
+    /// connect_event. If looping is false a Finished event is sent when the video ends, otherwise
+    /// the video starts over. This function returns the width and height of the video
+    pub fn play_video(
+        &mut self,
+        path: &str,
+        sender: Sender<VideoEvent>,
+        looping: bool,
+    ) -> (i32, i32) {
         self.source.set_property("location", path);
         let appsink = self.appsink.clone();
         self.sender = Some(sender.clone());
@@ -87,10 +102,22 @@ impl VideoPipeline {
         let height = structure
             .get::<i32>("height")
             .expect("Could not get height");
+        // the first frame is shown right away, so a video that is paused before it plays is not
+        // left blank
+        if let Some(buffer) = sample.buffer() {
+            if let Ok(map) = buffer.map_readable() {
+                let _ = sender.try_send(VideoEvent::Frame {
+                    data: map.as_slice().to_vec(),
+                });
+            }
+        }
         let pipeline = self.pipeline.clone();
 
         runtime().spawn(async move {
             loop {
+                if sender.is_closed() {
+                    break;
+                }
                 if !appsink.is_eos() {
                     if let Some(sample) = appsink.try_pull_sample(ClockTime::from_useconds(100)) {
                         let buffer = sample.buffer().expect("Could not get sample buffer");
@@ -99,10 +126,13 @@ impl VideoPipeline {
                             .expect("Could not get map")
                             .as_slice()
                             .to_vec();
-                        if let Err(_) = sender.send(data).await {
+                        if let Err(_) = sender.send(VideoEvent::Frame { data }).await {
                             break;
                         }
                     }
+                } else if !looping {
+                    let _ = sender.send(VideoEvent::Finished).await;
+                    break;
                 } else {
                     let seek_event = gstreamer::event::Seek::new(
                         1.0,
@@ -129,43 +159,28 @@ impl VideoPipeline {
         }
     }
 
-    pub fn thumbnail(&self, path: &str) -> Pixbuf {
-        self.source.set_property("location", path);
-        self.pipeline
-            .set_state(gstreamer::State::Playing)
-            .expect("Could not set state to playing");
-        let sample = self.appsink.pull_preroll().expect("Could not get sample");
-        let buffer = sample.buffer().expect("Could not get sample buffer");
-        let caps = sample.caps().expect("Could not get caps");
-        let structure = caps.structure(0).expect("Could not get caps structure");
-        let width = structure.get::<i32>("width").expect("Could not get width");
-        let height = structure
-            .get::<i32>("height")
-            .expect("Could not get height");
-        let data = buffer
-            .map_readable()
-            .expect("Could not get map")
-            .as_slice()
-            .to_vec();
-        self.pipeline
-            .set_state(gstreamer::State::Ready)
-            .expect("Could not set state to null");
-        Pixbuf::from_mut_slice(
-            data,
-            gtk::gdk_pixbuf::Colorspace::Rgb,
-            false,
-            8,
-            width,
-            height,
-            width * 3,
-        )
+    /// Pause or resume the video that is currently playing
+    pub fn set_paused(&self, paused: bool) -> Result<(), DragonDisplayError> {
+        // only change the state if a video is playing, otherwise the last video would be loaded
+        match &self.sender {
+            Some(sender) if !sender.is_closed() => (),
+            _ => return Ok(()),
+        }
+        let state = match paused {
+            true => gstreamer::State::Paused,
+            false => gstreamer::State::Playing,
+        };
+        self.pipeline.set_state(state).ok().context(OtherSnafu {
+            msg: "Could not pause or resume the video".to_owned(),
+        })?;
+        Ok(())
     }
 
-    /// Connect to the sender that sends frames
-    pub fn connect_frame<F: Fn(Vec<u8>) + 'static>(receiver: Receiver<Vec<u8>>, f: F) {
+    /// Connect to the sender that sends video events
+    pub fn connect_event<F: Fn(VideoEvent) + 'static>(receiver: Receiver<VideoEvent>, f: F) {
         spawn_future_local(async move {
-            while let Ok(frame) = receiver.recv().await {
-                f(frame)
+            while let Ok(event) = receiver.recv().await {
+                f(event)
             }
         });
     }
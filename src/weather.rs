@@ -0,0 +1,269 @@
+use std::f32::consts::TAU;
+
+use gdk4::RGBA;
+use gtk::graphene::{Point, Rect};
+use gtk::gsk::ColorStop;
+use gtk::{glib, Snapshot};
+
+/// The amount of particles of every kind of weather at full intensity, these are kept low so the
+/// effects run on a renderer without a gpu
+const MAX_RAIN: f32 = 500.0;
+const MAX_SNOW: f32 = 350.0;
+const MAX_FOG: f32 = 10.0;
+const MAX_EMBERS: f32 = 120.0;
+/// The average amount of lightning strikes per second at full intensity
+const LIGHTNING_RATE: f32 = 0.25;
+
+/// The kind of weather that is drawn over the display
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Snow,
+    Fog,
+    Embers,
+    Lightning,
+}
+
+/// The weather as it is set in the control window. The intensity goes from 0 to 1 and the wind
+/// from -1 (blowing to the left) to 1 (blowing to the right)
+#[derive(Clone, Copy, Debug, PartialEq, glib::Boxed)]
+#[boxed_type(name = "DdWeatherSettings")]
+pub struct WeatherSettings {
+    pub kind: WeatherKind,
+    pub intensity: f32,
+    pub wind: f32,
+}
+
+/// A particle of the weather. The position is in fractions of the display size, so the weather
+/// does not have to be recreated when the display changes size
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    x: f32,
+    y: f32,
+    /// A random value between 0 and 1 that varies the speed and size of the particle
+    size: f32,
+    /// A random angle that makes the particles sway out of step
+    phase: f32,
+}
+
+/// The state of the weather effect
+#[derive(Debug, Default)]
+pub struct Weather {
+    settings: WeatherSettings,
+    particles: Vec<Particle>,
+    /// The brightness of the lightning flash, fades from 1 to 0
+    flash: f32,
+    /// The time in seconds since the weather started, used for swaying particles
+    time: f32,
+}
+
+impl WeatherKind {
+    pub const ALL: [WeatherKind; 6] = [
+        WeatherKind::Clear,
+        WeatherKind::Rain,
+        WeatherKind::Snow,
+        WeatherKind::Fog,
+        WeatherKind::Embers,
+        WeatherKind::Lightning,
+    ];
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "Clear",
+            WeatherKind::Rain => "Rain",
+            WeatherKind::Snow => "Snow",
+            WeatherKind::Fog => "Drifting fog",
+            WeatherKind::Embers => "Embers",
+            WeatherKind::Lightning => "Lightning",
+        }
+    }
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        Self {
+            kind: WeatherKind::Clear,
+            intensity: 0.5,
+            wind: 0.0,
+        }
+    }
+}
+
+impl Particle {
+    fn random() -> Self {
+        Self {
+            x: random(),
+            y: random(),
+            size: random(),
+            phase: random() * TAU,
+        }
+    }
+}
+
+impl Weather {
+    /// Change the weather, the particles of the previous weather are removed when the kind of
+    /// weather changed
+    pub fn set_settings(&mut self, settings: WeatherSettings) {
+        if settings.kind != self.settings.kind {
+            self.particles.clear();
+            self.flash = 0.0;
+        }
+        self.settings = settings;
+    }
+
+    /// Returns true if there is weather that needs to be animated
+    pub fn is_active(&self) -> bool {
+        self.settings.kind != WeatherKind::Clear
+    }
+
+    /// Move the particles forward by the time in seconds since the last step
+    pub fn step(&mut self, seconds: f32) {
+        // a long pause such as a hidden window should not make the particles jump
+        let seconds = seconds.min(0.1);
+        self.time += seconds;
+        let WeatherSettings {
+            kind,
+            intensity,
+            wind,
+        } = self.settings;
+        let amount = match kind {
+            WeatherKind::Rain => MAX_RAIN,
+            WeatherKind::Snow => MAX_SNOW,
+            WeatherKind::Fog => MAX_FOG,
+            WeatherKind::Embers => MAX_EMBERS,
+            WeatherKind::Clear | WeatherKind::Lightning => 0.0,
+        };
+        let amount = (amount * intensity.clamp(0.0, 1.0)).round() as usize;
+        self.particles.truncate(amount);
+        while self.particles.len() < amount {
+            self.particles.push(Particle::random());
+        }
+
+        let time = self.time;
+        for particle in &mut self.particles {
+            let sway = (time + particle.phase).sin();
+            match kind {
+                WeatherKind::Rain => {
+                    let speed = 1.2 + 0.6 * particle.size;
+                    particle.y += speed * seconds;
+                    particle.x += wind * 0.5 * speed * seconds;
+                }
+                WeatherKind::Snow => {
+                    particle.y += (0.05 + 0.1 * particle.size) * seconds;
+                    particle.x += (wind * 0.1 + sway * 0.02) * seconds;
+                }
+                WeatherKind::Fog => {
+                    particle.x += (wind * 0.03 + 0.005 + 0.005 * particle.size) * seconds;
+                    particle.y += sway * 0.005 * seconds;
+                }
+                WeatherKind::Embers => {
+                    particle.y -= (0.05 + 0.15 * particle.size) * seconds;
+                    particle.x += (wind * 0.05 + sway * 0.03) * seconds;
+                }
+                WeatherKind::Clear | WeatherKind::Lightning => (),
+            }
+            // particles that leave the display come back at the other side
+            if particle.y > 1.05 {
+                particle.y -= 1.1;
+                particle.x = random();
+            } else if particle.y < -0.05 {
+                particle.y += 1.1;
+                particle.x = random();
+            }
+            particle.x = particle.x.rem_euclid(1.0);
+        }
+
+        self.flash = (self.flash - seconds * 3.0).max(0.0);
+        if kind == WeatherKind::Lightning
+            && self.flash == 0.0
+            && random() < LIGHTNING_RATE * intensity * seconds
+        {
+            self.flash = 1.0;
+        }
+    }
+
+    /// Draw the weather on a display of the given size in pixels
+    pub fn draw(&self, snapshot: &Snapshot, width: f32, height: f32) {
+        let WeatherSettings {
+            kind,
+            intensity,
+            wind,
+        } = self.settings;
+        snapshot.save();
+        match kind {
+            WeatherKind::Rain => {
+                let color = RGBA::new(0.7, 0.75, 0.85, 0.5);
+                let angle = -(wind * 0.5).atan().to_degrees();
+                for particle in &self.particles {
+                    let length = height * (0.02 + 0.02 * particle.size);
+                    snapshot.save();
+                    snapshot.translate(&Point::new(particle.x * width, particle.y * height));
+                    snapshot.rotate(angle);
+                    snapshot.append_color(&color, &Rect::new(-0.75, -length, 1.5, length));
+                    snapshot.restore();
+                }
+            }
+            WeatherKind::Snow => {
+                let color = RGBA::new(1.0, 1.0, 1.0, 0.8);
+                for particle in &self.particles {
+                    let size = 2.0 + 3.0 * particle.size;
+                    let (x, y) = (particle.x * width, particle.y * height);
+                    snapshot.append_color(&color, &Rect::new(x, y, size, size));
+                }
+            }
+            WeatherKind::Fog => {
+                let fog = RGBA::new(0.8, 0.8, 0.82, 0.25 + 0.35 * intensity);
+                let clear = RGBA::new(0.8, 0.8, 0.82, 0.0);
+                let stops = [ColorStop::new(0.0, fog), ColorStop::new(1.0, clear)];
+                for particle in &self.particles {
+                    let radius = width * (0.2 + 0.2 * particle.size);
+                    // the fog wraps around, so it is drawn a second time at the other side
+                    for offset in [-1.0, 0.0] {
+                        let center =
+                            Point::new((particle.x + offset) * width + radius, particle.y * height);
+                        let bounds = Rect::new(
+                            center.x() - radius,
+                            center.y() - radius * 0.5,
+                            radius * 2.0,
+                            radius,
+                        );
+                        snapshot.append_radial_gradient(
+                            &bounds,
+                            &center,
+                            radius,
+                            radius * 0.5,
+                            0.0,
+                            1.0,
+                            &stops,
+                        );
+                    }
+                }
+            }
+            WeatherKind::Embers => {
+                for particle in &self.particles {
+                    let glow = 0.5 + 0.5 * (self.time * 4.0 + particle.phase).sin();
+                    let color = RGBA::new(1.0, 0.35 + 0.3 * glow, 0.05, 0.4 + 0.6 * glow);
+                    let size = 2.0 + 2.0 * particle.size;
+                    let (x, y) = (particle.x * width, particle.y * height);
+                    snapshot.append_color(&color, &Rect::new(x, y, size, size));
+                }
+            }
+            WeatherKind::Clear | WeatherKind::Lightning => (),
+        }
+        if self.flash > 0.0 {
+            let color = RGBA::new(1.0, 1.0, 1.0, 0.8 * self.flash);
+            snapshot.append_color(&color, &Rect::new(0.0, 0.0, width, height));
+        }
+        snapshot.restore();
+    }
+}
+
+fn random() -> f32 {
+    glib::random_double() as f32
+}
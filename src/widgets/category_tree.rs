@@ -0,0 +1,188 @@
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, Label, ListBoxRow};
+
+/// The amount of pixels a subcategory is indented relative to its parent
+const INDENT: i32 = 12;
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{glib, CompositeTemplate, ListBox};
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/category_tree.ui")]
+    pub struct DdCategoryTree {
+        #[template_child]
+        pub category_list: TemplateChild<ListBox>,
+        /// The categories in the order of the rows, the first row is the empty category that
+        /// holds all media
+        pub categories: RefCell<Vec<String>>,
+        pub selected: RefCell<String>,
+        /// True while the rows are rebuilt, so no category signal is emitted
+        pub updating: Cell<bool>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdCategoryTree {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdCategoryTree";
+        type Type = super::DdCategoryTree;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdCategoryTree {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("category")
+                    .param_types([String::static_type()])
+                    .build()]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            // the tree is created from the control window template, so it is set up here
+            // instead of in new()
+            self.obj().setup();
+        }
+
+        fn dispose(&self) {
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdCategoryTree {}
+}
+
+glib::wrapper! {
+    pub struct DdCategoryTree(ObjectSubclass<imp::DdCategoryTree>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdCategoryTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdCategoryTree {
+    /// Create a category tree that only holds the category with all media
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    fn setup(&self) {
+        self.imp().category_list.connect_row_selected(
+            clone!(@weak self as object => move |_, row| {
+                if object.imp().updating.get() {
+                    return;
+                }
+                let row = match row {
+                    Some(r) => r,
+                    None => return,
+                };
+                let category = match object.imp().categories.borrow().get(row.index() as usize) {
+                    Some(c) => c.clone(),
+                    None => return,
+                };
+                object.select(category);
+            }),
+        );
+        self.set_categories(Vec::new());
+    }
+
+    /// The category that is selected, an empty string means all media
+    pub fn selected(&self) -> String {
+        self.imp().selected.borrow().clone()
+    }
+
+    /// Show the categories as a tree, categories are paths like "Act 1/Taverns" and should be
+    /// sorted so subcategories follow their parent. The selected category stays selected if it
+    /// still exists, otherwise all media is selected
+    pub fn set_categories(&self, categories: Vec<String>) {
+        let list = &self.imp().category_list;
+        self.imp().updating.set(true);
+        while let Some(row) = list.row_at_index(0) {
+            list.remove(&row);
+        }
+        list.append(&Self::row("All media", 0));
+        for category in &categories {
+            let depth = category.matches('/').count() as i32 + 1;
+            let name = category.rsplit('/').next().unwrap_or(category);
+            list.append(&Self::row(name, depth));
+        }
+
+        let mut all = vec![String::new()];
+        all.extend(categories);
+        let selected = self.selected();
+        let index = all.iter().position(|c| *c == selected).unwrap_or(0);
+        list.select_row(list.row_at_index(index as i32).as_ref());
+        let category = all[index].clone();
+        self.imp().categories.replace(all);
+        self.imp().updating.set(false);
+        self.select(category);
+    }
+
+    fn select(&self, category: String) {
+        if *self.imp().selected.borrow() == category {
+            return;
+        }
+        self.imp().selected.replace(category.clone());
+        self.emit_by_name::<()>("category", &[&category]);
+    }
+
+    fn row(name: &str, depth: i32) -> ListBoxRow {
+        let label = Label::builder()
+            .label(name)
+            .halign(gtk::Align::Start)
+            .margin_start(6 + depth * INDENT)
+            .margin_top(3)
+            .margin_bottom(3)
+            .build();
+        ListBoxRow::builder().child(&label).build()
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when another category is selected
+    pub fn connect_category<F: Fn(&Self, String) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "category",
+            true,
+            glib::closure_local!(|tree, category| {
+                f(tree, category);
+            }),
+        )
+    }
+}
@@ -0,0 +1,216 @@
+use gdk4::RGBA;
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, StringList};
+
+use crate::color_grade::{ColorGrade, GradePreset};
+
+mod imp {
+    use std::cell::Cell;
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{glib, template_callbacks, Button, ColorButton, CompositeTemplate, DropDown, Scale};
+
+    use crate::color_grade::ColorGrade;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/color_grade_controls.ui")]
+    pub struct DdColorGradeControls {
+        #[template_child]
+        pub preset_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub brightness_scale: TemplateChild<Scale>,
+        #[template_child]
+        pub contrast_scale: TemplateChild<Scale>,
+        #[template_child]
+        pub saturation_scale: TemplateChild<Scale>,
+        #[template_child]
+        pub tint_button: TemplateChild<ColorButton>,
+        #[template_child]
+        pub tint_scale: TemplateChild<Scale>,
+        /// True while the controls are filled in, no signals are emitted during this
+        pub updating: Cell<bool>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdColorGradeControls {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdColorGradeControls";
+        type Type = super::DdColorGradeControls;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdColorGradeControls {
+        #[template_callback]
+        fn handle_scale(&self, _: Scale) {
+            self.obj().changed();
+        }
+
+        #[template_callback]
+        fn handle_tint(&self, _: ColorButton) {
+            self.obj().changed();
+        }
+
+        #[template_callback]
+        fn handle_reset(&self, _: Button) {
+            self.obj().set_grade(&ColorGrade::default());
+            self.obj().changed();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdColorGradeControls {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("changed")
+                    .param_types([ColorGrade::static_type()])
+                    .build()]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            self.obj().setup();
+        }
+
+        fn dispose(&self) {
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdColorGradeControls {}
+}
+
+glib::wrapper! {
+    pub struct DdColorGradeControls(ObjectSubclass<imp::DdColorGradeControls>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdColorGradeControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdColorGradeControls {
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    /// Fill in the controls with the grade without emitting the changed signal
+    pub fn set_grade(&self, grade: &ColorGrade) {
+        let imp = self.imp();
+        imp.updating.set(true);
+        imp.brightness_scale.set_value(grade.brightness as f64);
+        imp.contrast_scale.set_value(grade.contrast as f64);
+        imp.saturation_scale.set_value(grade.saturation as f64);
+        let [red, green, blue] = grade.tint;
+        imp.tint_button.set_rgba(&RGBA::new(red, green, blue, 1.0));
+        imp.tint_scale.set_value(grade.tint_strength as f64);
+        self.update_preset(grade);
+        imp.updating.set(false);
+    }
+
+    /// The grade as it is set in the controls
+    pub fn grade(&self) -> ColorGrade {
+        let imp = self.imp();
+        let tint = imp.tint_button.rgba();
+        ColorGrade {
+            brightness: imp.brightness_scale.value() as f32,
+            contrast: imp.contrast_scale.value() as f32,
+            saturation: imp.saturation_scale.value() as f32,
+            tint: [tint.red(), tint.green(), tint.blue()],
+            tint_strength: imp.tint_scale.value() as f32,
+        }
+    }
+
+    /// Fill the preset dropdown, the first item is selected when the grade is not a preset
+    fn setup(&self) {
+        let mut names = vec!["Custom"];
+        names.extend(GradePreset::ALL.iter().map(|p| p.name()));
+        let dropdown = &self.imp().preset_dropdown;
+        dropdown.set_model(Some(&StringList::new(&names)));
+        dropdown.connect_selected_notify(clone!(@weak self as obj => move |dropdown| {
+            if obj.imp().updating.get() {
+                return;
+            }
+            let Some(preset) = (dropdown.selected() as usize)
+                .checked_sub(1)
+                .and_then(|i| GradePreset::ALL.get(i))
+            else {
+                return;
+            };
+            obj.set_grade(&preset.grade());
+            obj.changed();
+        }));
+        self.set_grade(&ColorGrade::default());
+    }
+
+    /// Select the preset that matches the grade in the preset dropdown
+    fn update_preset(&self, grade: &ColorGrade) {
+        let index = match GradePreset::find(grade) {
+            Some(preset) => preset as u32 + 1,
+            None => 0,
+        };
+        self.imp().preset_dropdown.set_selected(index);
+    }
+
+    /// Emit the changed signal with the grade of the controls
+    fn changed(&self) {
+        let imp = self.imp();
+        if imp.updating.get() {
+            return;
+        }
+        let grade = self.grade();
+        imp.updating.set(true);
+        self.update_preset(&grade);
+        imp.updating.set(false);
+        self.emit_by_name::<()>("changed", &[&grade]);
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the color grade is changed by the user
+    pub fn connect_changed<F: Fn(&Self, ColorGrade) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|controls, grade| {
+                f(controls, grade);
+            }),
+        )
+    }
+}
@@ -0,0 +1,302 @@
+use gtk::glib::clone;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, Button, CheckButton, Label, SpinButton};
+use gtk::{prelude::*, Orientation};
+use snafu::Report;
+
+use crate::errors::DragonDisplayError;
+use crate::initiative::{
+    read_combat, write_combat, Combat, Combatant, MAX_INITIATIVE, MIN_INITIATIVE,
+};
+use crate::try_emit;
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CheckButton, CompositeTemplate, Entry, Label, ListBox,
+        SpinButton, ToggleButton,
+    };
+
+    use crate::initiative::Combat;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/initiative_panel.ui")]
+    pub struct DdInitiativePanel {
+        #[template_child]
+        pub name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub modifier_spin: TemplateChild<SpinButton>,
+        #[template_child]
+        pub hidden_check: TemplateChild<CheckButton>,
+        #[template_child]
+        pub combatant_list: TemplateChild<ListBox>,
+        #[template_child]
+        pub round_label: TemplateChild<Label>,
+        pub campaign_path: OnceCell<String>,
+        pub combat: RefCell<Combat>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdInitiativePanel {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdInitiativePanel";
+        type Type = super::DdInitiativePanel;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdInitiativePanel {
+        #[template_callback]
+        fn handle_name_activate(&self, _: Entry) {
+            self.obj().add_combatant();
+        }
+
+        #[template_callback]
+        fn handle_add(&self, _: Button) {
+            self.obj().add_combatant();
+        }
+
+        #[template_callback]
+        fn handle_roll(&self, _: Button) {
+            self.obj().update_combat(|combat| combat.roll_all());
+        }
+
+        #[template_callback]
+        fn handle_previous(&self, _: Button) {
+            self.obj().update_combat(|combat| combat.previous_turn());
+        }
+
+        #[template_callback]
+        fn handle_next(&self, _: Button) {
+            self.obj().update_combat(|combat| combat.next_turn());
+        }
+
+        #[template_callback]
+        fn handle_end(&self, _: Button) {
+            self.obj().update_combat(|combat| combat.end());
+        }
+
+        #[template_callback]
+        fn handle_show(&self, button: ToggleButton) {
+            let shown = button.is_active();
+            self.obj().update_combat(|combat| combat.shown = shown);
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdInitiativePanel {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed")
+                        .param_types([Combat::static_type()])
+                        .build(),
+                    Signal::builder("error")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+        }
+
+        fn dispose(&self) {
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdInitiativePanel {}
+}
+
+glib::wrapper! {
+    pub struct DdInitiativePanel(ObjectSubclass<imp::DdInitiativePanel>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl DdInitiativePanel {
+    /// Create a new initiative panel showing the combat saved in the campaign at campaign_path
+    pub fn new(campaign_path: String) -> Result<Self, DragonDisplayError> {
+        let object = glib::Object::new::<Self>();
+        let combat = read_combat(&campaign_path)?;
+        object.imp().combat.replace(combat);
+        object
+            .imp()
+            .campaign_path
+            .set(campaign_path)
+            .expect("Expected campaign path to not be set");
+        object.rebuild_combatants();
+        Ok(object)
+    }
+
+    /// The current round of the combat, 0 if there is no combat going on
+    pub fn round(&self) -> u32 {
+        self.imp().combat.borrow().round
+    }
+
+    /// Add a combatant with the name in the name entry and roll its initiative
+    fn add_combatant(&self) {
+        let imp = self.imp();
+        let name = imp.name_entry.text().trim().to_string();
+        if name.is_empty() {
+            let msg = "A combatant needs a name".to_string();
+            self.emit_error(DragonDisplayError::InvalidName { msg }, false);
+            return;
+        }
+        let combatant = Combatant::new(
+            name,
+            imp.modifier_spin.value() as i32,
+            imp.hidden_check.is_active(),
+        );
+        imp.name_entry.set_text("");
+        self.update_combat(|combat| combat.add(combatant));
+    }
+
+    fn set_initiative(&self, index: usize, initiative: i32) {
+        self.update_combat(|combat| {
+            if let Some(combatant) = combat.combatants.get_mut(index) {
+                combatant.initiative = initiative;
+            }
+            combat.sort();
+        });
+    }
+
+    fn set_hidden(&self, index: usize, hidden: bool) {
+        self.update_combat(|combat| {
+            if let Some(combatant) = combat.combatants.get_mut(index) {
+                combatant.hidden = hidden;
+            }
+        });
+    }
+
+    /// Change the combat, save it and update the panel and the display
+    fn update_combat<F: FnOnce(&mut Combat)>(&self, f: F) {
+        let combat = {
+            let mut combat = self.imp().combat.borrow_mut();
+            f(&mut combat);
+            combat.clone()
+        };
+        self.rebuild_combatants();
+        self.emit_by_name::<()>("changed", &[&combat]);
+        let campaign_path = self
+            .imp()
+            .campaign_path
+            .get()
+            .expect("Expected a campaign path");
+        try_emit!(self, write_combat(campaign_path, &combat), false);
+    }
+
+    /// Recreate the rows of the combatant list, the combatant whose turn it is is selected
+    fn rebuild_combatants(&self) {
+        let imp = self.imp();
+        let combatant_list = &imp.combatant_list;
+        while let Some(row) = combatant_list.first_child() {
+            combatant_list.remove(&row);
+        }
+        let combat = imp.combat.borrow().clone();
+        imp.round_label.set_label(&match combat.round {
+            0 => "No combat".to_string(),
+            round => format!("Round {}", round),
+        });
+        for (index, combatant) in combat.combatants.iter().enumerate() {
+            let row = gtk::Box::builder()
+                .orientation(Orientation::Horizontal)
+                .spacing(3)
+                .build();
+            let label = Label::builder()
+                .label(&combatant.name)
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            row.append(&label);
+            let initiative = SpinButton::with_range(MIN_INITIATIVE, MAX_INITIATIVE, 1.0);
+            initiative.set_value(combatant.initiative as f64);
+            initiative.set_tooltip_text(Some("Initiative"));
+            initiative.connect_value_changed(clone!(@weak self as obj => move |spin| {
+                obj.set_initiative(index, spin.value() as i32);
+            }));
+            row.append(&initiative);
+            let hidden = CheckButton::with_label("Hidden");
+            hidden.set_active(combatant.hidden);
+            hidden.connect_toggled(clone!(@weak self as obj => move |check| {
+                obj.set_hidden(index, check.is_active());
+            }));
+            row.append(&hidden);
+            let remove = Button::with_label("Remove");
+            remove.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.update_combat(|combat| combat.remove(index));
+            }));
+            row.append(&remove);
+            combatant_list.append(&row);
+        }
+        match combatant_list.row_at_index(combat.turn as i32) {
+            Some(row) if combat.round > 0 => combatant_list.select_row(Some(&row)),
+            _ => combatant_list.unselect_all(),
+        }
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the combat changed
+    pub fn connect_changed<F: Fn(&Self, Combat) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|panel, combat| {
+                f(panel, combat);
+            }),
+        )
+    }
+
+    /// Emit an error message based on the input error
+    pub fn emit_error(&self, err: DragonDisplayError, fatal: bool) {
+        let msg = Report::from_error(err).to_string();
+        self.emit_by_name::<()>("error", &[&msg, &fatal]);
+    }
+
+    /// Signal emitted when an error occurs
+    pub fn connect_error<F: Fn(&Self, String, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "error",
+            true,
+            glib::closure_local!(|panel, msg, fatal| {
+                f(panel, msg, fatal);
+            }),
+        )
+    }
+}
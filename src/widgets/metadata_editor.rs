@@ -0,0 +1,139 @@
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, Widget};
+
+use crate::metadata::{parse_tags, MediaMetadata};
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CheckButton, CompositeTemplate, Entry, Label, TextView,
+    };
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/metadata_editor.ui")]
+    pub struct DdMetadataEditor {
+        #[template_child]
+        pub name_label: TemplateChild<Label>,
+        #[template_child]
+        pub favorite_check: TemplateChild<CheckButton>,
+        #[template_child]
+        pub tags_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub notes_view: TemplateChild<TextView>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdMetadataEditor {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdMetadataEditor";
+        type Type = super::DdMetadataEditor;
+        type ParentType = gtk::Popover;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdMetadataEditor {
+        #[template_callback]
+        fn handle_save(&self, _: Button) {
+            self.obj().emit_by_name::<()>("saved", &[]);
+            self.obj().popdown();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdMetadataEditor {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("saved").build()])
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdMetadataEditor {}
+
+    // Trait shared by all popovers
+    impl PopoverImpl for DdMetadataEditor {}
+}
+
+glib::wrapper! {
+    pub struct DdMetadataEditor(ObjectSubclass<imp::DdMetadataEditor>)
+        @extends gtk::Popover, gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                        gtk::Native, gtk::ShortcutManager;
+}
+
+impl DdMetadataEditor {
+    /// Create an editor for the metadata of the file with the given name. The editor is shown
+    /// next to the parent widget and removes itself from the parent when it is closed
+    pub fn new(parent: &impl IsA<Widget>, file_name: &str, metadata: &MediaMetadata) -> Self {
+        let object = glib::Object::new::<Self>();
+        let imp = object.imp();
+        imp.name_label.set_text(file_name);
+        imp.favorite_check.set_active(metadata.favorite);
+        imp.tags_entry.set_text(&metadata.tags.join(", "));
+        imp.notes_view.buffer().set_text(&metadata.notes);
+        object.set_parent(parent);
+        object.connect_closed(|editor| {
+            // unparenting during the closed signal would break the popdown animation
+            glib::idle_add_local_once(glib::clone!(@weak editor => move || {
+                editor.unparent();
+            }));
+        });
+        object
+    }
+
+    /// The metadata as it is entered in the editor
+    pub fn metadata(&self) -> MediaMetadata {
+        let imp = self.imp();
+        let buffer = imp.notes_view.buffer();
+        let notes = buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .trim()
+            .to_string();
+        MediaMetadata {
+            tags: parse_tags(&imp.tags_entry.text()),
+            favorite: imp.favorite_check.is_active(),
+            notes,
+        }
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the save button is pressed
+    pub fn connect_saved<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "saved",
+            true,
+            glib::closure_local!(|editor| {
+                f(editor);
+            }),
+        )
+    }
+}
@@ -1,6 +1,16 @@
+pub mod category_tree;
+pub mod color_grade_controls;
 pub mod google_folder_object;
 pub mod google_folder_tree;
+pub mod initiative_panel;
+pub mod metadata_editor;
+pub mod pdf_controls;
+pub mod playlist_panel;
 pub mod progress_bar;
+pub mod text_card_panel;
 pub mod thumbnail;
 pub mod thumbnail_grid;
+pub mod timer_panel;
 pub mod vtt_area;
+pub mod weather_controls;
+pub mod weather_layer;
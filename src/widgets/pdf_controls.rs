@@ -0,0 +1,465 @@
+use std::path::{Path, PathBuf};
+
+use gdk4::Texture;
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{gio, glib, Button, Label, Orientation, Picture};
+use snafu::{Report, ResultExt};
+
+use crate::errors::{DragonDisplayError, GlibSnafu};
+use crate::pdf::{page_count, render_page, send_page_thumbnails, PdfPage};
+use crate::runtime;
+
+/// The size in pixels of the page that is shown to select the crop
+const CROP_PREVIEW_SIZE: i32 = 1024;
+/// A crop smaller than this fraction of the page is ignored, it is most likely a click
+const MIN_CROP_SIZE: f64 = 0.01;
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CompositeTemplate, DrawingArea, Label, Overlay, Picture,
+        ToggleButton,
+    };
+
+    use crate::pdf::PdfPage;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/pdf_controls.ui")]
+    pub struct DdPdfControls {
+        #[template_child]
+        pub previous_button: TemplateChild<Button>,
+        #[template_child]
+        pub next_button: TemplateChild<Button>,
+        #[template_child]
+        pub page_label: TemplateChild<Label>,
+        #[template_child]
+        pub crop_button: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub clear_crop_button: TemplateChild<Button>,
+        #[template_child]
+        pub crop_overlay: TemplateChild<Overlay>,
+        #[template_child]
+        pub crop_picture: TemplateChild<Picture>,
+        #[template_child]
+        pub crop_area: TemplateChild<DrawingArea>,
+        #[template_child]
+        pub page_strip: TemplateChild<gtk::Box>,
+        /// The page that is shown on the display
+        pub page: RefCell<Option<PdfPage>>,
+        pub page_count: Cell<u32>,
+        /// The buttons in the page strip together with the picture of their page
+        pub page_buttons: RefCell<Vec<(Button, Picture)>>,
+        /// The crop that is being dragged, in fractions of the page size
+        pub selection: Cell<Option<[f64; 4]>>,
+        pub drag_start: Cell<Option<(f64, f64)>>,
+        /// Receives the page thumbnails of the pdf, closed when another pdf is shown
+        pub thumbnail_receiver: RefCell<Option<async_channel::Receiver<(u32, Vec<u8>)>>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdPdfControls {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdPdfControls";
+        type Type = super::DdPdfControls;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdPdfControls {
+        #[template_callback]
+        fn handle_previous(&self, _: Button) {
+            self.obj().step(-1);
+        }
+
+        #[template_callback]
+        fn handle_next(&self, _: Button) {
+            self.obj().step(1);
+        }
+
+        #[template_callback]
+        fn handle_crop(&self, button: ToggleButton) {
+            self.crop_overlay.set_visible(button.is_active());
+            if button.is_active() {
+                self.obj().load_crop_preview();
+            }
+        }
+
+        #[template_callback]
+        fn handle_clear_crop(&self, _: Button) {
+            self.obj().set_crop(None);
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdPdfControls {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed")
+                        .param_types([PdfPage::static_type()])
+                        .build(),
+                    Signal::builder("error")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            self.obj().setup_crop_area();
+        }
+
+        fn dispose(&self) {
+            if let Some(receiver) = self.thumbnail_receiver.take() {
+                receiver.close();
+            }
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdPdfControls {}
+}
+
+glib::wrapper! {
+    pub struct DdPdfControls(ObjectSubclass<imp::DdPdfControls>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdPdfControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdPdfControls {
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    /// Show the first page of the pdf and the thumbnails of all its pages
+    pub fn set_document(&self, path: &str) -> Result<(), DragonDisplayError> {
+        let imp = self.imp();
+        let count = page_count(Path::new(path))?;
+        imp.page_count.set(count);
+        imp.page.replace(Some(PdfPage::new(path.to_string())));
+
+        while let Some(child) = imp.page_strip.first_child() {
+            imp.page_strip.remove(&child);
+        }
+        let mut buttons = Vec::new();
+        for page in 0..count {
+            let picture = Picture::builder()
+                .width_request(100)
+                .height_request(120)
+                .content_fit(gtk::ContentFit::Contain)
+                .build();
+            let content = gtk::Box::new(Orientation::Vertical, 3);
+            content.append(&picture);
+            content.append(&Label::new(Some(&(page + 1).to_string())));
+            let button = Button::builder().child(&content).build();
+            button.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.show_page(page);
+            }));
+            imp.page_strip.append(&button);
+            buttons.push((button, picture));
+        }
+        imp.page_buttons.replace(buttons);
+        self.load_page_thumbnails(PathBuf::from(path));
+        self.update_page();
+        Ok(())
+    }
+
+    /// Show the page on the display, the crop is removed
+    pub fn show_page(&self, page: u32) {
+        let imp = self.imp();
+        if page >= imp.page_count.get() {
+            return;
+        }
+        if let Some(pdf_page) = imp.page.borrow_mut().as_mut() {
+            pdf_page.page = page;
+            pdf_page.crop = None;
+        }
+        self.update_page();
+        self.emit_changed();
+    }
+
+    /// Go forward or back the amount of pages
+    fn step(&self, amount: i64) {
+        let page = self.imp().page.borrow().as_ref().map(|p| p.page);
+        if let Some(page) = page {
+            let page = page as i64 + amount;
+            if page >= 0 {
+                self.show_page(page as u32);
+            }
+        }
+    }
+
+    /// Update the page label, the buttons and the crop preview to the current page
+    fn update_page(&self) {
+        let imp = self.imp();
+        let page = match imp.page.borrow().as_ref() {
+            Some(p) => p.page,
+            None => return,
+        };
+        let count = imp.page_count.get();
+        imp.page_label
+            .set_text(&format!("Page {} of {}", page + 1, count));
+        imp.previous_button.set_sensitive(page > 0);
+        imp.next_button.set_sensitive(page + 1 < count);
+        imp.clear_crop_button.set_sensitive(false);
+        for (index, (button, _)) in imp.page_buttons.borrow().iter().enumerate() {
+            if index as u32 == page {
+                button.add_css_class("suggested-action");
+            } else {
+                button.remove_css_class("suggested-action");
+            }
+        }
+        imp.selection.set(None);
+        imp.crop_area.queue_draw();
+        if imp.crop_button.is_active() {
+            self.load_crop_preview();
+        }
+    }
+
+    /// Only show the region of the page on the display, None shows the whole page
+    fn set_crop(&self, crop: Option<[f64; 4]>) {
+        let imp = self.imp();
+        if let Some(pdf_page) = imp.page.borrow_mut().as_mut() {
+            pdf_page.crop = crop;
+        }
+        imp.clear_crop_button.set_sensitive(crop.is_some());
+        imp.crop_area.queue_draw();
+        self.emit_changed();
+    }
+
+    fn emit_changed(&self) {
+        let page = self.imp().page.borrow().clone();
+        if let Some(page) = page {
+            self.emit_by_name::<()>("changed", &[&page]);
+        }
+    }
+
+    /// Render the thumbnails of the pages in the background, a thumbnail is shown as soon as it
+    /// is rendered
+    fn load_page_thumbnails(&self, path: PathBuf) {
+        let (sender, receiver) = async_channel::bounded(1);
+        if let Some(old_receiver) = self
+            .imp()
+            .thumbnail_receiver
+            .replace(Some(receiver.clone()))
+        {
+            old_receiver.close();
+        }
+        runtime().spawn_blocking(move || send_page_thumbnails(&path, sender));
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            while let Ok((page, data)) = receiver.recv().await {
+                let texture = match Texture::from_bytes(&glib::Bytes::from_owned(data)) {
+                    Ok(t) => t,
+                    Err(_) => continue,
+                };
+                if let Some((_, picture)) = obj.imp().page_buttons.borrow().get(page as usize) {
+                    picture.set_paintable(Some(&texture));
+                }
+            }
+        }));
+    }
+
+    /// Render the current page in the background to select a crop on
+    fn load_crop_preview(&self) {
+        let page = match self.imp().page.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            let path = PathBuf::from(&page.path);
+            let index = page.page;
+            let result = gio::spawn_blocking(move || {
+                render_page(&path, index, None, CROP_PREVIEW_SIZE).and_then(|pixbuf| {
+                    pixbuf.save_to_bufferv("png", &[]).context(GlibSnafu {
+                        msg: "Could not encode the page preview".to_string(),
+                    })
+                })
+            })
+            .await;
+            // another page could have been selected while rendering
+            if obj.imp().page.borrow().as_ref().map(|p| (&p.path, p.page)) != Some((&page.path, index)) {
+                return;
+            }
+            let data = match result {
+                Ok(Ok(data)) => data,
+                Ok(Err(err)) => {
+                    obj.emit_error(err, false);
+                    return;
+                }
+                Err(_) => return,
+            };
+            if let Ok(texture) = Texture::from_bytes(&glib::Bytes::from_owned(data)) {
+                obj.imp().crop_picture.set_paintable(Some(&texture));
+                obj.imp().crop_area.queue_draw();
+            }
+        }));
+    }
+
+    /// Let the user drag a rectangle over the page preview to select the crop
+    fn setup_crop_area(&self) {
+        let imp = self.imp();
+        imp.crop_area
+            .set_draw_func(clone!(@weak self as obj => move |_, context, _, _| {
+                obj.draw_crop(context);
+            }));
+        let gesture = gtk::GestureDrag::new();
+        gesture.connect_drag_begin(clone!(@weak self as obj => move |_, x, y| {
+            obj.imp().drag_start.set(Some((x, y)));
+        }));
+        gesture.connect_drag_update(clone!(@weak self as obj => move |_, dx, dy| {
+            let selection = obj.selection(dx, dy);
+            obj.imp().selection.set(selection);
+            obj.imp().crop_area.queue_draw();
+        }));
+        gesture.connect_drag_end(clone!(@weak self as obj => move |_, dx, dy| {
+            let selection = obj.selection(dx, dy);
+            obj.imp().selection.set(None);
+            obj.imp().drag_start.set(None);
+            match selection {
+                Some(crop) if crop[2] > MIN_CROP_SIZE && crop[3] > MIN_CROP_SIZE => {
+                    obj.set_crop(Some(crop))
+                }
+                _ => obj.imp().crop_area.queue_draw(),
+            }
+        }));
+        imp.crop_area.add_controller(gesture);
+    }
+
+    /// The dragged rectangle in fractions of the page size
+    fn selection(&self, dx: f64, dy: f64) -> Option<[f64; 4]> {
+        let (start_x, start_y) = self.imp().drag_start.get()?;
+        let (x, y, width, height) = self.page_rect()?;
+        let to_page = |px: f64, py: f64| {
+            (
+                ((px - x) / width).clamp(0.0, 1.0),
+                ((py - y) / height).clamp(0.0, 1.0),
+            )
+        };
+        let (x0, y0) = to_page(start_x, start_y);
+        let (x1, y1) = to_page(start_x + dx, start_y + dy);
+        Some([x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs()])
+    }
+
+    /// The region of the crop area where the page is drawn as (x, y, width, height)
+    fn page_rect(&self) -> Option<(f64, f64, f64, f64)> {
+        let imp = self.imp();
+        let paintable = imp.crop_picture.paintable()?;
+        let page_width = paintable.intrinsic_width() as f64;
+        let page_height = paintable.intrinsic_height() as f64;
+        let width = imp.crop_area.width() as f64;
+        let height = imp.crop_area.height() as f64;
+        if page_width <= 0.0 || page_height <= 0.0 {
+            return None;
+        }
+        let scale = (width / page_width).min(height / page_height);
+        let (shown_width, shown_height) = (page_width * scale, page_height * scale);
+        Some((
+            (width - shown_width) / 2.0,
+            (height - shown_height) / 2.0,
+            shown_width,
+            shown_height,
+        ))
+    }
+
+    /// Darken the part of the page outside of the crop
+    fn draw_crop(&self, context: &gtk::cairo::Context) {
+        let imp = self.imp();
+        let crop = imp
+            .selection
+            .get()
+            .or_else(|| imp.page.borrow().as_ref().and_then(|p| p.crop));
+        let (Some([cx, cy, cw, ch]), Some((x, y, width, height))) = (crop, self.page_rect()) else {
+            return;
+        };
+        let crop_rect = (x + cx * width, y + cy * height, cw * width, ch * height);
+        context.rectangle(x, y, width, height);
+        context.rectangle(crop_rect.0, crop_rect.1, crop_rect.2, crop_rect.3);
+        context.set_fill_rule(gtk::cairo::FillRule::EvenOdd);
+        context.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+        let _ = context.fill();
+        context.rectangle(crop_rect.0, crop_rect.1, crop_rect.2, crop_rect.3);
+        context.set_source_rgb(1.0, 0.0, 0.0);
+        context.set_line_width(2.0);
+        let _ = context.stroke();
+    }
+
+    /// Returns true if the controls belong to the pdf at the path
+    pub fn is_showing(&self, path: &str) -> bool {
+        self.imp()
+            .page
+            .borrow()
+            .as_ref()
+            .is_some_and(|p| p.path == path)
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when another page or crop should be shown on the display
+    pub fn connect_changed<F: Fn(&Self, PdfPage) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|controls, page| {
+                f(controls, page);
+            }),
+        )
+    }
+
+    /// Emit an error message based on the input error
+    pub fn emit_error(&self, err: DragonDisplayError, fatal: bool) {
+        let msg = Report::from_error(err).to_string();
+        self.emit_by_name::<()>("error", &[&msg, &fatal]);
+    }
+
+    /// Signal emitted when an error occurs
+    pub fn connect_error<F: Fn(&Self, String, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "error",
+            true,
+            glib::closure_local!(|controls, msg, fatal| {
+                f(controls, msg, fatal);
+            }),
+        )
+    }
+}
@@ -0,0 +1,660 @@
+use std::time::{Duration, Instant};
+
+use gtk::glib::clone;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, Button, Label, SpinButton, StringList};
+use gtk::{prelude::*, Orientation};
+use snafu::Report;
+
+use crate::errors::DragonDisplayError;
+use crate::playlist::{
+    read_playlists, write_playlists, Playlist, PlaylistItem, MAX_ITEM_DURATION, MIN_ITEM_DURATION,
+};
+use crate::try_emit;
+
+mod imp {
+    use std::cell::{Cell, OnceCell, RefCell};
+    use std::sync::OnceLock;
+    use std::time::{Duration, Instant};
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CheckButton, CompositeTemplate, DropDown, Entry, ListBox,
+    };
+
+    use crate::playlist::Playlist;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/playlist_panel.ui")]
+    pub struct DdPlaylistPanel {
+        #[template_child]
+        pub playlist_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub delete_button: TemplateChild<Button>,
+        #[template_child]
+        pub item_list: TemplateChild<ListBox>,
+        #[template_child]
+        pub add_button: TemplateChild<Button>,
+        #[template_child]
+        pub loop_check: TemplateChild<CheckButton>,
+        #[template_child]
+        pub play_button: TemplateChild<Button>,
+        pub campaign_path: OnceCell<String>,
+        pub playlists: RefCell<Vec<Playlist>>,
+        /// The media that was last shown from the thumbnail grids
+        pub shown_media: RefCell<Option<String>>,
+        /// The index of the item of the selected playlist that is currently shown
+        pub position: Cell<Option<usize>>,
+        pub playing: Cell<bool>,
+        pub timer: RefCell<Option<glib::SourceId>>,
+        pub timer_started: Cell<Option<Instant>>,
+        /// The time left before the current image advances
+        pub remaining: Cell<Option<Duration>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdPlaylistPanel {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdPlaylistPanel";
+        type Type = super::DdPlaylistPanel;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdPlaylistPanel {
+        #[template_callback]
+        fn handle_new(&self, _: Button) {
+            self.obj().new_playlist();
+        }
+
+        #[template_callback]
+        fn handle_delete(&self, _: Button) {
+            self.obj().delete_playlist();
+        }
+
+        #[template_callback]
+        fn handle_add(&self, _: Button) {
+            self.obj().add_shown_media();
+        }
+
+        #[template_callback]
+        fn handle_loop(&self, check: CheckButton) {
+            self.obj().set_looping(check.is_active());
+        }
+
+        #[template_callback]
+        fn handle_previous(&self, _: Button) {
+            self.obj().previous();
+        }
+
+        #[template_callback]
+        fn handle_play(&self, _: Button) {
+            if self.playing.get() {
+                self.obj().pause();
+            } else {
+                self.obj().play();
+            }
+        }
+
+        #[template_callback]
+        fn handle_next(&self, _: Button) {
+            self.obj().next();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdPlaylistPanel {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("image")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("video")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("paused")
+                        .param_types([bool::static_type()])
+                        .build(),
+                    Signal::builder("error")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+        }
+
+        fn dispose(&self) {
+            if let Some(timer) = self.timer.take() {
+                timer.remove();
+            }
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdPlaylistPanel {}
+}
+
+glib::wrapper! {
+    pub struct DdPlaylistPanel(ObjectSubclass<imp::DdPlaylistPanel>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl DdPlaylistPanel {
+    /// Create a new playlist panel showing the playlists saved in the campaign at campaign_path
+    pub fn new(campaign_path: String) -> Result<Self, DragonDisplayError> {
+        let object = glib::Object::new::<Self>();
+        let playlists = read_playlists(&campaign_path)?;
+        object.imp().playlists.replace(playlists);
+        object
+            .imp()
+            .campaign_path
+            .set(campaign_path)
+            .expect("Expected campaign path to not be set");
+
+        object
+            .imp()
+            .playlist_dropdown
+            .connect_selected_notify(clone!(@weak object => move |_| {
+                object.playlist_changed();
+            }));
+        object.update_dropdown(Some(0));
+        Ok(object)
+    }
+
+    /// Set the media that was last shown from the thumbnail grids, this media can be added to
+    /// the selected playlist. The playlist is stopped, so it does not replace the media
+    pub fn set_shown_media(&self, path: String) {
+        self.stop();
+        self.imp().shown_media.replace(Some(path));
+        self.imp()
+            .add_button
+            .set_sensitive(self.selected_index().is_some());
+    }
+
+    /// Advance the playlist if the video that is currently shown was played by the playlist
+    pub fn video_finished(&self) {
+        if !self.imp().playing.get() {
+            return;
+        }
+        if self.current_item().is_some_and(|i| i.is_video()) {
+            self.next();
+        }
+    }
+
+    /// Start playing the selected playlist or resume it if it was paused
+    pub fn play(&self) {
+        let length = match self.selected_playlist() {
+            Some(p) => p.items.len(),
+            None => return,
+        };
+        if length == 0 {
+            return;
+        }
+        let imp = self.imp();
+        imp.playing.set(true);
+        imp.play_button.set_label("Pause");
+        let item = match self.current_item() {
+            Some(item) => item,
+            None => {
+                self.show_item(0);
+                return;
+            }
+        };
+        if item.is_video() {
+            self.emit_by_name::<()>("paused", &[&false]);
+        } else {
+            let remaining = imp.remaining.take().unwrap_or(item.duration());
+            self.start_timer(remaining);
+        }
+    }
+
+    /// Pause the playlist, the current item keeps being displayed
+    pub fn pause(&self) {
+        let imp = self.imp();
+        imp.playing.set(false);
+        imp.play_button.set_label("Play");
+        if let Some(timer) = imp.timer.take() {
+            timer.remove();
+            let elapsed = imp
+                .timer_started
+                .take()
+                .map(|s| s.elapsed())
+                .unwrap_or_default();
+            let remaining = imp.remaining.get().map(|r| r.saturating_sub(elapsed));
+            imp.remaining.set(remaining);
+        }
+        if self.current_item().is_some_and(|i| i.is_video()) {
+            self.emit_by_name::<()>("paused", &[&true]);
+        }
+    }
+
+    /// Show the next item of the playlist. Stops the playlist after the last item unless the
+    /// playlist loops
+    pub fn next(&self) {
+        let (length, looping) = match self.selected_playlist() {
+            Some(p) => (p.items.len(), p.looping),
+            None => return,
+        };
+        let next = self.imp().position.get().map_or(0, |p| p + 1);
+        if next < length {
+            self.show_item(next);
+        } else if looping && length > 0 {
+            self.show_item(0);
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Show the previous item of the playlist
+    pub fn previous(&self) {
+        let length = match self.selected_playlist() {
+            Some(p) => p.items.len(),
+            None => return,
+        };
+        if length == 0 {
+            return;
+        }
+        let previous = self.imp().position.get().map_or(0, |p| p.saturating_sub(1));
+        self.show_item(previous);
+    }
+
+    /// Stop the playlist and forget the current position
+    pub fn stop(&self) {
+        let imp = self.imp();
+        self.stop_timer();
+        imp.playing.set(false);
+        imp.position.set(None);
+        imp.play_button.set_label("Play");
+        imp.item_list.unselect_all();
+    }
+
+    /// Display the item at index of the selected playlist
+    fn show_item(&self, index: usize) {
+        let item = match self
+            .selected_playlist()
+            .and_then(|p| p.items.get(index).cloned())
+        {
+            Some(item) => item,
+            None => return,
+        };
+        let imp = self.imp();
+        self.stop_timer();
+        imp.position.set(Some(index));
+        if let Some(row) = imp.item_list.row_at_index(index as i32) {
+            imp.item_list.select_row(Some(&row));
+        }
+        if item.is_video() {
+            self.emit_by_name::<()>("video", &[&item.path]);
+            // a paused playlist shows the first frame of the video until it is resumed
+            if !imp.playing.get() {
+                self.emit_by_name::<()>("paused", &[&true]);
+            }
+        } else {
+            self.emit_by_name::<()>("image", &[&item.path]);
+            if imp.playing.get() {
+                self.start_timer(item.duration());
+            }
+        }
+    }
+
+    /// Advance to the next item after the given duration
+    fn start_timer(&self, duration: Duration) {
+        let imp = self.imp();
+        imp.remaining.set(Some(duration));
+        imp.timer_started.set(Some(Instant::now()));
+        let timer = glib::timeout_add_local_once(
+            duration,
+            clone!(@weak self as obj => move || {
+                obj.imp().timer.replace(None);
+                obj.next();
+            }),
+        );
+        if let Some(old_timer) = imp.timer.replace(Some(timer)) {
+            old_timer.remove();
+        }
+    }
+
+    fn stop_timer(&self) {
+        let imp = self.imp();
+        if let Some(timer) = imp.timer.take() {
+            timer.remove();
+        }
+        imp.timer_started.set(None);
+        imp.remaining.set(None);
+    }
+
+    /// Create a new playlist with the name in the name entry
+    fn new_playlist(&self) {
+        let imp = self.imp();
+        let name = imp.name_entry.text().trim().to_string();
+        if name.is_empty() {
+            let msg = "A playlist needs a name".to_string();
+            self.emit_error(DragonDisplayError::InvalidName { msg }, false);
+            return;
+        }
+        if imp.playlists.borrow().iter().any(|p| p.name == name) {
+            let msg = format!("A playlist with the name {} already exists", name);
+            self.emit_error(DragonDisplayError::InvalidName { msg }, false);
+            return;
+        }
+        imp.playlists.borrow_mut().push(Playlist::new(name));
+        imp.name_entry.set_text("");
+        self.save();
+        let last = imp.playlists.borrow().len() - 1;
+        self.update_dropdown(Some(last));
+    }
+
+    /// Remove the selected playlist
+    fn delete_playlist(&self) {
+        let index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        self.stop();
+        self.imp().playlists.borrow_mut().remove(index);
+        self.save();
+        self.update_dropdown(Some(index.saturating_sub(1)));
+    }
+
+    /// Add the media that was last shown to the end of the selected playlist
+    fn add_shown_media(&self) {
+        let path = match self.imp().shown_media.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+        let index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        self.imp().playlists.borrow_mut()[index]
+            .items
+            .push(PlaylistItem::new(path));
+        self.save();
+        self.rebuild_items();
+    }
+
+    fn set_looping(&self, looping: bool) {
+        let index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        let mut playlists = self.imp().playlists.borrow_mut();
+        if playlists[index].looping == looping {
+            return;
+        }
+        playlists[index].looping = looping;
+        drop(playlists);
+        self.save();
+    }
+
+    /// Update the panel to show the newly selected playlist
+    fn playlist_changed(&self) {
+        let imp = self.imp();
+        self.stop();
+        let looping = self.selected_playlist().is_some_and(|p| p.looping);
+        imp.loop_check.set_active(looping);
+        let selected = self.selected_index().is_some();
+        imp.delete_button.set_sensitive(selected);
+        imp.add_button
+            .set_sensitive(selected && imp.shown_media.borrow().is_some());
+        self.rebuild_items();
+    }
+
+    /// Fill the dropdown with the names of all playlists and select the playlist at index
+    fn update_dropdown(&self, select: Option<usize>) {
+        let names: Vec<String> = self
+            .imp()
+            .playlists
+            .borrow()
+            .iter()
+            .map(|p| p.name.clone())
+            .collect();
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        let model = StringList::new(&names);
+        let dropdown = &self.imp().playlist_dropdown;
+        dropdown.set_model(Some(&model));
+        if let Some(index) = select {
+            if index < names.len() {
+                dropdown.set_selected(index as u32);
+            }
+        }
+        self.playlist_changed();
+    }
+
+    /// Recreate the rows of the item list for the selected playlist
+    fn rebuild_items(&self) {
+        let item_list = &self.imp().item_list;
+        while let Some(row) = item_list.first_child() {
+            item_list.remove(&row);
+        }
+        let playlist = match self.selected_playlist() {
+            Some(p) => p,
+            None => return,
+        };
+        for (index, item) in playlist.items.iter().enumerate() {
+            let row = gtk::Box::builder()
+                .orientation(Orientation::Horizontal)
+                .spacing(3)
+                .build();
+            let label = Label::builder()
+                .label(item.file_name())
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::Middle)
+                .build();
+            row.append(&label);
+            if item.is_video() {
+                row.append(&Label::new(Some("Until the end")));
+            } else {
+                let duration = SpinButton::with_range(MIN_ITEM_DURATION, MAX_ITEM_DURATION, 1.0);
+                duration.set_value(item.duration as f64);
+                duration.set_tooltip_text(Some("Seconds to show the image"));
+                duration.connect_value_changed(clone!(@weak self as obj => move |spin| {
+                    obj.set_item_duration(index, spin.value() as u32);
+                }));
+                row.append(&duration);
+            }
+            let up = Button::with_label("Up");
+            up.set_sensitive(index > 0);
+            up.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.move_item(index, index - 1);
+            }));
+            row.append(&up);
+            let down = Button::with_label("Down");
+            down.set_sensitive(index + 1 < playlist.items.len());
+            down.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.move_item(index, index + 1);
+            }));
+            row.append(&down);
+            let remove = Button::with_label("Remove");
+            remove.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.remove_item(index);
+            }));
+            row.append(&remove);
+            item_list.append(&row);
+        }
+        if let Some(position) = self.imp().position.get() {
+            if let Some(row) = item_list.row_at_index(position as i32) {
+                item_list.select_row(Some(&row));
+            }
+        }
+    }
+
+    fn set_item_duration(&self, index: usize, duration: u32) {
+        let playlist_index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        if let Some(item) = self.imp().playlists.borrow_mut()[playlist_index]
+            .items
+            .get_mut(index)
+        {
+            item.duration = duration;
+        }
+        self.save();
+    }
+
+    fn move_item(&self, from: usize, to: usize) {
+        let playlist_index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        {
+            let mut playlists = self.imp().playlists.borrow_mut();
+            let items = &mut playlists[playlist_index].items;
+            if from >= items.len() || to >= items.len() {
+                return;
+            }
+            items.swap(from, to);
+        }
+        self.save();
+        self.rebuild_items();
+    }
+
+    fn remove_item(&self, index: usize) {
+        let playlist_index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        {
+            let mut playlists = self.imp().playlists.borrow_mut();
+            let items = &mut playlists[playlist_index].items;
+            if index >= items.len() {
+                return;
+            }
+            items.remove(index);
+        }
+        if self.imp().position.get().is_some_and(|p| p >= index) {
+            self.stop();
+        }
+        self.save();
+        self.rebuild_items();
+    }
+
+    /// Write the playlists to the campaign data folder
+    fn save(&self) {
+        let campaign_path = self
+            .imp()
+            .campaign_path
+            .get()
+            .expect("Expected a campaign path");
+        try_emit!(
+            self,
+            write_playlists(campaign_path, &self.imp().playlists.borrow()),
+            false
+        );
+    }
+
+    /// The index of the selected playlist, None if there are no playlists
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.imp().playlist_dropdown.selected();
+        if selected == gtk::INVALID_LIST_POSITION {
+            return None;
+        }
+        let selected = selected as usize;
+        match selected < self.imp().playlists.borrow().len() {
+            true => Some(selected),
+            false => None,
+        }
+    }
+
+    /// A cloned version of the selected playlist
+    fn selected_playlist(&self) -> Option<Playlist> {
+        let index = self.selected_index()?;
+        self.imp().playlists.borrow().get(index).cloned()
+    }
+
+    /// A cloned version of the item that is currently shown
+    fn current_item(&self) -> Option<PlaylistItem> {
+        let position = self.imp().position.get()?;
+        self.selected_playlist()?.items.get(position).cloned()
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the playlist shows an image
+    pub fn connect_image<F: Fn(&Self, String) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "image",
+            true,
+            glib::closure_local!(|panel, path| {
+                f(panel, path);
+            }),
+        )
+    }
+
+    /// Signal emitted when the playlist shows a video
+    pub fn connect_video<F: Fn(&Self, String) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "video",
+            true,
+            glib::closure_local!(|panel, path| {
+                f(panel, path);
+            }),
+        )
+    }
+
+    /// Signal emitted when the playlist is paused (true) or resumed (false) while showing a video
+    pub fn connect_paused<F: Fn(&Self, bool) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "paused",
+            true,
+            glib::closure_local!(|panel, paused| {
+                f(panel, paused);
+            }),
+        )
+    }
+
+    /// Emit an error message based on the input error
+    pub fn emit_error(&self, err: DragonDisplayError, fatal: bool) {
+        let msg = Report::from_error(err).to_string();
+        self.emit_by_name::<()>("error", &[&msg, &fatal]);
+    }
+
+    /// Signal emitted when an error occurs
+    pub fn connect_error<F: Fn(&Self, String, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "error",
+            true,
+            glib::closure_local!(|panel, msg, fatal| {
+                f(panel, msg, fatal);
+            }),
+        )
+    }
+}
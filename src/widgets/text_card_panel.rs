@@ -0,0 +1,315 @@
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, StringList};
+use snafu::Report;
+
+use crate::errors::DragonDisplayError;
+use crate::text_card::{read_text_cards, write_text_cards, CardLayout, TextCard};
+use crate::try_emit;
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CompositeTemplate, DropDown, Entry, StringList, TextView,
+    };
+
+    use crate::text_card::{CardLayout, TextCard};
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/text_card_panel.ui")]
+    pub struct DdTextCardPanel {
+        #[template_child]
+        pub card_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub delete_button: TemplateChild<Button>,
+        #[template_child]
+        pub title_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub text_view: TemplateChild<TextView>,
+        #[template_child]
+        pub layout_dropdown: TemplateChild<DropDown>,
+        pub campaign_path: OnceCell<String>,
+        pub cards: RefCell<Vec<TextCard>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdTextCardPanel {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdTextCardPanel";
+        type Type = super::DdTextCardPanel;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdTextCardPanel {
+        #[template_callback]
+        fn handle_save(&self, _: Button) {
+            self.obj().save_card();
+        }
+
+        #[template_callback]
+        fn handle_delete(&self, _: Button) {
+            self.obj().delete_card();
+        }
+
+        #[template_callback]
+        fn handle_show(&self, _: Button) {
+            let card = self.obj().edited_card();
+            self.obj().emit_by_name::<()>("show", &[&card]);
+        }
+
+        #[template_callback]
+        fn handle_hide(&self, _: Button) {
+            self.obj().emit_by_name::<()>("hide", &[]);
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdTextCardPanel {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("show")
+                        .param_types([TextCard::static_type()])
+                        .build(),
+                    Signal::builder("hide").build(),
+                    Signal::builder("error")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            let names: Vec<&str> = CardLayout::ALL.iter().map(|l| l.name()).collect();
+            self.layout_dropdown
+                .set_model(Some(&StringList::new(&names)));
+        }
+
+        fn dispose(&self) {
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdTextCardPanel {}
+}
+
+glib::wrapper! {
+    pub struct DdTextCardPanel(ObjectSubclass<imp::DdTextCardPanel>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl DdTextCardPanel {
+    /// Create a new text card panel showing the text cards saved in the campaign at
+    /// campaign_path
+    pub fn new(campaign_path: String) -> Result<Self, DragonDisplayError> {
+        let object = glib::Object::new::<Self>();
+        let cards = read_text_cards(&campaign_path)?;
+        object.imp().cards.replace(cards);
+        object
+            .imp()
+            .campaign_path
+            .set(campaign_path)
+            .expect("Expected campaign path to not be set");
+
+        object
+            .imp()
+            .card_dropdown
+            .connect_selected_notify(clone!(@weak object => move |_| {
+                object.card_changed();
+            }));
+        object.update_dropdown(Some(0));
+        Ok(object)
+    }
+
+    /// The text card as it is filled in in the panel
+    fn edited_card(&self) -> TextCard {
+        let imp = self.imp();
+        let buffer = imp.text_view.buffer();
+        let (start, end) = buffer.bounds();
+        TextCard {
+            name: imp.name_entry.text().trim().to_string(),
+            title: imp.title_entry.text().trim().to_string(),
+            text: buffer.text(&start, &end, false).trim().to_string(),
+            layout: CardLayout::from_index(imp.layout_dropdown.selected()),
+        }
+    }
+
+    /// Save the card that is filled in under the name in the name entry. A saved card with the
+    /// same name is overwritten
+    fn save_card(&self) {
+        let card = self.edited_card();
+        if card.name.is_empty() {
+            let msg = "A text card needs a name".to_string();
+            self.emit_error(DragonDisplayError::InvalidName { msg }, false);
+            return;
+        }
+        let index = {
+            let mut cards = self.imp().cards.borrow_mut();
+            match cards.iter().position(|c| c.name == card.name) {
+                Some(index) => {
+                    cards[index] = card;
+                    index
+                }
+                None => {
+                    cards.push(card);
+                    cards.len() - 1
+                }
+            }
+        };
+        self.save();
+        self.update_dropdown(Some(index));
+    }
+
+    /// Remove the selected card
+    fn delete_card(&self) {
+        let index = match self.selected_index() {
+            Some(i) => i,
+            None => return,
+        };
+        self.imp().cards.borrow_mut().remove(index);
+        self.save();
+        self.update_dropdown(Some(index.saturating_sub(1)));
+    }
+
+    /// Fill the editor with the newly selected card
+    fn card_changed(&self) {
+        let imp = self.imp();
+        let index = self.selected_index();
+        imp.delete_button.set_sensitive(index.is_some());
+        let card = match index.and_then(|i| imp.cards.borrow().get(i).cloned()) {
+            Some(card) => card,
+            None => return,
+        };
+        imp.name_entry.set_text(&card.name);
+        imp.title_entry.set_text(&card.title);
+        imp.text_view.buffer().set_text(&card.text);
+        imp.layout_dropdown.set_selected(card.layout.index());
+    }
+
+    /// Fill the dropdown with the names of all cards and select the card at index
+    fn update_dropdown(&self, select: Option<usize>) {
+        let names: Vec<String> = self
+            .imp()
+            .cards
+            .borrow()
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        let model = StringList::new(&names);
+        let dropdown = &self.imp().card_dropdown;
+        dropdown.set_model(Some(&model));
+        if let Some(index) = select {
+            if index < names.len() {
+                dropdown.set_selected(index as u32);
+            }
+        }
+        self.card_changed();
+    }
+
+    /// Write the text cards to the campaign data folder
+    fn save(&self) {
+        let campaign_path = self
+            .imp()
+            .campaign_path
+            .get()
+            .expect("Expected a campaign path");
+        try_emit!(
+            self,
+            write_text_cards(campaign_path, &self.imp().cards.borrow()),
+            false
+        );
+    }
+
+    /// The index of the selected card, None if there are no cards
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.imp().card_dropdown.selected();
+        if selected == gtk::INVALID_LIST_POSITION {
+            return None;
+        }
+        let selected = selected as usize;
+        match selected < self.imp().cards.borrow().len() {
+            true => Some(selected),
+            false => None,
+        }
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the text card should be shown on the display
+    pub fn connect_show<F: Fn(&Self, TextCard) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "show",
+            true,
+            glib::closure_local!(|panel, card| {
+                f(panel, card);
+            }),
+        )
+    }
+
+    /// Signal emitted when the text card on the display should be hidden
+    pub fn connect_hide<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "hide",
+            true,
+            glib::closure_local!(|panel| {
+                f(panel);
+            }),
+        )
+    }
+
+    /// Emit an error message based on the input error
+    pub fn emit_error(&self, err: DragonDisplayError, fatal: bool) {
+        let msg = Report::from_error(err).to_string();
+        self.emit_by_name::<()>("error", &[&msg, &fatal]);
+    }
+
+    /// Signal emitted when an error occurs
+    pub fn connect_error<F: Fn(&Self, String, bool) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "error",
+            true,
+            glib::closure_local!(|panel, msg, fatal| {
+                f(panel, msg, fatal);
+            }),
+        )
+    }
+}
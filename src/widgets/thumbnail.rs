@@ -1,7 +1,7 @@
 use gdk4::Texture;
 use gtk::{
-    gio::File,
-    glib::{self},
+    gio,
+    glib::{self, clone},
     subclass::prelude::{ObjectSubclass, ObjectSubclassIsExt},
 };
 use gtk::{prelude::*, ToggleButton};
@@ -9,17 +9,28 @@ use std::path::PathBuf;
 
 use gtk::subclass::prelude::*;
 
+use crate::metadata::MediaMetadata;
+use crate::pdf::page_thumbnail;
+use crate::thumbnail_cache::image_thumbnail;
+use crate::video_thumbnail::{format_duration, video_offset, video_poster, VideoPreview};
+use crate::APP_ID;
+
+#[derive(Clone, Copy, Default, PartialEq)]
 pub enum MediaType {
+    #[default]
     Image,
     Video,
+    Pdf,
 }
 
 mod imp {
 
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::sync::OnceLock;
 
     use glib::subclass::InitializingObject;
-    use gtk::{CompositeTemplate, Label, Picture};
+    use gtk::glib::subclass::Signal;
+    use gtk::{CompositeTemplate, Label, Picture, Spinner};
 
     use super::*;
     // Object holding the campaign
@@ -30,7 +41,19 @@ mod imp {
         pub icon: TemplateChild<Picture>,
         #[template_child]
         pub label: TemplateChild<Label>,
+        #[template_child]
+        pub favorite: TemplateChild<Label>,
+        #[template_child]
+        pub spinner: TemplateChild<Spinner>,
+        #[template_child]
+        pub duration: TemplateChild<Label>,
         pub path: RefCell<Option<String>>,
+        pub media_type: Cell<MediaType>,
+        /// True when the thumbnail of the current file is loaded or being loaded
+        pub loaded: Cell<bool>,
+        /// The thumbnail that is shown again when the video preview stops
+        pub poster: RefCell<Option<Texture>>,
+        pub preview: RefCell<Option<VideoPreview>>,
     }
 
     // The central trait for subclassing a GObject
@@ -52,12 +75,39 @@ mod imp {
 
     // Trait shared by all GObjects
     impl ObjectImpl for DdThumbnail {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("context-menu").build()])
+        }
+
         fn constructed(&self) {
             self.parent_constructed();
+            // a right click opens the context menu instead of showing the media
+            let gesture = gtk::GestureClick::builder()
+                .button(gtk::gdk::BUTTON_SECONDARY)
+                .build();
+            gesture.connect_pressed(glib::clone!(@weak self as imp => move |gesture, _, _, _| {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                imp.obj().emit_by_name::<()>("context-menu", &[]);
+            }));
+            self.obj().add_controller(gesture);
+            // videos play a preview while the mouse is over the thumbnail
+            let motion = gtk::EventControllerMotion::new();
+            motion.connect_enter(glib::clone!(@weak self as imp => move |_, _, _| {
+                imp.obj().start_preview();
+            }));
+            motion.connect_leave(glib::clone!(@weak self as imp => move |_| {
+                imp.obj().stop_preview();
+            }));
+            self.obj().add_controller(motion);
+            // thumbnails are only loaded once their page is shown
+            self.obj()
+                .connect_map(|thumbnail| thumbnail.load_thumbnail());
+            self.obj()
+                .connect_unmap(|thumbnail| thumbnail.stop_preview());
         }
 
         fn dispose(&self) {
-            println!("Dispose called on button {}", self.label.text());
             while let Some(child) = self.obj().first_child() {
                 child.unparent();
             }
@@ -81,7 +131,16 @@ glib::wrapper! {
 impl DdThumbnail {
     pub fn new(path: &PathBuf, prev_button: Option<&ToggleButton>, t: &MediaType) -> Self {
         let object = glib::Object::new::<Self>();
-        let imp = object.imp();
+        object.imp().media_type.set(*t);
+        object.imp().icon.set_content_fit(gtk::ContentFit::Fill);
+        object.set_file(path);
+        object.set_group(prev_button);
+        object
+    }
+
+    /// Show the name of the file, the picture is loaded when the thumbnail is shown
+    fn set_file(&self, path: &PathBuf) {
+        let imp = self.imp();
         let file_name = path
             .file_name()
             .expect("Could not get filename of file")
@@ -92,22 +151,104 @@ impl DdThumbnail {
             .to_str()
             .expect("Path of file could not be obtained")
             .to_string();
-        match t {
-            MediaType::Image => {
-                let image = Texture::from_filename(&file_path).expect("Expected an image");
-                imp.icon.set_paintable(Some(&image));
-                imp.label.set_text(file_name);
+        imp.label.set_text(file_name);
+        imp.preview.replace(None);
+        imp.poster.replace(None);
+        imp.icon.set_paintable(gdk4::Paintable::NONE);
+        imp.duration.set_visible(false);
+        imp.spinner.stop();
+        imp.spinner.set_visible(false);
+        imp.path.replace(Some(file_path));
+        imp.loaded.set(false);
+        if self.is_mapped() {
+            self.load_thumbnail();
+        }
+    }
+
+    /// Load the thumbnail of the image, the poster frame of the video or the first page of the
+    /// pdf in a background thread, a spinner is shown meanwhile
+    fn load_thumbnail(&self) {
+        let imp = self.imp();
+        if imp.loaded.get() {
+            return;
+        }
+        imp.loaded.set(true);
+        imp.spinner.set_visible(true);
+        imp.spinner.start();
+        let path = self.file();
+        let media_type = imp.media_type.get();
+        let offset = video_offset();
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            let file = PathBuf::from(&path);
+            let result = gio::spawn_blocking(move || match media_type {
+                MediaType::Image => image_thumbnail(&file).map(|data| (data, None)),
+                MediaType::Video => video_poster(&file, offset).map(|p| (p.data, p.duration)),
+                MediaType::Pdf => page_thumbnail(&file, 0).map(|data| (data, None)),
+            })
+            .await;
+            // the thumbnail could have been updated with another file while loading
+            if obj.file() != path {
+                return;
             }
-            MediaType::Video => {
-                let markup = format!("<span font=\"30\">{}</span>", &file_name);
-                imp.label.set_label(&markup);
+            let imp = obj.imp();
+            imp.spinner.stop();
+            imp.spinner.set_visible(false);
+            let (data, duration) = match result {
+                Ok(Ok(thumbnail)) => thumbnail,
+                // the file could still be written to, in which case only the name is shown until
+                // the thumbnail is shown again
+                _ => {
+                    imp.loaded.set(false);
+                    return;
+                }
+            };
+            if let Some(duration) = duration {
+                imp.duration.set_text(&format_duration(duration));
+                imp.duration.set_visible(true);
             }
+            match Texture::from_bytes(&glib::Bytes::from_owned(data)) {
+                Ok(texture) => {
+                    // a preview that already started keeps showing until the mouse leaves
+                    if imp.preview.borrow().is_none() {
+                        imp.icon.set_paintable(Some(&texture));
+                    }
+                    imp.poster.replace(Some(texture));
+                }
+                Err(_) => imp.loaded.set(false),
+            }
+        }));
+    }
+
+    /// Play a preview of the video in place of the thumbnail if previews are enabled
+    fn start_preview(&self) {
+        let imp = self.imp();
+        if imp.media_type.get() != MediaType::Video || imp.preview.borrow().is_some() {
+            return;
         }
-        imp.icon.set_content_fit(gtk::ContentFit::Fill);
-        imp.path.replace(Some(file_path));
+        if !gio::Settings::new(APP_ID).boolean("video-hover-preview") {
+            return;
+        }
+        let file = PathBuf::from(self.file());
+        let preview = VideoPreview::start(
+            &file,
+            video_offset(),
+            clone!(@weak self as obj => move |texture| {
+                obj.imp().icon.set_paintable(Some(&texture));
+            }),
+        );
+        // a video that can not be previewed keeps showing its poster
+        if let Ok(preview) = preview {
+            imp.preview.replace(Some(preview));
+        }
+    }
 
-        object.set_group(prev_button);
-        object
+    /// Stop the preview of the video and show the poster again
+    fn stop_preview(&self) {
+        let imp = self.imp();
+        if imp.preview.take().is_none() {
+            return;
+        }
+        imp.icon.set_paintable(imp.poster.borrow().as_ref());
     }
 
     /// Returns the file path value linked to the button
@@ -116,21 +257,25 @@ impl DdThumbnail {
         binding.expect("filepath should be set")
     }
 
+    /// Show whether the file is a favorite, the tags and notes are shown as tooltip
+    pub fn set_metadata(&self, metadata: &MediaMetadata) {
+        self.imp().favorite.set_visible(metadata.favorite);
+        self.set_tooltip_text(metadata.description().as_deref());
+    }
+
+    /// Signal emitted when the thumbnail is right clicked
+    pub fn connect_context_menu<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "context-menu",
+            true,
+            glib::closure_local!(|thumbnail| {
+                f(thumbnail);
+            }),
+        )
+    }
+
     /// Update the thumbnail with a new file, updating the picture and the name
     pub fn update(&self, new_file: &PathBuf) {
-        let file_name = new_file
-            .file_name()
-            .expect("Could not get filename of file")
-            .to_str()
-            .expect("Could not obtain filename");
-        // the path.to_string was already checked
-        let file_path = new_file
-            .to_str()
-            .expect("Path of file could not be obtained")
-            .to_string();
-        let file = File::for_path(&file_path);
-        self.imp().icon.set_file(Some(&file));
-        self.imp().label.set_text(file_name);
-        self.imp().path.replace(Some(file_path));
+        self.set_file(new_file);
     }
 }
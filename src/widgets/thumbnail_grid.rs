@@ -1,9 +1,10 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 use crate::APP_ID;
 use gtk::glib::clone;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
-use gtk::{glib, Grid};
+use gtk::{gdk, glib, DragSource, DropTarget, Grid, WidgetPaintable};
 use gtk::{prelude::*, ToggleButton};
 
 use super::thumbnail::{DdThumbnail, MediaType};
@@ -11,13 +12,16 @@ use super::thumbnail::{DdThumbnail, MediaType};
 mod imp {
     use gtk::glib::subclass::Signal;
     use std::cell::{Cell, RefCell};
+    use std::collections::HashSet;
     use std::sync::OnceLock;
 
     use glib::subclass::InitializingObject;
     use gtk::subclass::prelude::*;
-    use gtk::{glib, template_callbacks, Box, CompositeTemplate, Grid, ToggleButton};
+    use gtk::{glib, template_callbacks, Box, CompositeTemplate, Grid, Label, ToggleButton};
     use gtk::{prelude::*, Button};
 
+    use crate::widgets::thumbnail::DdThumbnail;
+
     // Object holding the state
     #[derive(CompositeTemplate, Default)]
     #[template(resource = "/dragon/display/thumbnail_grid.ui")]
@@ -30,7 +34,13 @@ mod imp {
         pub next: TemplateChild<Button>,
         #[template_child]
         pub previous: TemplateChild<Button>,
+        #[template_child]
+        pub no_matches: TemplateChild<Label>,
         pub togglebuttons: RefCell<Vec<ToggleButton>>,
+        /// The files that are shown, all files are shown if there is no filter
+        pub filter: RefCell<Option<HashSet<String>>>,
+        /// The buttons that pass the filter, in the order they are placed in the grids
+        pub visible_buttons: RefCell<Vec<ToggleButton>>,
         pub current_grid_nr: Cell<usize>,
         pub page_vec: RefCell<Vec<Grid>>,
     }
@@ -99,9 +109,17 @@ mod imp {
         fn signals() -> &'static [glib::subclass::Signal] {
             static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
             SIGNALS.get_or_init(|| {
-                vec![Signal::builder("path")
-                    .param_types([String::static_type()])
-                    .build()]
+                vec![
+                    Signal::builder("path")
+                        .param_types([String::static_type()])
+                        .build(),
+                    Signal::builder("context-menu")
+                        .param_types([DdThumbnail::static_type()])
+                        .build(),
+                    Signal::builder("reorder")
+                        .param_types([String::static_type(), String::static_type()])
+                        .build(),
+                ]
             })
         }
 
@@ -135,9 +153,7 @@ impl DdThumbnailGrid {
         let mut prev_button: Option<ToggleButton> = None;
         for file in files {
             let thumbnail_image = DdThumbnail::new(&file, prev_button.as_ref(), t);
-            thumbnail_image.connect_clicked(clone!(@weak object => move |button| {
-                object.emit_by_name::<()>("path", &[&button.file()])
-            }));
+            object.connect_thumbnail(&thumbnail_image);
             object
                 .imp()
                 .togglebuttons
@@ -149,6 +165,46 @@ impl DdThumbnailGrid {
         object
     }
 
+    /// Forward the clicks and context menu requests of the thumbnail as signals of the grid
+    fn connect_thumbnail(&self, thumbnail: &DdThumbnail) {
+        thumbnail.connect_clicked(clone!(@weak self as obj => move |button| {
+            obj.emit_by_name::<()>("path", &[&button.file()])
+        }));
+        thumbnail.connect_context_menu(clone!(@weak self as obj => move |thumbnail| {
+            obj.emit_by_name::<()>("context-menu", &[thumbnail])
+        }));
+
+        // thumbnails can be dragged onto another thumbnail to take its position
+        let drag_source = DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+        drag_source.set_propagation_phase(gtk::PropagationPhase::Capture);
+        drag_source.connect_prepare(
+            clone!(@weak thumbnail => @default-return None, move |_, _, _| {
+                Some(gdk::ContentProvider::for_value(&thumbnail.file().to_value()))
+            }),
+        );
+        drag_source.connect_drag_begin(clone!(@weak thumbnail => move |source, _| {
+            source.set_icon(Some(&WidgetPaintable::new(Some(&thumbnail))), 0, 0);
+        }));
+        thumbnail.add_controller(drag_source);
+
+        let drop_target = DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+        drop_target.connect_drop(
+            clone!(@weak self as obj, @weak thumbnail => @default-return false, move |_, value, _, _| {
+                let file = match value.get::<String>() {
+                    Ok(f) => f,
+                    Err(_) => return false,
+                };
+                if file == thumbnail.file() {
+                    return false;
+                }
+                obj.emit_by_name::<()>("reorder", &[&file, &thumbnail.file()]);
+                true
+            }),
+        );
+        thumbnail.add_controller(drop_target);
+    }
+
     /// Given a vector of paths to media files this function updates the buttons in the current
     /// grid.
     pub fn update(&self, images: Vec<PathBuf>, t: &MediaType) {
@@ -177,6 +233,7 @@ impl DdThumbnailGrid {
 
         // no new images need to be added
         if new_images.len() == 0 {
+            Self::order_buttons(&mut keep, &image_strings);
             self.imp().togglebuttons.replace(keep);
             self.populate_grids();
             return;
@@ -186,6 +243,8 @@ impl DdThumbnailGrid {
         let mut i = 0;
         for button in replace {
             if let Some(new_path) = new_images.get(i) {
+                // the file of an active button was removed, so it should not be selected anymore
+                button.set_active(false);
                 button.update(new_path);
                 keep.push(button.upcast::<ToggleButton>());
                 i += 1;
@@ -195,24 +254,53 @@ impl DdThumbnailGrid {
         while let Some(new_path) = new_images.get(i) {
             let prev_button = keep.last();
             let thumbnail = DdThumbnail::new(new_path, prev_button, t);
-            thumbnail.connect_clicked(clone!(@weak self as obj => move |button| {
-                obj.emit_by_name::<()>("path", &[&button.file()])
-            }));
+            self.connect_thumbnail(&thumbnail);
             keep.push(thumbnail.upcast::<ToggleButton>());
             i += 1;
         }
+        Self::order_buttons(&mut keep, &image_strings);
         self.imp().togglebuttons.replace(keep);
         self.populate_grids();
     }
 
+    /// Sort the buttons in the order of the files
+    fn order_buttons(buttons: &mut [ToggleButton], files: &[String]) {
+        buttons.sort_by_cached_key(|b| {
+            b.downcast_ref::<DdThumbnail>()
+                .and_then(|t| files.iter().position(|f| *f == t.file()))
+        });
+    }
+
     /// Create an amount of grids for the given amount of storage needed and populate these grids
-    /// with the self.togglebuttons
+    /// with the self.togglebuttons. The page with the active button is shown if there is one
     pub fn populate_grids(&self) {
         if let Some(child) = self.imp().main_box.first_child() {
             if let Some(grid) = child.downcast_ref::<Grid>() {
                 self.imp().main_box.remove(grid);
             }
         }
+        // buttons that are kept are still attached to the old grids
+        for togglebutton in self.imp().togglebuttons.borrow().iter() {
+            if let Some(grid) = togglebutton.parent().and_downcast::<Grid>() {
+                grid.remove(togglebutton);
+            }
+        }
+        let visible_buttons: Vec<ToggleButton> = {
+            let filter = self.imp().filter.borrow();
+            self.imp()
+                .togglebuttons
+                .borrow()
+                .iter()
+                .filter(|b| match (&*filter, b.downcast_ref::<DdThumbnail>()) {
+                    (Some(files), Some(thumbnail)) => files.contains(&thumbnail.file()),
+                    _ => true,
+                })
+                .cloned()
+                .collect()
+        };
+        self.imp()
+            .no_matches
+            .set_visible(visible_buttons.is_empty() && self.imp().filter.borrow().is_some());
 
         let settings = gtk::gio::Settings::new(APP_ID);
         let mut column = settings.int("imagegrid-column-amount");
@@ -223,7 +311,7 @@ impl DdThumbnailGrid {
         if row <= 0 {
             row = 3;
         }
-        let total_files = self.imp().togglebuttons.borrow().len() as f64;
+        let total_files = visible_buttons.len() as f64;
         let files_per_page = (row * column) as f64;
         let grids_needed = (total_files / files_per_page).ceil() as usize;
         let mut new_grids = Vec::new();
@@ -240,23 +328,157 @@ impl DdThumbnailGrid {
         }
         self.imp().page_vec.replace(Vec::new());
         let mut i = 0;
-        for togglebutton in self.imp().togglebuttons.borrow().iter() {
+        for togglebutton in visible_buttons.iter() {
             let grid_nr = i / files_per_page as i32;
             let grid = new_grids.get(grid_nr as usize).expect("Expected a grid");
             grid.attach(togglebutton, i % column, (i / column) % row, 1, 1);
             i += 1;
         }
-        if new_grids.len() <= 1 {
+        let active = visible_buttons.iter().position(|b| b.is_active());
+        self.imp().visible_buttons.replace(visible_buttons);
+        if new_grids.is_empty() {
             self.imp().navigation_box.set_child_visible(false);
+            return;
         }
-        self.imp().previous.set_sensitive(false);
-        self.imp().current_grid_nr.replace(0);
+        let page = match active {
+            Some(index) => index / files_per_page as usize,
+            None => self.imp().current_grid_nr.get().min(new_grids.len() - 1),
+        };
+        self.imp()
+            .navigation_box
+            .set_child_visible(new_grids.len() > 1);
+        self.imp().previous.set_sensitive(page > 0);
+        self.imp().next.set_sensitive(page + 1 < new_grids.len());
+        self.imp().current_grid_nr.replace(page);
         self.imp()
             .main_box
-            .prepend(new_grids.get(0).expect("Expected a grid"));
+            .prepend(new_grids.get(page).expect("Expected a grid"));
         self.imp().page_vec.replace(new_grids);
     }
 
+    /// Only show the files in the set, or all files if the filter is None. The thumbnails are
+    /// kept, so changing the filter does not reload any media
+    pub fn set_filter(&self, files: Option<HashSet<String>>) {
+        if *self.imp().filter.borrow() == files {
+            return;
+        }
+        self.imp().filter.replace(files);
+        // the results of a new filter start at the first page
+        self.imp().current_grid_nr.replace(0);
+        self.populate_grids();
+    }
+
+    /// The files of all thumbnails, including the thumbnails that do not pass the filter
+    pub fn files(&self) -> Vec<String> {
+        self.imp()
+            .togglebuttons
+            .borrow()
+            .iter()
+            .filter_map(|b| b.downcast_ref::<DdThumbnail>())
+            .map(|b| b.file())
+            .collect()
+    }
+
+    /// All thumbnails, including the thumbnails that do not pass the filter
+    pub fn thumbnails(&self) -> Vec<DdThumbnail> {
+        self.imp()
+            .togglebuttons
+            .borrow()
+            .iter()
+            .filter_map(|b| b.downcast_ref::<DdThumbnail>().cloned())
+            .collect()
+    }
+
+    /// The amount of thumbnails that pass the filter
+    pub fn match_count(&self) -> usize {
+        self.imp().visible_buttons.borrow().len()
+    }
+
+    /// Click the first thumbnail that passes the filter, returns false if there is none
+    pub fn activate_first(&self) -> bool {
+        let first = self.imp().visible_buttons.borrow().first().cloned();
+        match first {
+            Some(button) => {
+                button.emit_clicked();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the keyboard focus to the next (or previous) thumbnail that passes the filter,
+    /// the page of the thumbnail is shown. Starts at the first thumbnail if none has the focus
+    pub fn focus_next(&self, forward: bool) {
+        let buttons = self.imp().visible_buttons.borrow().clone();
+        if buttons.is_empty() {
+            return;
+        }
+        let focused = buttons.iter().position(|b| b.has_focus());
+        let index = match (focused, forward) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1) % buttons.len(),
+            (Some(i), false) => i.checked_sub(1).unwrap_or(buttons.len() - 1),
+        };
+        self.show_page(index / self.files_per_page());
+        buttons[index].grab_focus();
+    }
+
+    fn files_per_page(&self) -> usize {
+        let settings = gtk::gio::Settings::new(APP_ID);
+        let column = settings.int("imagegrid-column-amount");
+        let row = settings.int("imagegrid-row-amount");
+        let column = if column <= 0 { 3 } else { column };
+        let row = if row <= 0 { 3 } else { row };
+        (column * row) as usize
+    }
+
+    /// Show the page with the given number instead of the current page
+    fn show_page(&self, page: usize) {
+        let page_vec = self.imp().page_vec.borrow();
+        let current_nr = self.imp().current_grid_nr.get();
+        let (current_page, new_page) = match (page_vec.get(current_nr), page_vec.get(page)) {
+            (Some(c), Some(n)) => (c, n),
+            _ => return,
+        };
+        if current_nr == page {
+            return;
+        }
+        self.imp().main_box.remove(current_page);
+        self.imp().main_box.prepend(new_page);
+        self.imp().current_grid_nr.replace(page);
+        self.imp().previous.set_sensitive(page > 0);
+        self.imp().next.set_sensitive(page + 1 < page_vec.len());
+    }
+
+    /// Signal emitted when a thumbnail is dropped on another thumbnail, with the file of the
+    /// dropped thumbnail and the file of the thumbnail it was dropped on
+    pub fn connect_reorder<F: Fn(&Self, String, String) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "reorder",
+            true,
+            glib::closure_local!(|window, file, target| {
+                f(window, file, target);
+            }),
+        )
+    }
+
+    /// Signal emitted when a thumbnail is right clicked
+    pub fn connect_context_menu<F: Fn(&Self, DdThumbnail) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "context-menu",
+            true,
+            glib::closure_local!(|window, thumbnail| {
+                f(window, thumbnail);
+            }),
+        )
+    }
+
     /// Signal emitted when an image is clicked
     pub fn connect_path<F: Fn(&Self, String) + 'static>(&self, f: F) -> glib::SignalHandlerId {
         self.connect_closure(
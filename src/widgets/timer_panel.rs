@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use gtk::glib::clone;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, Button, Label, StringList};
+use gtk::{prelude::*, Orientation};
+
+use crate::timer::{play_alarm, Timer, TimerPosition, TimerStyle, TimerUnit, Timers};
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{
+        glib, template_callbacks, Button, CheckButton, CompositeTemplate, DropDown, Entry, Label,
+        ListBox, SpinButton,
+    };
+
+    use crate::timer::{Timer, Timers};
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/timer_panel.ui")]
+    pub struct DdTimerPanel {
+        #[template_child]
+        pub name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub amount_spin: TemplateChild<SpinButton>,
+        #[template_child]
+        pub unit_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub position_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub style_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub flash_check: TemplateChild<CheckButton>,
+        #[template_child]
+        pub sound_check: TemplateChild<CheckButton>,
+        #[template_child]
+        pub timer_list: TemplateChild<ListBox>,
+        pub timers: RefCell<Vec<Timer>>,
+        /// The labels in the timer list that show the remaining time of every timer
+        pub labels: RefCell<Vec<Label>>,
+        /// The timer that counts down the wall-clock timers every second
+        pub ticker: RefCell<Option<glib::SourceId>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdTimerPanel {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdTimerPanel";
+        type Type = super::DdTimerPanel;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdTimerPanel {
+        #[template_callback]
+        fn handle_add(&self, _: Button) {
+            self.obj().add_timer();
+        }
+
+        #[template_callback]
+        fn handle_next_round(&self, _: Button) {
+            self.obj().next_round();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdTimerPanel {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("changed")
+                    .param_types([Timers::static_type()])
+                    .build()]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            self.obj().setup();
+        }
+
+        fn dispose(&self) {
+            if let Some(ticker) = self.ticker.take() {
+                ticker.remove();
+            }
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdTimerPanel {}
+}
+
+glib::wrapper! {
+    pub struct DdTimerPanel(ObjectSubclass<imp::DdTimerPanel>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdTimerPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdTimerPanel {
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    /// Count down the running round timers by one round
+    pub fn next_round(&self) {
+        self.count_down(true);
+    }
+
+    /// Fill the dropdowns and start counting down the wall-clock timers
+    fn setup(&self) {
+        let imp = self.imp();
+        let units: Vec<&str> = TimerUnit::ALL.iter().map(|u| u.name()).collect();
+        imp.unit_dropdown.set_model(Some(&StringList::new(&units)));
+        imp.unit_dropdown.set_selected(1);
+        let positions: Vec<&str> = TimerPosition::ALL.iter().map(|p| p.name()).collect();
+        imp.position_dropdown
+            .set_model(Some(&StringList::new(&positions)));
+        let styles: Vec<&str> = TimerStyle::ALL.iter().map(|s| s.name()).collect();
+        imp.style_dropdown
+            .set_model(Some(&StringList::new(&styles)));
+
+        let ticker = glib::timeout_add_local(
+            Duration::from_secs(1),
+            clone!(@weak self as obj => @default-return glib::ControlFlow::Break, move || {
+                obj.count_down(false);
+                glib::ControlFlow::Continue
+            }),
+        );
+        imp.ticker.replace(Some(ticker));
+    }
+
+    /// Add a timer with the settings that are filled in in the panel
+    fn add_timer(&self) {
+        let imp = self.imp();
+        let mut timer = Timer::new(
+            imp.name_entry.text().trim().to_string(),
+            imp.amount_spin.value() as u32,
+            TimerUnit::from_index(imp.unit_dropdown.selected()),
+        );
+        timer.position = TimerPosition::from_index(imp.position_dropdown.selected());
+        timer.style = TimerStyle::from_index(imp.style_dropdown.selected());
+        timer.flash = imp.flash_check.is_active();
+        timer.sound = imp.sound_check.is_active();
+        imp.name_entry.set_text("");
+        imp.timers.borrow_mut().push(timer);
+        self.rebuild_timers();
+        self.emit_changed();
+    }
+
+    /// Count down all running timers that count rounds or all running timers that count
+    /// seconds. An alarm sounds for the timers that expire
+    fn count_down(&self, rounds: bool) {
+        let mut changed = false;
+        let mut alarm = false;
+        for timer in self.imp().timers.borrow_mut().iter_mut() {
+            if timer.rounds != rounds || !timer.running || timer.is_expired() {
+                continue;
+            }
+            changed = true;
+            if timer.count_down() && timer.sound {
+                alarm = true;
+            }
+        }
+        if alarm {
+            play_alarm();
+        }
+        if changed {
+            self.update_labels();
+            self.emit_changed();
+        }
+    }
+
+    fn toggle_running(&self, index: usize) {
+        if let Some(timer) = self.imp().timers.borrow_mut().get_mut(index) {
+            timer.running = !timer.running;
+        }
+        self.rebuild_timers();
+        self.emit_changed();
+    }
+
+    fn remove_timer(&self, index: usize) {
+        {
+            let mut timers = self.imp().timers.borrow_mut();
+            if index >= timers.len() {
+                return;
+            }
+            timers.remove(index);
+        }
+        self.rebuild_timers();
+        self.emit_changed();
+    }
+
+    /// Recreate the rows of the timer list
+    fn rebuild_timers(&self) {
+        let imp = self.imp();
+        let timer_list = &imp.timer_list;
+        while let Some(row) = timer_list.first_child() {
+            timer_list.remove(&row);
+        }
+        let mut labels = Vec::new();
+        for (index, timer) in imp.timers.borrow().iter().enumerate() {
+            let row = gtk::Box::builder()
+                .orientation(Orientation::Horizontal)
+                .spacing(3)
+                .build();
+            let label = Label::builder()
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            row.append(&label);
+            labels.push(label);
+            let pause = Button::with_label(match timer.running {
+                true => "Pause",
+                false => "Resume",
+            });
+            pause.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.toggle_running(index);
+            }));
+            row.append(&pause);
+            let remove = Button::with_label("Remove");
+            remove.connect_clicked(clone!(@weak self as obj => move |_| {
+                obj.remove_timer(index);
+            }));
+            row.append(&remove);
+            timer_list.append(&row);
+        }
+        imp.labels.replace(labels);
+        self.update_labels();
+    }
+
+    /// Show the remaining time of every timer in the timer list
+    fn update_labels(&self) {
+        let imp = self.imp();
+        for (timer, label) in imp.timers.borrow().iter().zip(imp.labels.borrow().iter()) {
+            let text = match timer.is_expired() {
+                true => format!("{} (expired)", timer.text()),
+                false => timer.text(),
+            };
+            label.set_label(&text);
+        }
+    }
+
+    fn emit_changed(&self) {
+        let timers = Timers(self.imp().timers.borrow().clone());
+        self.emit_by_name::<()>("changed", &[&timers]);
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when a timer is added, removed, paused or counts down
+    pub fn connect_changed<F: Fn(&Self, Timers) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|panel, timers| {
+                f(panel, timers);
+            }),
+        )
+    }
+}
@@ -0,0 +1,147 @@
+use gtk::glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{glib, StringList};
+
+use crate::weather::{WeatherKind, WeatherSettings};
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use glib::subclass::InitializingObject;
+    use gtk::glib::subclass::Signal;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+    use gtk::{glib, template_callbacks, CompositeTemplate, DropDown, Scale};
+
+    use crate::weather::WeatherSettings;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/dragon/display/weather_controls.ui")]
+    pub struct DdWeatherControls {
+        #[template_child]
+        pub kind_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub intensity_scale: TemplateChild<Scale>,
+        #[template_child]
+        pub wind_scale: TemplateChild<Scale>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdWeatherControls {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "DdWeatherControls";
+        type Type = super::DdWeatherControls;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[template_callbacks]
+    impl DdWeatherControls {
+        #[template_callback]
+        fn handle_changed(&self, _: Scale) {
+            self.obj().emit_changed();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdWeatherControls {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("changed")
+                    .param_types([WeatherSettings::static_type()])
+                    .build()]
+            })
+        }
+
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            self.obj().setup();
+        }
+
+        fn dispose(&self) {
+            while let Some(child) = self.obj().first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdWeatherControls {}
+}
+
+glib::wrapper! {
+    pub struct DdWeatherControls(ObjectSubclass<imp::DdWeatherControls>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdWeatherControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdWeatherControls {
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    /// The weather as it is set in the controls
+    pub fn settings(&self) -> WeatherSettings {
+        let imp = self.imp();
+        WeatherSettings {
+            kind: WeatherKind::from_index(imp.kind_dropdown.selected()),
+            intensity: imp.intensity_scale.value() as f32,
+            wind: imp.wind_scale.value() as f32,
+        }
+    }
+
+    fn setup(&self) {
+        let kinds: Vec<&str> = WeatherKind::ALL.iter().map(|k| k.name()).collect();
+        let dropdown = &self.imp().kind_dropdown;
+        dropdown.set_model(Some(&StringList::new(&kinds)));
+        dropdown.connect_selected_notify(clone!(@weak self as obj => move |_| {
+            obj.emit_changed();
+        }));
+    }
+
+    fn emit_changed(&self) {
+        self.emit_by_name::<()>("changed", &[&self.settings()]);
+    }
+
+    /**
+     * ----------------------------------
+     *
+     * Signal connect functions
+     *
+     * --------------------------------
+     **/
+
+    /// Signal emitted when the weather, its intensity or the wind direction changed
+    pub fn connect_changed<F: Fn(&Self, WeatherSettings) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|controls, settings| {
+                f(controls, settings);
+            }),
+        )
+    }
+}
@@ -0,0 +1,125 @@
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use crate::weather::WeatherSettings;
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use gtk::glib;
+    use gtk::prelude::*;
+    use gtk::subclass::prelude::*;
+
+    use crate::weather::Weather;
+
+    /// Layer over the content of the display that draws the weather
+    #[derive(Default)]
+    pub struct DdWeatherLayer {
+        pub weather: RefCell<Weather>,
+        pub tick: RefCell<Option<gtk::TickCallbackId>>,
+        /// The frame time in microseconds of the last step of the weather
+        pub last_frame: Cell<Option<i64>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for DdWeatherLayer {
+        const NAME: &'static str = "DdWeatherLayer";
+        type Type = super::DdWeatherLayer;
+        type ParentType = gtk::Widget;
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for DdWeatherLayer {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+            self.obj().set_can_target(false);
+        }
+
+        fn dispose(&self) {
+            if let Some(tick) = self.tick.take() {
+                tick.remove();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for DdWeatherLayer {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            widget.draw(snapshot, widget.width() as f32, widget.height() as f32);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct DdWeatherLayer(ObjectSubclass<imp::DdWeatherLayer>)
+        @extends gtk::Widget,
+            @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl Default for DdWeatherLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdWeatherLayer {
+    pub fn new() -> Self {
+        glib::Object::new::<Self>()
+    }
+
+    /// Change the weather. The weather is animated on every frame of the display until the
+    /// weather is set to clear
+    pub fn set_weather(&self, settings: WeatherSettings) {
+        let imp = self.imp();
+        imp.weather.borrow_mut().set_settings(settings);
+        let active = imp.weather.borrow().is_active();
+        let running = imp.tick.borrow().is_some();
+        match (active, running) {
+            (true, false) => self.start(),
+            (false, true) => self.stop(),
+            _ => (),
+        }
+        self.queue_draw();
+    }
+
+    /// Draw the weather on a display of the given size, a display that is rendered offscreen
+    /// draws the weather itself instead of showing the layer
+    pub fn draw(&self, snapshot: &gtk::Snapshot, width: f32, height: f32) {
+        self.imp().weather.borrow().draw(snapshot, width, height);
+    }
+
+    /// Step the weather forward on every frame of the frame clock
+    fn start(&self) {
+        let tick = self.add_tick_callback(|layer, clock| {
+            layer.advance(clock.frame_time());
+            glib::ControlFlow::Continue
+        });
+        self.imp().tick.replace(Some(tick));
+    }
+
+    /// Step the active weather forward to the time in microseconds of the monotonic clock. The
+    /// frame clock does this on a shown display, a display that is rendered offscreen calls it
+    /// before a frame is drawn
+    pub fn advance(&self, now: i64) {
+        let imp = self.imp();
+        if imp.tick.borrow().is_none() {
+            return;
+        }
+        if let Some(last) = imp.last_frame.replace(Some(now)) {
+            let seconds = (now - last).max(0) as f32 / 1_000_000.0;
+            imp.weather.borrow_mut().step(seconds);
+        }
+        self.queue_draw();
+    }
+
+    fn stop(&self) {
+        if let Some(tick) = self.imp().tick.take() {
+            tick.remove();
+        }
+        self.imp().last_frame.set(None);
+    }
+}